@@ -0,0 +1,179 @@
+use sfsm::*;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::ptr;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+// An example of how the states of a state machine can await futures, like the transfers of a
+// peripheral driver. The state machine does not allocate and runs on any executor.
+
+/// A future that is pending once before it completes, like a bus transfer that finishes in the
+/// background
+pub struct Transfer {
+    pending: bool,
+}
+
+impl Future for Transfer {
+    type Output = u32;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.pending {
+            self.pending = false;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        } else {
+            Poll::Ready(21)
+        }
+    }
+}
+
+fn read_sensor() -> Transfer {
+    Transfer { pending: true }
+}
+
+/// A waker that does nothing, as the executor polls the future again right away
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    // The functions of the vtable do not use the data pointer, so a null pointer is fine
+    unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) }
+}
+
+/// A minimal executor that polls the future until it is ready
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = noop_waker();
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+pub struct Idle {}
+pub struct Measuring {}
+pub struct Sampling {
+    readings: u32,
+    sum: u32,
+}
+pub struct Averaging {
+    average: u32,
+}
+
+add_async_state_machine!(
+    Thermometer,
+    Idle,
+    [
+        Idle,
+        Measuring {
+            Sampling,
+            [Sampling, #[sfsm(final)] Averaging],
+            [Sampling => Averaging]
+        },
+    ],
+    [
+        Idle => Measuring,
+        Measuring => Idle after completion,
+    ]
+);
+
+derive_async_state!(Idle);
+derive_async_transition!(Idle, Measuring, TransitGuard::Transit);
+derive_transition_into!(Idle, Measuring);
+
+derive_async_state!(Measuring);
+impl CompositeState<Sampling> for Measuring {
+    fn initial_state(&mut self) -> Sampling {
+        Sampling { readings: 0, sum: 0 }
+    }
+}
+derive_async_transition!(Measuring, Idle, TransitGuard::Transit);
+derive_transition_into!(Measuring, Idle);
+
+// The functions of the states are async and can await the transfers
+impl AsyncState for Sampling {
+    async fn execute(&mut self) {
+        self.sum += read_sensor().await;
+        self.readings += 1;
+    }
+}
+impl AsyncTransition<Averaging> for Sampling {
+    async fn guard(&self) -> TransitGuard {
+        (self.readings == 2).into()
+    }
+}
+impl From<Sampling> for Averaging {
+    fn from(sampling: Sampling) -> Self {
+        Averaging {
+            average: sampling.sum / sampling.readings,
+        }
+    }
+}
+
+impl AsyncState for Averaging {
+    async fn entry(&mut self) {
+        println!("Average temperature: {}", self.average);
+    }
+}
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+async fn measure() -> Result<(), SfsmError> {
+    let mut thermometer = Thermometer::new();
+    // Every call into the state machine is awaited
+    thermometer.start(Idle {}).await?;
+
+    thermometer.step().await?;
+    assert!(IsState::<Sampling>::is_state(&thermometer));
+
+    thermometer.step().await?;
+    assert!(IsState::<Sampling>::is_state(&thermometer));
+
+    let outcome = thermometer.step_report().await?;
+    assert_eq!(
+        outcome,
+        StepOutcome::Transitioned {
+            from: ThermometerStateId::Sampling,
+            to: ThermometerStateId::Averaging
+        }
+    );
+    assert!(IsState::<Measuring>::is_state(&thermometer));
+
+    // Once the averaging is done, the measurement is completed
+    thermometer.step().await?;
+    assert!(IsState::<Idle>::is_state(&thermometer));
+
+    let stopped = thermometer.stop().await?;
+    assert!(matches!(stopped, ThermometerStates::IdleState(Some(_))));
+
+    Ok(())
+}
+
+fn run_async_states_example() -> Result<(), SfsmError> {
+    block_on(measure())
+}
+
+fn main() {
+    run_async_states_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_async_states_example;
+
+    #[test]
+    fn async_states_example() {
+        run_async_states_example().unwrap();
+    }
+}
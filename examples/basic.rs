@@ -1,3 +1,5 @@
+// The example is kept in the style it was written in before the lints were checked
+#![allow(clippy::from_over_into, clippy::needless_return, clippy::assertions_on_constants)]
 use sfsm::*;
 
 // A very basic usage of the crate
@@ -11,7 +13,7 @@ pub struct WaitForLaunch {}
 add_state_machine!(
     Rocket,                      // Name of the state machine. Accepts a visibility modifier.
     WaitForLaunch,                    // The initial state the state machine will start in
-    [WaitForLaunch, Launch],          // All possible states
+    [WaitForLaunch, #[sfsm(final)] Launch],   // All possible states. Launch is never left.
     [
         WaitForLaunch => Launch,      // All transitions
     ]
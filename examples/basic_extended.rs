@@ -1,3 +1,5 @@
+// The example is kept in the style it was written in before the lints were checked
+#![allow(clippy::from_over_into, clippy::assertions_on_constants)]
 use sfsm::*;
 use std::marker::PhantomData;
 
@@ -16,7 +18,7 @@ add_state_machine!(
     #[derive(Debug)]                                            // Attributes for the generated struct can be defined
     pub Rocket,                                                 // Name of the state machine. Accepts a visibility modifier.
     Action<WaitForLaunch>,                                      // The state machine will start at the count Descent
-    [Action<WaitForLaunch>, Action<Ascent>, Action<Descent>],   // All possible states
+    [Action<WaitForLaunch>, Action<Ascent>, #[sfsm(final)] Action<Descent>],   // All possible states
     [
         Action<WaitForLaunch> => Action<Ascent>,
         Action<Ascent> => Action<Descent>
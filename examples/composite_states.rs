@@ -0,0 +1,215 @@
+use sfsm::*;
+
+// An example of how a hierarchical state machine can be declared with composite states directly
+// inside the state machine definition
+
+// The context records the order in which the states are entered and exited
+pub struct Journal {
+    entries: Vec<&'static str>,
+}
+
+pub struct Offline {}
+pub struct Online {
+    remaining_cycles: u32,
+}
+pub struct Standby {}
+pub struct Requesting {}
+pub struct Reporting {}
+
+// An event that takes the observer offline no matter which sub-state is active
+pub struct Disconnect {}
+
+// The types generated for the sub-states have a reserved prefix, so a type named after the state
+// machine and its composite state does not clash with them
+pub struct ForwardObserverOnline {
+    entries: usize,
+}
+
+add_state_machine!(
+    #[sfsm(context = Journal)]
+    ForwardObserver,
+    Offline,
+    [
+        Offline,
+        Online {                            // A composite state containing its own sub-states
+            Standby,                        // The initial sub-state
+            [Standby, Requesting, Reporting],
+            [
+                Standby => Requesting,
+                Requesting => Reporting,
+                Reporting => Standby,
+            ]
+        },
+    ],
+    [
+        Offline => Online,
+        Online => Offline,                  // Applies to whichever sub-state is active
+        Online => Offline on Disconnect,
+    ]
+);
+
+impl ContextState<Journal> for Offline {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Offline");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Offline");
+    }
+}
+impl ContextTransition<Online, Journal> for Offline {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+impl From<Offline> for Online {
+    fn from(_: Offline) -> Self {
+        Online {
+            remaining_cycles: 3,
+        }
+    }
+}
+
+impl ContextState<Journal> for Online {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Online");
+    }
+    // The composite state is executed before its active sub-state
+    fn execute(&mut self, _journal: &mut Journal) {
+        self.remaining_cycles -= 1;
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Online");
+    }
+}
+// The initial sub-state is created every time the composite state is entered
+impl CompositeState<Standby> for Online {
+    fn initial_state(&mut self) -> Standby {
+        Standby {}
+    }
+}
+impl ContextTransition<Offline, Journal> for Online {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        (self.remaining_cycles == 0).into()
+    }
+}
+impl ContextEventTransition<Offline, Disconnect, Journal> for Online {}
+derive_transition_into!(Online, Offline);
+
+impl ContextState<Journal> for Standby {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Standby");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Standby");
+    }
+}
+impl ContextTransition<Requesting, Journal> for Standby {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Standby, Requesting);
+
+impl ContextState<Journal> for Requesting {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Requesting");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Requesting");
+    }
+}
+impl ContextTransition<Reporting, Journal> for Requesting {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Requesting, Reporting);
+
+impl ContextState<Journal> for Reporting {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Reporting");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Reporting");
+    }
+}
+impl ContextTransition<Standby, Journal> for Reporting {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Reporting, Standby);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_composite_states_example() -> Result<(), SfsmError> {
+    let mut forward_observer = ForwardObserver::new(Journal { entries: vec![] });
+    forward_observer.start(Offline {})?;
+    assert!(IsState::<Offline>::is_state(&forward_observer));
+
+    // Entering the composite state enters its initial sub-state right after it
+    forward_observer.step()?;
+    assert!(IsState::<Online>::is_state(&forward_observer));
+    assert!(IsState::<Standby>::is_state(&forward_observer));
+    assert_eq!(
+        forward_observer.context().entries,
+        vec!["Enter Offline", "Exit Offline", "Enter Online", "Enter Standby"]
+    );
+
+    // As long as the composite state remains, its sub-states are stepped
+    forward_observer.step()?;
+    assert!(IsState::<Online>::is_state(&forward_observer));
+    assert!(IsState::<Requesting>::is_state(&forward_observer));
+
+    forward_observer.step()?;
+    assert!(IsState::<Reporting>::is_state(&forward_observer));
+
+    // The transition of the composite state first exits the active sub-state
+    forward_observer.context_mut().entries.clear();
+    forward_observer.step()?;
+    assert!(IsState::<Offline>::is_state(&forward_observer));
+    assert!(!IsState::<Online>::is_state(&forward_observer));
+    assert!(!IsState::<Reporting>::is_state(&forward_observer));
+    assert_eq!(
+        forward_observer.context().entries,
+        vec!["Exit Reporting", "Exit Online", "Enter Offline"]
+    );
+
+    // Events are handled by the composite state as well, whichever sub-state is active
+    forward_observer.step()?;
+    assert!(IsState::<Standby>::is_state(&forward_observer));
+    forward_observer.context_mut().entries.clear();
+    forward_observer.handle(Disconnect {}.into())?;
+    assert!(IsState::<Offline>::is_state(&forward_observer));
+    assert_eq!(
+        forward_observer.context().entries,
+        vec!["Exit Standby", "Exit Online", "Enter Offline"]
+    );
+    let summary = ForwardObserverOnline {
+        entries: forward_observer.context().entries.len(),
+    };
+    assert_eq!(summary.entries, 3);
+
+    Ok(())
+}
+
+fn main() {
+    run_composite_states_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_composite_states_example;
+
+    #[test]
+    fn composite_states_example() {
+        run_composite_states_example().unwrap();
+    }
+}
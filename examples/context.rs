@@ -0,0 +1,129 @@
+use sfsm::*;
+
+// An example of how data can be shared between all states with a context
+
+// The context is owned by the state machine and handed to every state, guard and action
+pub struct LaunchPad {
+    fuel: u32,
+    countdown: u32,
+    launches: u32,
+}
+
+// The states themselves don't have to carry the shared data anymore
+pub struct Refuel {}
+pub struct WaitForLaunch {}
+pub struct Launch {}
+
+add_state_machine!(
+    #[sfsm(context = LaunchPad)]        // The type of the context
+    Rocket,
+    Refuel,
+    [Refuel, WaitForLaunch, Launch],
+    [
+        Refuel => WaitForLaunch,
+        WaitForLaunch => Launch,
+        Launch => Refuel,
+    ]
+);
+
+// States of a state machine with a context implement the ContextState trait
+impl ContextState<LaunchPad> for Refuel {
+    fn execute(&mut self, context: &mut LaunchPad) {
+        context.fuel += 50;
+    }
+}
+// And the transitions the ContextTransition trait. The guard has read access to the context.
+impl ContextTransition<WaitForLaunch, LaunchPad> for Refuel {
+    fn guard(&self, context: &LaunchPad) -> TransitGuard {
+        (context.fuel >= 100).into()
+    }
+}
+derive_transition_into!(Refuel, WaitForLaunch);
+
+impl ContextState<LaunchPad> for WaitForLaunch {
+    fn entry(&mut self, context: &mut LaunchPad) {
+        context.countdown = 2;
+    }
+    fn execute(&mut self, context: &mut LaunchPad) {
+        context.countdown -= 1;
+    }
+}
+impl ContextTransition<Launch, LaunchPad> for WaitForLaunch {
+    // The action has write access to the context
+    fn action(&mut self, context: &mut LaunchPad) {
+        context.fuel = 0;
+    }
+    fn guard(&self, context: &LaunchPad) -> TransitGuard {
+        (context.countdown == 0).into()
+    }
+}
+derive_transition_into!(WaitForLaunch, Launch);
+
+impl ContextState<LaunchPad> for Launch {
+    fn entry(&mut self, context: &mut LaunchPad) {
+        context.launches += 1;
+    }
+}
+impl ContextTransition<Refuel, LaunchPad> for Launch {
+    fn guard(&self, _context: &LaunchPad) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Launch, Refuel);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_context_example() -> Result<(), SfsmError> {
+    // The context is moved into the state machine when it is created
+    let mut rocket = Rocket::new(LaunchPad {
+        fuel: 0,
+        countdown: 0,
+        launches: 0,
+    });
+    rocket.start(Refuel {})?;
+
+    rocket.step()?;
+    assert!(IsState::<Refuel>::is_state(&rocket));
+    assert_eq!(rocket.context().fuel, 50);
+
+    rocket.step()?;
+    assert!(IsState::<WaitForLaunch>::is_state(&rocket));
+    assert_eq!(rocket.context().countdown, 2);
+
+    rocket.step()?;
+    assert!(IsState::<WaitForLaunch>::is_state(&rocket));
+
+    rocket.step()?;
+    assert!(IsState::<Launch>::is_state(&rocket));
+    assert_eq!(rocket.context().fuel, 0);
+    assert_eq!(rocket.context().launches, 1);
+
+    // The context can also be modified from the outside
+    rocket.context_mut().fuel = 100;
+    rocket.step()?;
+    rocket.step()?;
+    assert!(IsState::<WaitForLaunch>::is_state(&rocket));
+
+    Ok(())
+}
+
+fn main() {
+    run_context_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_context_example;
+
+    #[test]
+    fn context_example() {
+        run_context_example().unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+use sfsm::*;
+
+// An example of how a state machine can be defined in a TOML or YAML file. In a crate, the
+// definition is compiled by the build script with:
+//
+//     sfsm_build::compile("machines/rocket.toml").unwrap();
+//
+// and the generated code is included with:
+//
+//     include!(concat!(env!("OUT_DIR"), "/rocket.rs"));
+//
+// Examples cannot have their own build script, so this example includes a generated copy that
+// is checked against the definitions in the test.
+
+pub struct WaitForLaunch {
+    countdown: u32,
+}
+pub struct Launch {
+    altitude: u32,
+}
+pub struct Landed {}
+pub struct Abort {}
+
+// The event
+pub struct Ignite {}
+
+// The messages
+#[derive(Debug)]
+pub struct Countdown {
+    seconds: u32,
+}
+pub struct Altitude {
+    meters: u32,
+}
+
+#[derive(Debug)]
+pub enum RocketError {
+    Aborted,
+}
+
+include!("machines/rocket.rs");
+
+impl TryState for WaitForLaunch {
+    type Error = RocketError;
+}
+impl TryEventTransition<Launch, Ignite> for WaitForLaunch {
+    fn guard(&self, _: &Ignite) -> TransitGuard {
+        (self.countdown == 0).into()
+    }
+}
+impl From<WaitForLaunch> for Launch {
+    fn from(_: WaitForLaunch) -> Self {
+        Launch { altitude: 0 }
+    }
+}
+impl ReceiveMessage<Countdown> for WaitForLaunch {
+    fn receive_message(&mut self, message: Countdown) {
+        self.countdown = message.seconds;
+    }
+}
+
+impl TryState for Launch {
+    type Error = RocketError;
+    fn try_execute(&mut self) -> Result<(), Self::Error> {
+        self.altitude += 1000;
+        Ok(())
+    }
+}
+impl TryTransition<Landed> for Launch {
+    fn guard(&self) -> TransitGuard {
+        (self.altitude >= 3000).into()
+    }
+}
+derive_transition_into!(Launch, Landed);
+impl ReturnMessage<Altitude> for Launch {
+    fn return_message(&mut self) -> Option<Altitude> {
+        Some(Altitude {
+            meters: self.altitude,
+        })
+    }
+}
+
+impl TryState for Landed {
+    type Error = RocketError;
+}
+
+impl TryState for Abort {
+    type Error = RocketError;
+}
+impl TryErrorState for Abort {
+    fn consume_error(&mut self, _: Self::Error) {}
+}
+impl TryTransition<WaitForLaunch> for Abort {
+    fn guard(&self) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+impl From<Abort> for WaitForLaunch {
+    fn from(_: Abort) -> Self {
+        WaitForLaunch { countdown: 10 }
+    }
+}
+derive_transition_into!(WaitForLaunch, Abort);
+derive_transition_into!(Launch, Abort);
+derive_transition_into!(Landed, Abort);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_definition_files_example() -> Result<(), ExtendedSfsmError<RocketError>> {
+    let mut rocket = Rocket::new();
+    rocket.start(WaitForLaunch { countdown: 10 })?;
+
+    // The rocket only launches once the countdown is over
+    rocket.handle(Ignite {}.into())?;
+    assert!(IsState::<WaitForLaunch>::is_state(&rocket));
+
+    PushMessage::<WaitForLaunch, Countdown>::push_message(&mut rocket, Countdown { seconds: 0 })
+        .unwrap();
+    rocket.handle(Ignite {}.into())?;
+    assert!(IsState::<Launch>::is_state(&rocket));
+
+    rocket.step()?;
+    let altitude = PollMessage::<Launch, Altitude>::poll_message(&mut rocket).unwrap();
+    assert_eq!(altitude.map(|altitude| altitude.meters), Some(1000));
+
+    rocket.step()?;
+    rocket.step()?;
+    rocket.step()?;
+    assert!(IsState::<Landed>::is_state(&rocket));
+    assert!(rocket.is_finished());
+
+    Ok(())
+}
+
+fn main() {
+    run_definition_files_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_definition_files_example;
+
+    #[test]
+    fn definition_files_example() {
+        run_definition_files_example().unwrap();
+    }
+
+    #[test]
+    fn definition_files_are_generated() {
+        // Both formats generate the code that is included above
+        let generated = include_str!("machines/rocket.rs");
+        let toml = sfsm_build::generate_file("examples/machines/rocket.toml").unwrap();
+        let yaml = sfsm_build::generate_file("examples/machines/rocket.yaml").unwrap();
+        assert_eq!(toml, generated);
+        assert_eq!(yaml, generated);
+
+        // Errors point to the position in the definition file
+        let definition = "name = \"Rocket\"\ninitial = \"Nope\"\nstates = [\"Launch\"]\n";
+        let err = sfsm_build::generate("rocket.toml", definition).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "rocket.toml:2:11: The initial state 'Nope' is not in the list of states"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use sfsm::*;
+
+// An example of how the definition of a state machine can be exported as a Graphviz diagram to
+// keep the documentation in sync with the code
+
+#[derive(Debug)]
+pub enum PrintError {
+    PaperJam,
+}
+
+pub struct Idle {}
+pub struct Printing {}
+pub struct Feeding {}
+pub struct Inking {}
+pub struct Jammed {}
+
+// Messages that are exchanged with the states
+pub struct Job {}
+pub struct Progress {}
+
+add_fallible_state_machine!(
+    Printer,
+    Idle,
+    [
+        Idle,
+        Printing {
+            Feeding,
+            [Feeding, #[sfsm(final)] Inking],
+            [Feeding => Inking]
+        },
+        Jammed,
+    ],
+    [
+        Idle => Printing,
+        Printing => Idle after completion,
+        Jammed => Idle,
+    ],
+    PrintError,
+    Jammed
+);
+
+add_messages!(
+    Printer,
+    [
+        Job -> Idle,
+        Progress <- Printing,
+    ]
+);
+
+impl TryState for Idle {
+    type Error = PrintError;
+}
+impl TryTransition<Printing> for Idle {
+    fn guard(&self) -> TransitGuard {
+        TransitGuard::Remain
+    }
+}
+derive_transition_into!(Idle, Printing);
+impl ReceiveMessage<Job> for Idle {
+    fn receive_message(&mut self, _: Job) {}
+}
+
+impl TryState for Printing {
+    type Error = PrintError;
+}
+impl CompositeState<Feeding> for Printing {
+    fn initial_state(&mut self) -> Feeding {
+        Feeding {}
+    }
+}
+impl TryTransition<Idle> for Printing {
+    fn guard(&self) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Printing, Idle);
+derive_transition_into!(Printing, Jammed);
+impl ReturnMessage<Progress> for Printing {
+    fn return_message(&mut self) -> Option<Progress> {
+        None
+    }
+}
+
+impl TryState for Feeding {
+    type Error = PrintError;
+}
+impl TryTransition<Inking> for Feeding {
+    fn guard(&self) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Feeding, Inking);
+impl TryState for Inking {
+    type Error = PrintError;
+}
+
+impl TryState for Jammed {
+    type Error = PrintError;
+}
+impl TryErrorState for Jammed {
+    fn consume_error(&mut self, _: Self::Error) {}
+}
+impl TryTransition<Idle> for Jammed {
+    fn guard(&self) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Jammed, Idle);
+derive_transition_into!(Idle, Jammed);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_dot_export_example() -> Result<(), core::fmt::Error> {
+    // The graph can be rendered with: dot -Tsvg printer.dot -o printer.svg
+    let dot = Printer::DOT;
+    println!("{}", dot);
+
+    assert!(dot.starts_with("digraph Printer {"));
+    assert!(dot.contains("\"__start\" -> \"Idle\";"));
+    assert!(dot.contains("\"Jammed\" [color = red];"));
+    assert!(dot.contains("\"Inking\" [peripheries = 2];"));
+    assert!(dot.contains(
+        "\"Printing\" -> \"Idle\" [label = \"after completion\", ltail = \"cluster Printing\"];"
+    ));
+
+    // The messages are only known once they are added to the state machine
+    let mut dot_with_messages = String::new();
+    Printer::write_dot_with_messages(&mut dot_with_messages)?;
+    println!("{}", dot_with_messages);
+
+    assert!(dot_with_messages.contains("\"message Job\" -> \"Idle\" [style = dashed];"));
+    assert!(dot_with_messages.contains("\"Printing\" -> \"message Progress\" [style = dashed];"));
+    assert!(dot_with_messages.ends_with("}\n"));
+
+    Ok(())
+}
+
+fn main() {
+    run_dot_export_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_dot_export_example;
+
+    #[test]
+    fn dot_export_example() {
+        run_dot_export_example().unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+use sfsm::*;
+
+// An example of how transitions can be bound to events instead of being polled in every step
+
+// The states
+pub struct WaitForLaunch {
+    countdown: u32,
+}
+pub struct Launch {
+    thrust: u32,
+}
+pub struct Abort {}
+
+// The events that can be handled by the state machine. Events can carry a payload.
+pub struct Ignite {
+    thrust: u32,
+}
+pub struct Cancel {}
+
+add_state_machine!(
+    Rocket,                                 // Name of the state machine. Accepts a visibility modifier.
+    WaitForLaunch,                          // The initial state the state machine will start in
+    [WaitForLaunch, Launch, Abort],         // All possible states
+    [
+        WaitForLaunch => Launch on Ignite,  // Only evaluated when an Ignite event is handled
+        WaitForLaunch => Abort on Cancel,   // Only evaluated when a Cancel event is handled
+        Launch => Abort on Cancel,
+        Abort => WaitForLaunch,             // Transitions without an event are polled during step
+    ]
+);
+
+impl State for WaitForLaunch {
+    fn execute(&mut self) {
+        if self.countdown > 0 {
+            self.countdown -= 1;
+        }
+    }
+}
+
+// Event transitions receive the event in the guard and the action.
+// Here the rocket only ignites once the countdown has finished and the thrust is sufficient.
+impl EventTransition<Launch, Ignite> for WaitForLaunch {
+    fn guard(&self, event: &Ignite) -> TransitGuard {
+        (self.countdown == 0 && event.thrust > 100).into()
+    }
+}
+impl From<WaitForLaunch> for Launch {
+    fn from(_: WaitForLaunch) -> Self {
+        Launch { thrust: 0 }
+    }
+}
+
+// The guard defaults to always transit once the event arrives
+impl EventTransition<Abort, Cancel> for WaitForLaunch {}
+derive_transition_into!(WaitForLaunch, Abort);
+
+impl State for Launch {
+    fn entry(&mut self) {
+        self.thrust = 150;
+    }
+}
+impl EventTransition<Abort, Cancel> for Launch {
+    fn action(&mut self, _event: &Cancel) {
+        println!("Cut the thrust of {}", self.thrust);
+        self.thrust = 0;
+    }
+}
+derive_transition_into!(Launch, Abort);
+
+derive_state!(Abort);
+derive_transition!(Abort, WaitForLaunch, TransitGuard::Transit);
+impl From<Abort> for WaitForLaunch {
+    fn from(_: Abort) -> Self {
+        WaitForLaunch { countdown: 1 }
+    }
+}
+
+// A state machine whose states carry a log of the actions that have been run so far
+pub struct Parked {
+    log: Vec<&'static str>,
+}
+pub struct Driving {
+    log: Vec<&'static str>,
+}
+pub struct Towed {}
+
+// The event
+pub struct Start {}
+
+add_state_machine!(
+    Car,
+    Parked,
+    [Parked, #[sfsm(final)] Driving, #[sfsm(final)] Towed],
+    [
+        Parked => Driving on Start,
+        Parked => Towed,                    // Its action does not run when the car is started
+    ]
+);
+
+derive_state!(Parked);
+impl EventTransition<Driving, Start> for Parked {
+    fn action(&mut self, _event: &Start) {
+        self.log.push("Start engine");
+    }
+}
+impl From<Parked> for Driving {
+    fn from(parked: Parked) -> Self {
+        Driving { log: parked.log }
+    }
+}
+impl Transition<Towed> for Parked {
+    fn action(&mut self) {
+        self.log.push("Release brake");
+    }
+    fn guard(&self) -> TransitGuard {
+        TransitGuard::Remain
+    }
+}
+derive_transition_into!(Parked, Towed);
+
+derive_state!(Driving);
+derive_state!(Towed);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_events_example() -> Result<(), SfsmError> {
+    let mut rocket = Rocket::new();
+    rocket.start(WaitForLaunch { countdown: 1 })?;
+
+    // Stepping never evaluates the event transitions
+    rocket.step()?;
+    assert!(IsState::<WaitForLaunch>::is_state(&rocket));
+
+    // The guard sees the payload of the event and rejects the ignition due to the low thrust
+    rocket.handle(Ignite { thrust: 50 }.into())?;
+    assert!(IsState::<WaitForLaunch>::is_state(&rocket));
+
+    // Events can also be constructed with the generated enum directly
+    rocket.handle(RocketEvents::IgniteEvent(Ignite { thrust: 120 }))?;
+    assert!(IsState::<Launch>::is_state(&rocket));
+
+    // Events for which the active state has no transition are dropped
+    rocket.handle(Ignite { thrust: 120 }.into())?;
+    assert!(IsState::<Launch>::is_state(&rocket));
+
+    rocket.handle(Cancel {}.into())?;
+    assert!(IsState::<Abort>::is_state(&rocket));
+
+    // Polled transitions still work as usual
+    rocket.step()?;
+    assert!(IsState::<WaitForLaunch>::is_state(&rocket));
+
+    Ok(())
+}
+
+fn run_event_action_example() -> Result<(), SfsmError> {
+    let mut car = Car::new();
+    car.start(Parked { log: vec![] })?;
+
+    // Only the action of the event transition is run, but not the ones of the polled transitions
+    car.handle(Start {}.into())?;
+    assert_eq!(
+        car.get::<Driving>().map(|driving| driving.log.clone()),
+        Some(vec!["Start engine"])
+    );
+
+    Ok(())
+}
+
+fn main() {
+    run_events_example().unwrap();
+    run_event_action_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_event_action_example, run_events_example};
+
+    #[test]
+    fn events_example() {
+        run_events_example().unwrap();
+    }
+
+    #[test]
+    fn event_action_example() {
+        run_event_action_example().unwrap();
+    }
+}
@@ -0,0 +1,130 @@
+use sfsm::*;
+
+// An example of how the actions can be restricted to the transition that is actually taken
+
+// Every state carries a log of the actions that have been run so far
+pub struct WaitForLaunch {
+    malfunction: bool,
+    log: Vec<&'static str>,
+}
+pub struct Launch {
+    log: Vec<&'static str>,
+}
+#[derive(Debug)]
+pub struct Abort {
+    log: Vec<&'static str>,
+}
+
+add_state_machine!(
+    #[sfsm(exclusive_actions)]          // Only run the action of the transition that is taken
+    Rocket,
+    WaitForLaunch,
+    [WaitForLaunch, #[sfsm(final)] Launch, #[sfsm(final)] Abort],
+    [
+        WaitForLaunch => Launch,
+        WaitForLaunch => Abort,
+    ]
+);
+
+derive_state!(WaitForLaunch);
+derive_state!(Launch);
+
+impl Transition<Launch> for WaitForLaunch {
+    fn action(&mut self) {
+        self.log.push("Ignite boosters");
+    }
+    fn guard(&self) -> TransitGuard {
+        (!self.malfunction).into()
+    }
+}
+impl From<WaitForLaunch> for Launch {
+    fn from(state: WaitForLaunch) -> Self {
+        Launch { log: state.log }
+    }
+}
+
+impl Transition<Abort> for WaitForLaunch {
+    fn action(&mut self) {
+        self.log.push("Disarm boosters");
+    }
+    fn guard(&self) -> TransitGuard {
+        self.malfunction.into()
+    }
+}
+impl From<WaitForLaunch> for Abort {
+    fn from(state: WaitForLaunch) -> Self {
+        Abort { log: state.log }
+    }
+}
+
+// The stop hook is called whenever the state machine is stopped while the state is active
+impl State for Abort {
+    fn on_stop(&mut self) {
+        self.log.push("Stopped");
+    }
+}
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_exclusive_actions_example() -> Result<(), SfsmError> {
+    let mut rocket = Rocket::new();
+    rocket.start(WaitForLaunch {
+        malfunction: true,
+        log: vec![],
+    })?;
+
+    rocket.step()?;
+    assert!(IsState::<Abort>::is_state(&rocket));
+
+    // Only the action of the transition to Abort has been run, followed by the stop hook
+    match rocket.stop()? {
+        match_state_entry!(Rocket, Abort, exit_state) => {
+            let exit_state = exit_state.unwrap();
+            assert_eq!(exit_state.log, vec!["Disarm boosters", "Stopped"]);
+        }
+        _ => {
+            panic!("Expected to stop in the Abort state");
+        }
+    }
+
+    // Without a malfunction, only the action of the transition to Launch is run
+    let mut rocket = Rocket::new();
+    rocket.start(WaitForLaunch {
+        malfunction: false,
+        log: vec![],
+    })?;
+
+    rocket.step()?;
+    match rocket.stop()? {
+        match_state_entry!(Rocket, Launch, exit_state) => {
+            let exit_state = exit_state.unwrap();
+            assert_eq!(exit_state.log, vec!["Ignite boosters"]);
+        }
+        _ => {
+            panic!("Expected to stop in the Launch state");
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    run_exclusive_actions_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_exclusive_actions_example;
+
+    #[test]
+    fn exclusive_actions_example() {
+        run_exclusive_actions_example().unwrap();
+    }
+}
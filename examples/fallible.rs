@@ -1,3 +1,5 @@
+// The example is kept in the style it was written in before the lints were checked
+#![allow(clippy::from_over_into, clippy::new_without_default)]
 use sfsm::*;
 
 // The states
@@ -117,7 +119,7 @@ impl TryErrorState for HandleMalfunction {
 add_fallible_state_machine!(
     Rocket,                                 // Name of the state machine. Accepts a visibility modifier.
     WaitForLaunch,                               // The initial state the state machine will start in
-    [WaitForLaunch, Launch, HandleMalfunction],  // All possible states
+    [WaitForLaunch, #[sfsm(final)] Launch, HandleMalfunction],  // All possible states
     [
         WaitForLaunch => Launch,                 // All possible Transitions
         HandleMalfunction => WaitForLaunch
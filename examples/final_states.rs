@@ -0,0 +1,134 @@
+use sfsm::*;
+
+// An example of how final states end a state machine or trigger a completion transition of the
+// composite state they belong to
+
+pub struct Idle {}
+pub struct Transfer {}
+// The states of the transfer
+pub struct Connecting {}
+pub struct Receiving {
+    chunks: u32,
+}
+pub struct Received {}
+// The states after the transfer
+pub struct Verifying {}
+pub struct Done {}
+
+add_state_machine!(
+    Download,
+    Idle,
+    [
+        Idle,
+        Transfer {
+            Connecting,
+            [
+                Connecting,
+                Receiving,
+                #[sfsm(final)]                  // Ends the transfer
+                Received,
+            ],
+            [
+                Connecting => Receiving,
+                Receiving => Received,
+            ]
+        },
+        Verifying,
+        #[sfsm(final)]                          // Ends the whole download
+        Done,
+    ],
+    [
+        Idle => Transfer,
+        Transfer => Verifying after completion, // Taken once all regions of the transfer are finished
+        Verifying => Done,
+    ]
+);
+
+derive_state!(Idle);
+derive_transition!(Idle, Transfer, TransitGuard::Transit);
+derive_transition_into!(Idle, Transfer);
+
+derive_state!(Transfer);
+impl CompositeState<Connecting> for Transfer {
+    fn initial_state(&mut self) -> Connecting {
+        Connecting {}
+    }
+}
+// A completion transition is only taken if its guard allows it as well
+derive_transition!(Transfer, Verifying, TransitGuard::Transit);
+derive_transition_into!(Transfer, Verifying);
+
+derive_state!(Connecting);
+derive_transition!(Connecting, Receiving, TransitGuard::Transit);
+impl From<Connecting> for Receiving {
+    fn from(_: Connecting) -> Self {
+        Receiving { chunks: 0 }
+    }
+}
+
+impl State for Receiving {
+    fn execute(&mut self) {
+        self.chunks += 1;
+    }
+}
+impl Transition<Received> for Receiving {
+    fn guard(&self) -> TransitGuard {
+        (self.chunks == 2).into()
+    }
+}
+derive_transition_into!(Receiving, Received);
+
+derive_state!(Received);
+
+derive_state!(Verifying);
+derive_transition!(Verifying, Done, TransitGuard::Transit);
+derive_transition_into!(Verifying, Done);
+
+derive_state!(Done);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_final_states_example() -> Result<(), SfsmError> {
+    let mut download = Download::new();
+    download.start(Idle {})?;
+
+    download.step()?;
+    download.step()?;
+    assert!(IsState::<Receiving>::is_state(&download));
+
+    download.step()?;
+    download.step()?;
+    assert!(IsState::<Received>::is_state(&download));
+    assert!(!download.is_finished());
+
+    // The completion transition is taken on the next step after the transfer finished
+    download.step()?;
+    assert!(IsState::<Verifying>::is_state(&download));
+
+    download.step()?;
+    assert!(IsState::<Done>::is_state(&download));
+    assert!(download.is_finished());
+
+    Ok(())
+}
+
+fn main() {
+    run_final_states_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_final_states_example;
+
+    #[test]
+    fn final_states_example() {
+        run_final_states_example().unwrap();
+    }
+}
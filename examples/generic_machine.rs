@@ -0,0 +1,228 @@
+use sfsm::*;
+
+// An example of how a state machine can be generic over the sensor it reads and borrow the
+// peripheral it writes to
+
+pub trait Sensor {
+    fn read(&mut self) -> u32;
+}
+
+pub struct Thermistor {
+    temperature: u32,
+}
+
+impl Sensor for Thermistor {
+    fn read(&mut self) -> u32 {
+        self.temperature += 1;
+        self.temperature
+    }
+}
+
+// A peripheral that is borrowed by the states instead of being owned by them
+pub struct Uart<'a> {
+    sent: &'a mut Vec<u32>,
+}
+
+pub struct Measure<'a, T> {
+    sensor: T,
+    uart: Uart<'a>,
+    offset: u32,
+    value: Option<u32>,
+}
+pub struct Transmit<'a, T> {
+    sensor: T,
+    uart: Uart<'a>,
+    value: u32,
+}
+// The sub-states do not have to use the generic parameters of the state machine
+pub struct Framing {}
+pub struct Sending {}
+
+// A message that calibrates the measurement
+#[derive(Debug)]
+pub struct Calibrate {
+    offset: u32,
+}
+
+add_state_machine!(
+    Logger<'a, T>                                   // Generic parameters and lifetimes
+    where T: Sensor,                                // and a where clause follow the name
+    Measure<'a, T>,
+    [
+        Measure<'a, T>,
+        Transmit<'a, T> {
+            Framing,
+            [Framing, #[sfsm(final)] Sending],
+            [Framing => Sending]
+        },
+    ],
+    [
+        Measure<'a, T> => Transmit<'a, T>,
+        Transmit<'a, T> => Measure<'a, T> after completion,
+    ]
+);
+
+// The messages repeat the generic parameters of the state machine together with their bounds
+add_messages!(
+    Logger<'a, T: Sensor>,
+    [
+        Calibrate -> Measure<'a, T>,
+    ]
+);
+
+impl<T: Sensor> State for Measure<'_, T> {
+    fn execute(&mut self) {
+        self.value = Some(self.sensor.read() + self.offset);
+    }
+}
+impl<'a, T: Sensor> Transition<Transmit<'a, T>> for Measure<'a, T> {
+    fn guard(&self) -> TransitGuard {
+        self.value.is_some().into()
+    }
+}
+impl<'a, T> From<Measure<'a, T>> for Transmit<'a, T> {
+    fn from(measure: Measure<'a, T>) -> Self {
+        Transmit {
+            sensor: measure.sensor,
+            uart: measure.uart,
+            value: measure.value.unwrap_or_default(),
+        }
+    }
+}
+impl<T> ReceiveMessage<Calibrate> for Measure<'_, T> {
+    fn receive_message(&mut self, message: Calibrate) {
+        self.offset = message.offset;
+    }
+}
+
+impl<T> State for Transmit<'_, T> {
+    fn entry(&mut self) {
+        self.uart.sent.push(self.value);
+    }
+}
+impl<T> CompositeState<Framing> for Transmit<'_, T> {
+    fn initial_state(&mut self) -> Framing {
+        Framing {}
+    }
+}
+impl<'a, T> Transition<Measure<'a, T>> for Transmit<'a, T> {
+    fn guard(&self) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+impl<'a, T> From<Transmit<'a, T>> for Measure<'a, T> {
+    fn from(transmit: Transmit<'a, T>) -> Self {
+        Measure {
+            sensor: transmit.sensor,
+            uart: transmit.uart,
+            offset: 0,
+            value: None,
+        }
+    }
+}
+
+derive_state!(Framing);
+derive_transition!(Framing, Sending, TransitGuard::Transit);
+derive_transition_into!(Framing, Sending);
+derive_state!(Sending);
+
+impl Default for Thermistor {
+    fn default() -> Self {
+        Thermistor { temperature: 20 }
+    }
+}
+
+// The bounds can also be given inline with the generic parameters and be combined with a where
+// clause. The states are shared with the logger, but Transmit is not a composite state here.
+add_state_machine!(
+    Sampler<'a, T: Sensor + Default> where Transmit<'a, T>: State,
+    Measure<'a, T>,
+    [Measure<'a, T>, #[sfsm(final)] Transmit<'a, T>],
+    [
+        Measure<'a, T> => Transmit<'a, T>,
+    ]
+);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_generic_machine_example() -> Result<(), SfsmError> {
+    let mut sent = vec![];
+    {
+        let mut logger = Logger::new();
+        logger.start(Measure {
+            sensor: Thermistor { temperature: 20 },
+            uart: Uart { sent: &mut sent },
+            offset: 0,
+            value: None,
+        })?;
+
+        PushMessage::<Measure<Thermistor>, Calibrate>::push_message(
+            &mut logger,
+            Calibrate { offset: 100 },
+        )
+        .unwrap();
+
+        logger.step()?;
+        assert!(IsState::<Transmit<Thermistor>>::is_state(&logger));
+        assert!(IsState::<Framing>::is_state(&logger));
+
+        logger.step()?;
+        assert!(IsState::<Sending>::is_state(&logger));
+
+        logger.step()?;
+        assert!(IsState::<Measure<Thermistor>>::is_state(&logger));
+        logger.step()?;
+    }
+
+    // The borrow of the peripheral ends with the state machine
+    assert_eq!(sent, vec![121, 22]);
+
+    Ok(())
+}
+
+fn run_inline_bounds_example() -> Result<(), SfsmError> {
+    let mut sent = vec![];
+    {
+        let mut sampler = Sampler::new();
+        sampler.start(Measure {
+            sensor: Thermistor::default(),
+            uart: Uart { sent: &mut sent },
+            offset: 0,
+            value: None,
+        })?;
+
+        sampler.step()?;
+        assert!(IsState::<Transmit<Thermistor>>::is_state(&sampler));
+        assert!(sampler.is_finished());
+    }
+    assert_eq!(sent, vec![21]);
+
+    Ok(())
+}
+
+fn main() {
+    run_generic_machine_example().unwrap();
+    run_inline_bounds_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_generic_machine_example, run_inline_bounds_example};
+
+    #[test]
+    fn generic_machine_example() {
+        run_generic_machine_example().unwrap();
+    }
+
+    #[test]
+    fn inline_bounds_example() {
+        run_inline_bounds_example().unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+use sfsm::*;
+
+// An example of how the checks of the state machine graph can be allowed while a state machine
+// is still being built up
+
+pub struct Idle {}
+pub struct Heating {}
+pub struct Cooling {}
+// The maintenance mode is declared, but not yet wired into the state machine
+pub struct Maintenance {}
+
+add_state_machine!(
+    #[sfsm(allow(unreachable, dead_end))]     // Without it, Maintenance would be reported twice
+    Thermostat,
+    Idle,
+    [Idle, Heating, Cooling, Maintenance],
+    [
+        Idle => Heating,
+        Heating => Cooling,
+        Cooling => Idle,
+    ]
+);
+
+derive_state!(Idle);
+derive_transition!(Idle, Heating, TransitGuard::Transit);
+derive_transition_into!(Idle, Heating);
+
+derive_state!(Heating);
+derive_transition!(Heating, Cooling, TransitGuard::Transit);
+derive_transition_into!(Heating, Cooling);
+
+derive_state!(Cooling);
+derive_transition!(Cooling, Idle, TransitGuard::Transit);
+derive_transition_into!(Cooling, Idle);
+
+derive_state!(Maintenance);
+
+// The error state is neither checked for reachability nor for outgoing transitions, so a fallible
+// state machine can remain in it until it is reset
+pub struct Sampling {}
+pub struct Sending {
+    connected: bool,
+}
+pub struct Failed {
+    error: Option<LinkError>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LinkError {
+    Disconnected,
+}
+
+add_fallible_state_machine!(
+    Sensor,
+    Sampling,
+    [Sampling, Sending, Failed],
+    [
+        Sampling => Sending,
+        Sending => Sampling,
+    ],
+    LinkError,
+    Failed
+);
+
+impl TryState for Sampling {
+    type Error = LinkError;
+}
+derive_try_transition!(Sampling, Sending, TransitGuard::Transit);
+impl From<Sampling> for Sending {
+    fn from(_: Sampling) -> Self {
+        Sending { connected: false }
+    }
+}
+
+impl TryState for Sending {
+    type Error = LinkError;
+    fn try_execute(&mut self) -> Result<(), Self::Error> {
+        if !self.connected {
+            return Err(LinkError::Disconnected);
+        }
+        Ok(())
+    }
+}
+derive_try_transition!(Sending, Sampling, TransitGuard::Transit);
+derive_transition_into!(Sending, Sampling);
+
+impl TryState for Failed {
+    type Error = LinkError;
+}
+impl TryErrorState for Failed {
+    fn consume_error(&mut self, error: Self::Error) {
+        self.error = Some(error);
+    }
+}
+impl From<Sampling> for Failed {
+    fn from(_: Sampling) -> Self {
+        Failed { error: None }
+    }
+}
+impl From<Sending> for Failed {
+    fn from(_: Sending) -> Self {
+        Failed { error: None }
+    }
+}
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_graph_lints_example() -> Result<(), SfsmError> {
+    let mut thermostat = Thermostat::new();
+    thermostat.start(Idle {})?;
+
+    thermostat.step()?;
+    assert!(IsState::<Heating>::is_state(&thermostat));
+
+    thermostat.step()?;
+    thermostat.step()?;
+    assert!(IsState::<Idle>::is_state(&thermostat));
+    assert!(!IsState::<Maintenance>::is_state(&thermostat));
+
+    Ok(())
+}
+
+fn run_error_state_lints_example() -> Result<(), ExtendedSfsmError<LinkError>> {
+    let mut sensor = Sensor::new();
+    sensor.start(Sampling {})?;
+    sensor.step()?;
+    sensor.step()?;
+    assert_eq!(
+        sensor.get::<Failed>().and_then(|failed| failed.error.as_ref()),
+        Some(&LinkError::Disconnected)
+    );
+
+    // The state machine remains in the error state until it is reset
+    sensor.step()?;
+    assert!(IsState::<Failed>::is_state(&sensor));
+    sensor.reset(Sampling {})?;
+    assert!(IsState::<Sampling>::is_state(&sensor));
+
+    Ok(())
+}
+
+fn main() {
+    run_graph_lints_example().unwrap();
+    run_error_state_lints_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_error_state_lints_example, run_graph_lints_example};
+
+    #[test]
+    fn graph_lints_example() {
+        run_graph_lints_example().unwrap();
+    }
+
+    #[test]
+    fn error_state_lints_example() {
+        run_error_state_lints_example().unwrap();
+    }
+}
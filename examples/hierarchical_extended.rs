@@ -25,7 +25,7 @@ add_state_machine!(
 add_state_machine!(
     OnlineMachine,
     Standby,
-    [Standby, Requesting, Observing, Reporting],
+    [Standby, Requesting, Observing, #[sfsm(final)] Reporting],    // Ends the inner state machine
     [
         Standby => Requesting,
         Requesting => Observing,
@@ -40,14 +40,12 @@ derive_state!(Observing);
 derive_state!(Reporting);
 derive_transition!(Standby, Requesting, TransitGuard::Transit);
 derive_transition!(Requesting, Observing, TransitGuard::Transit);
-derive_transition!(Reporting, Standby, TransitGuard::Transit);
 derive_transition!(Observing, Reporting, TransitGuard::Transit);
 derive_transition!(Offline, Online, TransitGuard::Transit);
 derive_transition_into!(Online, Offline);
 derive_transition_into!(Standby, Requesting);
 derive_transition_into!(Requesting, Observing);
 derive_transition_into!(Observing, Reporting);
-derive_transition_into!(Reporting, Standby);
 
 impl State for Online {
     /// Executes the sub-state machine on each step.
@@ -1,3 +1,5 @@
+// The example is kept in the style it was written in before the lints were checked
+#![allow(clippy::from_over_into)]
 use sfsm::*;
 
 // An example of how a hierarchical state machine can be built
@@ -0,0 +1,187 @@
+use sfsm::*;
+
+// An example of how composite states can resume their last active sub-states when they are
+// entered again
+
+pub struct Off {}
+// The states of the menu
+pub struct Menu {}
+pub struct Main {}
+pub struct Settings {}
+pub struct Audio {}
+pub struct Video {}
+// The states of the game
+pub struct Game {}
+pub struct Lobby {}
+pub struct Match {}
+pub struct Warmup {}
+pub struct Round {}
+
+// The events
+pub struct Open {}
+pub struct Start {}
+pub struct Close {}
+
+add_state_machine!(
+    Console,
+    Off,
+    [
+        Off,
+        #[sfsm(history = shallow)]          // Only resumes the last active sub-state of the menu
+        Menu {
+            Main,
+            [
+                Main,
+                Settings {
+                    Audio,
+                    [Audio, Video],
+                    [Audio => Video]
+                },
+            ],
+            [Main => Settings]
+        },
+        #[sfsm(history = deep)]             // Resumes the last active sub-states on all levels
+        Game {
+            Lobby,
+            [
+                Lobby,
+                Match {
+                    Warmup,
+                    [Warmup, Round],
+                    [Warmup => Round]
+                },
+            ],
+            [Lobby => Match]
+        },
+    ],
+    [
+        Off => Menu on Open,
+        Off => Game on Start,
+        Menu => Off on Close,
+        Game => Off on Close,
+    ]
+);
+
+derive_state!(Off);
+impl EventTransition<Menu, Open> for Off {}
+impl From<Off> for Menu {
+    fn from(_: Off) -> Self {
+        Menu {}
+    }
+}
+impl EventTransition<Game, Start> for Off {}
+impl From<Off> for Game {
+    fn from(_: Off) -> Self {
+        Game {}
+    }
+}
+
+derive_state!(Menu);
+impl CompositeState<Main> for Menu {
+    fn initial_state(&mut self) -> Main {
+        Main {}
+    }
+}
+impl EventTransition<Off, Close> for Menu {}
+derive_transition_into!(Menu, Off);
+
+derive_state!(Main);
+derive_transition!(Main, Settings, TransitGuard::Transit);
+derive_transition_into!(Main, Settings);
+
+derive_state!(Settings);
+impl CompositeState<Audio> for Settings {
+    fn initial_state(&mut self) -> Audio {
+        Audio {}
+    }
+}
+
+derive_state!(Audio);
+derive_transition!(Audio, Video, TransitGuard::Transit);
+derive_transition_into!(Audio, Video);
+derive_state!(Video);
+
+derive_state!(Game);
+impl CompositeState<Lobby> for Game {
+    fn initial_state(&mut self) -> Lobby {
+        Lobby {}
+    }
+}
+impl EventTransition<Off, Close> for Game {}
+derive_transition_into!(Game, Off);
+
+derive_state!(Lobby);
+derive_transition!(Lobby, Match, TransitGuard::Transit);
+derive_transition_into!(Lobby, Match);
+
+derive_state!(Match);
+impl CompositeState<Warmup> for Match {
+    fn initial_state(&mut self) -> Warmup {
+        Warmup {}
+    }
+}
+
+derive_state!(Warmup);
+derive_transition!(Warmup, Round, TransitGuard::Transit);
+derive_transition_into!(Warmup, Round);
+derive_state!(Round);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_history_example() -> Result<(), SfsmError> {
+    let mut console = Console::new();
+    console.start(Off {})?;
+
+    // Navigate to the video settings
+    console.handle(Open {}.into())?;
+    console.step()?;
+    console.step()?;
+    assert!(IsState::<Settings>::is_state(&console));
+    assert!(IsState::<Video>::is_state(&console));
+
+    // The shallow history of the menu resumes the settings, but their sub-states start over
+    console.handle(Close {}.into())?;
+    console.handle(Open {}.into())?;
+    assert!(IsState::<Settings>::is_state(&console));
+    assert!(IsState::<Audio>::is_state(&console));
+
+    // Start a match and get to its first round
+    console.handle(Close {}.into())?;
+    console.handle(Start {}.into())?;
+    assert!(IsState::<Lobby>::is_state(&console));
+    console.step()?;
+    console.step()?;
+    assert!(IsState::<Match>::is_state(&console));
+    assert!(IsState::<Round>::is_state(&console));
+
+    // The deep history of the game resumes the match as well as the round
+    console.handle(Close {}.into())?;
+    assert!(IsState::<Off>::is_state(&console));
+    assert!(!IsState::<Round>::is_state(&console));
+    console.handle(Start {}.into())?;
+    assert!(IsState::<Match>::is_state(&console));
+    assert!(IsState::<Round>::is_state(&console));
+
+    Ok(())
+}
+
+fn main() {
+    run_history_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_history_example;
+
+    #[test]
+    fn history_example() {
+        run_history_example().unwrap();
+    }
+}
@@ -0,0 +1,27 @@
+// Generated by sfsm-build. Do not edit.
+
+sfsm::add_fallible_state_machine!(
+    Rocket,
+    WaitForLaunch,
+    [
+        WaitForLaunch,
+        Launch,
+        #[sfsm(final)] Landed,
+        Abort,
+    ],
+    [
+        WaitForLaunch => Launch on Ignite,
+        Launch => Landed,
+        Abort => WaitForLaunch,
+    ],
+    RocketError,
+    Abort
+);
+
+sfsm::add_messages!(
+    Rocket,
+    [
+        Countdown -> WaitForLaunch,
+        Altitude <- Launch,
+    ]
+);
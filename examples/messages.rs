@@ -1,3 +1,5 @@
+// The example is kept in the style it was written in before the lints were checked
+#![allow(clippy::from_over_into, clippy::needless_return, clippy::assertions_on_constants, clippy::collapsible_match)]
 use sfsm::*;
 use sfsm::message::{MessageError, ReturnMessage, ReceiveMessage};
 
@@ -11,7 +13,7 @@ struct Abort {}
 add_state_machine!(
     Rocket,
     WaitForLaunch,
-    [WaitForLaunch, Launch, Abort],
+    [WaitForLaunch, #[sfsm(final)] Launch, Abort],
     [
         WaitForLaunch => Launch,
         WaitForLaunch => Abort,
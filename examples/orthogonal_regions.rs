@@ -0,0 +1,212 @@
+use sfsm::*;
+
+// An example of how independent concerns can be modeled with orthogonal regions that are active
+// at the same time
+
+// The context records the order in which the states are entered and exited
+pub struct Journal {
+    entries: Vec<&'static str>,
+}
+
+pub struct Off {}
+pub struct Operating {}
+// The states of the radio link region
+pub struct LinkDown {
+    attempts: u32,
+}
+pub struct LinkUp {}
+// The states of the power management region
+pub struct Awake {}
+pub struct Asleep {}
+
+// The events
+pub struct Wake {}
+pub struct PowerOff {}
+
+add_state_machine!(
+    #[sfsm(context = Journal)]
+    Device,
+    Off,
+    [
+        Off,
+        Operating {
+            LinkDown,                           // The first region handles the radio link
+            [LinkDown, LinkUp],
+            [
+                LinkDown => LinkUp,
+            ]
+            ||                                  // Regions are separated with ||
+            Awake,                              // The second region handles the power management
+            [Awake, Asleep],
+            [
+                Awake => Asleep,
+                Asleep => Awake on Wake,
+            ]
+        },
+    ],
+    [
+        Off => Operating,
+        Operating => Off on PowerOff,
+    ]
+);
+
+impl ContextState<Journal> for Off {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Off");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Off");
+    }
+}
+impl ContextTransition<Operating, Journal> for Off {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Off, Operating);
+
+impl ContextState<Journal> for Operating {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Operating");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Operating");
+    }
+}
+// Every region needs its own initial state
+impl CompositeState<LinkDown> for Operating {
+    fn initial_state(&mut self) -> LinkDown {
+        LinkDown { attempts: 0 }
+    }
+}
+impl CompositeState<Awake> for Operating {
+    fn initial_state(&mut self) -> Awake {
+        Awake {}
+    }
+}
+impl ContextEventTransition<Off, PowerOff, Journal> for Operating {}
+derive_transition_into!(Operating, Off);
+
+impl ContextState<Journal> for LinkDown {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter LinkDown");
+    }
+    fn execute(&mut self, _journal: &mut Journal) {
+        self.attempts += 1;
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit LinkDown");
+    }
+}
+impl ContextTransition<LinkUp, Journal> for LinkDown {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        (self.attempts >= 2).into()
+    }
+}
+derive_transition_into!(LinkDown, LinkUp);
+
+impl ContextState<Journal> for LinkUp {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter LinkUp");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit LinkUp");
+    }
+}
+
+impl ContextState<Journal> for Awake {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Awake");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Awake");
+    }
+}
+impl ContextTransition<Asleep, Journal> for Awake {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Awake, Asleep);
+
+impl ContextState<Journal> for Asleep {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Asleep");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Asleep");
+    }
+}
+impl ContextEventTransition<Awake, Wake, Journal> for Asleep {}
+derive_transition_into!(Asleep, Awake);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_orthogonal_regions_example() -> Result<(), SfsmError> {
+    let mut device = Device::new(Journal { entries: vec![] });
+    device.start(Off {})?;
+
+    // The regions are entered in the order of their definition after the composite state
+    device.step()?;
+    assert!(IsState::<Operating>::is_state(&device));
+    assert!(IsState::<LinkDown>::is_state(&device));
+    assert!(IsState::<Awake>::is_state(&device));
+    assert_eq!(
+        device.context().entries,
+        vec![
+            "Enter Off",
+            "Exit Off",
+            "Enter Operating",
+            "Enter LinkDown",
+            "Enter Awake"
+        ]
+    );
+
+    // Every step steps all regions independently of each other
+    device.step()?;
+    assert!(IsState::<LinkDown>::is_state(&device));
+    assert!(IsState::<Asleep>::is_state(&device));
+
+    device.step()?;
+    assert!(IsState::<LinkUp>::is_state(&device));
+    assert!(IsState::<Asleep>::is_state(&device));
+
+    // Events are passed on to all regions
+    device.handle(Wake {}.into())?;
+    assert!(IsState::<LinkUp>::is_state(&device));
+    assert!(IsState::<Awake>::is_state(&device));
+
+    // The regions are exited in the reverse order of their definition before the composite state
+    device.context_mut().entries.clear();
+    device.handle(PowerOff {}.into())?;
+    assert!(IsState::<Off>::is_state(&device));
+    assert!(!IsState::<LinkUp>::is_state(&device));
+    assert!(!IsState::<Awake>::is_state(&device));
+    assert_eq!(
+        device.context().entries,
+        vec!["Exit Awake", "Exit LinkUp", "Exit Operating", "Enter Off"]
+    );
+
+    Ok(())
+}
+
+fn main() {
+    run_orthogonal_regions_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_orthogonal_regions_example;
+
+    #[test]
+    fn orthogonal_regions_example() {
+        run_orthogonal_regions_example().unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+use sfsm::*;
+
+// An example of how the state of a state machine can be persisted in a compact binary format,
+// like in the flash of a microcontroller to resume after a reset. The states encode their own
+// payload while the state machine adds the state id, the format version and a CRC.
+
+pub struct Idle {}
+pub struct Heating {
+    target: u16,
+    power: u8,
+}
+
+add_state_machine!(
+    #[sfsm(persist(version = 2))]
+    Thermostat,
+    Idle,
+    [Idle, Heating],
+    [
+        Idle => Heating,
+        Heating => Idle,
+    ]
+);
+
+derive_state!(Idle);
+derive_transition!(Idle, Heating, TransitGuard::Transit);
+impl From<Idle> for Heating {
+    fn from(_: Idle) -> Self {
+        Heating {
+            target: 215,
+            power: 0,
+        }
+    }
+}
+
+impl State for Heating {
+    fn entry(&mut self) {
+        self.power = 100;
+    }
+    fn execute(&mut self) {
+        self.power = self.power.saturating_sub(10);
+    }
+}
+impl Transition<Idle> for Heating {
+    fn guard(&self) -> TransitGuard {
+        (self.power == 0).into()
+    }
+}
+derive_transition_into!(Heating, Idle);
+
+// Idle has no payload, so only the decoding has to be implemented
+impl Persist for Idle {
+    fn decode(_: &[u8], _: u8) -> Result<Self, PersistError> {
+        Ok(Idle {})
+    }
+}
+
+impl Persist for Heating {
+    fn encode(&self, payload: &mut [u8]) -> Result<usize, PersistError> {
+        let [low, high] = self.target.to_le_bytes();
+        payload
+            .get_mut(..3)
+            .ok_or(PersistError::BufferTooSmall)?
+            .copy_from_slice(&[low, high, self.power]);
+        Ok(3)
+    }
+
+    fn decode(payload: &[u8], version: u8) -> Result<Self, PersistError> {
+        match (version, payload) {
+            // The first version only stored the target in tenths of degrees above 10°C
+            (1, [target]) => Ok(Heating {
+                target: 100 + u16::from(*target),
+                power: 100,
+            }),
+            (2, [low, high, power]) => Ok(Heating {
+                target: u16::from_le_bytes([*low, *high]),
+                power: *power,
+            }),
+            _ => Err(PersistError::InvalidPayload),
+        }
+    }
+}
+
+// A defroster that rests for a while after defrosting. Its timeout has to survive a reset.
+pub struct Defrosting {}
+pub struct Resting {}
+
+add_state_machine!(
+    #[sfsm(persist(version = 1), clock = MockClock)]
+    Defroster,
+    Defrosting,
+    [Defrosting, Resting],
+    [
+        Defrosting => Resting,
+        Resting => Defrosting after 5s,
+    ]
+);
+
+derive_state!(Defrosting);
+derive_transition!(Defrosting, Resting, TransitGuard::Transit);
+derive_transition_into!(Defrosting, Resting);
+
+derive_state!(Resting);
+derive_transition!(Resting, Defrosting, TransitGuard::Transit);
+derive_transition_into!(Resting, Defrosting);
+
+impl Persist for Defrosting {
+    fn decode(_: &[u8], _: u8) -> Result<Self, PersistError> {
+        Ok(Defrosting {})
+    }
+}
+impl Persist for Resting {
+    fn decode(_: &[u8], _: u8) -> Result<Self, PersistError> {
+        Ok(Resting {})
+    }
+}
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_persistence_example() -> Result<(), PersistError> {
+    // A page of the flash
+    let mut flash = [0xFFu8; 32];
+
+    // A state machine that has not been started has nothing to persist
+    let thermostat = Thermostat::new();
+    assert_eq!(thermostat.encode_into(&mut flash), Err(PersistError::NotStarted));
+
+    let mut thermostat = Thermostat::new();
+    thermostat.start(Idle {}).unwrap();
+    thermostat.step().unwrap();
+    thermostat.step().unwrap();
+    assert_eq!(thermostat.get::<Heating>().map(|heating| heating.power), Some(90));
+
+    // The version, the state id, the payload length, the payload and the CRC
+    let len = thermostat.encode_into(&mut flash)?;
+    assert_eq!(len, 9);
+    assert_eq!(flash[..6], [2, ThermostatStateId::Heating as u8, 3, 0, 215, 0]);
+
+    // After the reset, the state machine is decoded without entering the state again
+    let mut thermostat = Thermostat::decode_from(&flash)?;
+    assert_eq!(thermostat.get::<Heating>().map(|heating| heating.power), Some(90));
+    thermostat.step().unwrap();
+    assert_eq!(thermostat.get::<Heating>().map(|heating| heating.power), Some(80));
+
+    // Encodings of the previous version are migrated by the state
+    let mut old_flash = [0xFFu8; 32];
+    encode_frame(&mut old_flash, 1, ThermostatStateId::Heating as u8, |payload| {
+        payload[0] = 120;
+        Ok(1)
+    })?;
+    let thermostat = Thermostat::decode_from(&old_flash)?;
+    assert_eq!(thermostat.get::<Heating>().map(|heating| heating.target), Some(220));
+
+    // Erased or corrupted flash and encodings of a newer firmware are rejected
+    assert!(matches!(
+        Thermostat::decode_from(&[0xFFu8; 32]),
+        Err(PersistError::InvalidChecksum)
+    ));
+    flash[4] ^= 1;
+    assert!(matches!(
+        Thermostat::decode_from(&flash),
+        Err(PersistError::InvalidChecksum)
+    ));
+    let mut new_flash = [0xFFu8; 32];
+    encode_frame(&mut new_flash, 3, ThermostatStateId::Idle as u8, |_| Ok(0))?;
+    assert!(matches!(
+        Thermostat::decode_from(&new_flash),
+        Err(PersistError::UnsupportedVersion(3))
+    ));
+
+    Ok(())
+}
+
+fn run_decoded_timeout_example() -> Result<(), PersistError> {
+    let mut flash = [0xFFu8; 32];
+    let mut defroster = Defroster::new(MockClock::new());
+    defroster.start(Defrosting {}).unwrap();
+    defroster.step().unwrap();
+    assert!(IsState::<Resting>::is_state(&defroster));
+    defroster.encode_into(&mut flash)?;
+
+    // After the reset, the clock has kept running. The timeout of the decoded state starts
+    // again when it is decoded instead of being expired already.
+    let mut clock = MockClock::new();
+    clock.set(60_000);
+    let mut defroster = Defroster::decode_from(&flash, clock)?;
+    defroster.step().unwrap();
+    assert!(IsState::<Resting>::is_state(&defroster));
+
+    defroster.clock_mut().advance(5000);
+    defroster.step().unwrap();
+    assert!(IsState::<Defrosting>::is_state(&defroster));
+
+    Ok(())
+}
+
+fn main() {
+    run_persistence_example().unwrap();
+    run_decoded_timeout_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_decoded_timeout_example, run_persistence_example};
+
+    #[test]
+    fn persistence_example() {
+        run_persistence_example().unwrap();
+    }
+
+    #[test]
+    fn decoded_timeout_example() {
+        run_decoded_timeout_example().unwrap();
+    }
+}
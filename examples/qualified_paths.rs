@@ -0,0 +1,226 @@
+use sfsm::*;
+
+// An example of how states, messages and error types can be referred to by their full paths
+// instead of importing them into the scope of the state machine
+
+pub mod errors {
+    #[derive(Debug)]
+    pub enum Malfunction {
+        EngineFailure,
+    }
+}
+
+pub mod ground {
+    use crate::errors::Malfunction;
+    use sfsm::*;
+
+    pub struct Checkout {
+        pub checks: u32,
+    }
+    // A state with the same name as one of the flight states
+    pub struct Abort {}
+
+    // A message that requests another checkout
+    pub struct Recheck {}
+
+    impl TryState for Checkout {
+        type Error = Malfunction;
+        fn try_execute(&mut self) -> Result<(), Self::Error> {
+            self.checks += 1;
+            Ok(())
+        }
+    }
+    impl TryTransition<crate::flight::Ascent> for Checkout {
+        fn guard(&self) -> TransitGuard {
+            (self.checks >= 2).into()
+        }
+    }
+    impl From<Checkout> for crate::flight::Ascent {
+        fn from(_: Checkout) -> Self {
+            crate::flight::Ascent { altitude: 0 }
+        }
+    }
+    impl ReceiveMessage<Recheck> for Checkout {
+        fn receive_message(&mut self, _: Recheck) {
+            self.checks = 0;
+        }
+    }
+    impl From<Checkout> for Abort {
+        fn from(_: Checkout) -> Self {
+            Abort {}
+        }
+    }
+
+    impl TryState for Abort {
+        type Error = Malfunction;
+    }
+    impl TryErrorState for Abort {
+        fn consume_error(&mut self, err: Self::Error) {
+            println!("Aborted on the ground: {:?}", err);
+        }
+    }
+}
+
+pub mod flight {
+    use crate::errors::Malfunction;
+    use sfsm::*;
+
+    pub struct Ascent {
+        pub altitude: u32,
+    }
+    pub struct Abort {}
+
+    impl TryState for Ascent {
+        type Error = Malfunction;
+        fn try_execute(&mut self) -> Result<(), Self::Error> {
+            self.altitude += 1000;
+            if self.altitude > 1000 {
+                return Err(Malfunction::EngineFailure);
+            }
+            Ok(())
+        }
+    }
+    impl TryTransition<Abort> for Ascent {
+        fn guard(&self) -> TransitGuard {
+            TransitGuard::Remain
+        }
+    }
+    impl From<Ascent> for Abort {
+        fn from(_: Ascent) -> Self {
+            Abort {}
+        }
+    }
+    impl From<Ascent> for crate::ground::Abort {
+        fn from(_: Ascent) -> Self {
+            crate::ground::Abort {}
+        }
+    }
+
+    impl TryState for Abort {
+        type Error = Malfunction;
+    }
+    impl From<Abort> for crate::ground::Abort {
+        fn from(_: Abort) -> Self {
+            crate::ground::Abort {}
+        }
+    }
+}
+
+add_fallible_state_machine!(
+    Rocket,
+    crate::ground::Checkout,                    // A state can be given with a longer or shorter
+    [ground::Checkout, flight::Ascent, #[sfsm(final)] flight::Abort, #[sfsm(final)] ground::Abort],
+    [
+        crate::ground::Checkout => flight::Ascent,  // path than it is declared with
+        flight::Ascent => crate::flight::Abort,
+    ],
+    errors::Malfunction,
+    crate::ground::Abort
+);
+
+add_messages!(Rocket, [ground::Recheck -> ground::Checkout]);
+
+// Two modules whose paths only differ in how they are split into segments
+pub mod launch_pad {
+    pub struct Ready {}
+}
+pub mod launch {
+    pub mod pad {
+        pub struct Ready {}
+    }
+}
+
+add_state_machine!(
+    Countdown,
+    launch_pad::Ready,
+    [launch_pad::Ready, #[sfsm(final)] launch::pad::Ready],
+    [
+        launch_pad::Ready => launch::pad::Ready,
+    ]
+);
+
+derive_state!(launch_pad::Ready);
+derive_state!(launch::pad::Ready);
+derive_transition!(launch_pad::Ready, launch::pad::Ready, TransitGuard::Transit);
+derive_transition_into!(launch_pad::Ready, launch::pad::Ready);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_qualified_paths_example() -> Result<(), ExtendedSfsmError<errors::Malfunction>> {
+    let mut rocket = Rocket::new();
+    rocket.start(ground::Checkout { checks: 0 })?;
+
+    rocket.step()?;
+    PushMessage::<ground::Checkout, ground::Recheck>::push_message(
+        &mut rocket,
+        ground::Recheck {},
+    )
+    .unwrap_or_else(|_| panic!("Expected to be in the checkout"));
+    rocket.step()?;
+    assert!(IsState::<ground::Checkout>::is_state(&rocket));
+
+    rocket.step()?;
+    assert!(IsState::<flight::Ascent>::is_state(&rocket));
+
+    // The equally named states are told apart by their paths. The generated names use the path
+    // the states are declared with.
+    rocket.step()?;
+    rocket.step()?;
+    assert!(IsState::<ground::Abort>::is_state(&rocket));
+    assert!(!IsState::<flight::Abort>::is_state(&rocket));
+    match rocket.peek_state() {
+        RocketStates::Ground__AbortState(_) => {}
+        _ => panic!("Expected to abort on the ground"),
+    }
+
+    Ok(())
+}
+
+fn run_segmented_paths_example() -> Result<(), SfsmError> {
+    let mut countdown = Countdown::new();
+    countdown.start(launch_pad::Ready {})?;
+    assert_eq!(countdown.state_id(), Some(CountdownStateId::LaunchPad__Ready));
+
+    // The segments of the paths are separated in the generated names, so they do not collide
+    countdown.step()?;
+    assert_eq!(countdown.state_id(), Some(CountdownStateId::Launch__Pad__Ready));
+    match countdown.peek_state() {
+        CountdownStates::Launch__Pad__ReadyState(_) => {}
+        _ => panic!("Expected to be ready on the pad"),
+    }
+
+    // The exported SCXML uses the generated names as ids and keeps the paths in comments
+    assert!(Countdown::SCXML.contains("initial=\"LaunchPad__Ready\""));
+    assert!(Countdown::SCXML.contains("<!-- launch::pad::Ready -->"));
+    assert!(Countdown::SCXML.contains("<final id=\"Launch__Pad__Ready\"/>"));
+    assert!(Countdown::SCXML.contains("<transition target=\"Launch__Pad__Ready\"/>"));
+
+    Ok(())
+}
+
+fn main() {
+    run_qualified_paths_example().unwrap();
+    run_segmented_paths_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_qualified_paths_example, run_segmented_paths_example};
+
+    #[test]
+    fn qualified_paths_example() {
+        run_qualified_paths_example().unwrap();
+    }
+
+    #[test]
+    fn segmented_paths_example() {
+        run_segmented_paths_example().unwrap();
+    }
+}
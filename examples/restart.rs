@@ -0,0 +1,363 @@
+use sfsm::*;
+
+// An example of how a state machine can be stopped and started again, or reset into a new
+// initial state without creating a new state machine, and what happens if stopping fails
+
+pub struct Filling {
+    liters: u32,
+}
+pub struct Washing {
+    minutes: u32,
+}
+pub struct Draining {}
+
+add_state_machine!(
+    Washer,
+    Filling,
+    [Filling, Washing, #[sfsm(final)] Draining],
+    [
+        Filling => Washing,
+        Washing => Draining,
+    ]
+);
+
+impl State for Filling {
+    fn execute(&mut self) {
+        self.liters += 5;
+    }
+}
+impl Transition<Washing> for Filling {
+    fn guard(&self) -> TransitGuard {
+        (self.liters >= 10).into()
+    }
+}
+impl From<Filling> for Washing {
+    fn from(_: Filling) -> Self {
+        Washing { minutes: 0 }
+    }
+}
+
+impl State for Washing {
+    fn execute(&mut self) {
+        self.minutes += 15;
+    }
+    fn on_stop(&mut self) {
+        println!("Washing interrupted after {} minutes", self.minutes);
+    }
+}
+impl Transition<Draining> for Washing {
+    fn guard(&self) -> TransitGuard {
+        (self.minutes >= 45).into()
+    }
+}
+derive_transition_into!(Washing, Draining);
+
+derive_state!(Draining);
+
+// The context records the hooks and actions in the order they run
+pub struct Journal {
+    entries: Vec<&'static str>,
+}
+
+pub struct Loading {}
+pub struct Cleaning {}
+pub struct Spraying {}
+pub struct Rinsing {}
+
+add_state_machine!(
+    #[sfsm(context = Journal)]
+    Dishwasher,
+    Loading,
+    [
+        Loading,
+        Cleaning {
+            Spraying,
+            [Spraying, Rinsing],
+            [
+                Spraying => Rinsing,
+                Rinsing => Spraying,
+            ]
+        },
+    ],
+    [
+        Loading => Cleaning,
+        Cleaning => Loading,
+    ]
+);
+
+impl ContextState<Journal> for Loading {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Loading");
+    }
+}
+impl ContextTransition<Cleaning, Journal> for Loading {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Loading, Cleaning);
+
+impl ContextState<Journal> for Cleaning {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Cleaning");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Cleaning");
+    }
+    fn on_stop(&mut self, journal: &mut Journal) {
+        journal.entries.push("Stop Cleaning");
+    }
+}
+impl CompositeState<Spraying> for Cleaning {
+    fn initial_state(&mut self) -> Spraying {
+        Spraying {}
+    }
+}
+impl ContextTransition<Loading, Journal> for Cleaning {
+    fn action(&mut self, journal: &mut Journal) {
+        journal.entries.push("Cleaning to Loading");
+    }
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Remain
+    }
+}
+derive_transition_into!(Cleaning, Loading);
+
+impl ContextState<Journal> for Spraying {
+    fn entry(&mut self, journal: &mut Journal) {
+        journal.entries.push("Enter Spraying");
+    }
+    fn exit(&mut self, journal: &mut Journal) {
+        journal.entries.push("Exit Spraying");
+    }
+    fn on_stop(&mut self, journal: &mut Journal) {
+        journal.entries.push("Stop Spraying");
+    }
+}
+impl ContextTransition<Rinsing, Journal> for Spraying {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Remain
+    }
+}
+derive_transition_into!(Spraying, Rinsing);
+
+impl ContextState<Journal> for Rinsing {}
+impl ContextTransition<Spraying, Journal> for Rinsing {
+    fn guard(&self, _journal: &Journal) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+derive_transition_into!(Rinsing, Spraying);
+
+// A fallible state machine that cannot be stopped while its drum is still spinning
+pub struct Idle {}
+pub struct Spinning {
+    rpm: u32,
+}
+// The error state
+pub struct Stalled {
+    error: Option<DryerError>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DryerError {
+    DoorLocked,
+}
+
+add_fallible_state_machine!(
+    Dryer,
+    Idle,
+    [Idle, Spinning, Stalled],
+    [
+        Idle => Spinning,
+        Spinning => Idle,
+        Stalled => Idle,
+    ],
+    DryerError,
+    Stalled
+);
+
+impl TryState for Idle {
+    type Error = DryerError;
+}
+derive_try_transition!(Idle, Spinning, TransitGuard::Transit);
+impl From<Idle> for Spinning {
+    fn from(_: Idle) -> Self {
+        Spinning { rpm: 800 }
+    }
+}
+
+impl TryState for Spinning {
+    type Error = DryerError;
+    fn try_execute(&mut self) -> Result<(), Self::Error> {
+        self.rpm = self.rpm.saturating_sub(400);
+        Ok(())
+    }
+    // The door stays locked until the drum stopped
+    fn try_exit(&mut self) -> Result<(), Self::Error> {
+        if self.rpm > 0 {
+            return Err(DryerError::DoorLocked);
+        }
+        Ok(())
+    }
+}
+impl TryTransition<Idle> for Spinning {
+    fn guard(&self) -> TransitGuard {
+        (self.rpm == 0).into()
+    }
+}
+derive_transition_into!(Spinning, Idle);
+
+impl TryState for Stalled {
+    type Error = DryerError;
+}
+impl TryErrorState for Stalled {
+    fn consume_error(&mut self, error: Self::Error) {
+        self.error = Some(error);
+    }
+}
+derive_try_transition!(Stalled, Idle, TransitGuard::Transit);
+derive_transition_into!(Stalled, Idle);
+impl From<Idle> for Stalled {
+    fn from(_: Idle) -> Self {
+        Stalled { error: None }
+    }
+}
+impl From<Spinning> for Stalled {
+    fn from(_: Spinning) -> Self {
+        Stalled { error: None }
+    }
+}
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_restart_example() -> Result<(), SfsmError> {
+    let mut washer = Washer::new();
+    // A new state machine is stopped until it is started
+    assert!(matches!(washer.peek_state(), WasherStates::Stopped));
+    assert_eq!(washer.state_id(), None);
+    assert!(!IsState::<Filling>::is_state(&washer));
+
+    washer.start(Filling { liters: 0 })?;
+    washer.step()?;
+    washer.step()?;
+    assert!(IsState::<Washing>::is_state(&washer));
+
+    // Stopping leaves the active state and returns it
+    match washer.stop()? {
+        WasherStates::WashingState(Some(washing)) => assert_eq!(washing.minutes, 0),
+        _ => panic!("Expected to stop in the Washing state"),
+    }
+    assert!(matches!(washer.peek_state(), WasherStates::Stopped));
+    assert_eq!(washer.state_name(), None);
+    assert!(!IsState::<Filling>::is_state(&washer));
+    assert!(!IsState::<Washing>::is_state(&washer));
+    assert!(washer.step().is_err());
+
+    // The same state machine can be started again
+    washer.start(Filling { liters: 5 })?;
+    washer.step()?;
+    washer.step()?;
+    assert_eq!(washer.get::<Washing>().map(|washing| washing.minutes), Some(15));
+
+    // Resetting exits the active state and enters the new initial state
+    washer.reset(Filling { liters: 0 })?;
+    assert_eq!(washer.get::<Filling>().map(|filling| filling.liters), Some(0));
+
+    Ok(())
+}
+
+fn run_reset_example() -> Result<(), SfsmError> {
+    let mut dishwasher = Dishwasher::new(Journal { entries: vec![] });
+    dishwasher.start(Loading {})?;
+    dishwasher.step()?;
+    assert!(IsState::<Spraying>::is_state(&dishwasher));
+
+    // Resetting only runs the exit hooks, starting with the innermost sub-state. Neither the
+    // actions of the transitions nor the stop hooks run.
+    dishwasher.context_mut().entries.clear();
+    dishwasher.reset(Loading {})?;
+    assert_eq!(
+        dishwasher.context().entries,
+        vec!["Exit Spraying", "Exit Cleaning", "Enter Loading"]
+    );
+    assert!(IsState::<Loading>::is_state(&dishwasher));
+
+    // Stopping runs the stop hooks and, without exclusive actions, the actions as well
+    dishwasher.step()?;
+    dishwasher.context_mut().entries.clear();
+    dishwasher.stop()?;
+    assert_eq!(
+        dishwasher.context().entries,
+        vec![
+            "Exit Spraying",
+            "Stop Spraying",
+            "Exit Cleaning",
+            "Cleaning to Loading",
+            "Stop Cleaning"
+        ]
+    );
+
+    Ok(())
+}
+
+fn run_stop_error_example() -> Result<(), ExtendedSfsmError<DryerError>> {
+    let mut dryer = Dryer::new();
+    dryer.start_in(Spinning { rpm: 800 })?;
+
+    // If leaving the state fails, the error is returned and the state machine keeps running in
+    // the state instead of entering the error state
+    assert!(matches!(
+        dryer.stop(),
+        Err(ExtendedSfsmError::Custom(DryerError::DoorLocked))
+    ));
+    assert_eq!(dryer.state_id(), Some(DryerStateId::Spinning));
+    assert!(!IsState::<Stalled>::is_state(&dryer));
+    assert!(dryer.reset(Idle {}).is_err());
+    assert_eq!(dryer.state_id(), Some(DryerStateId::Spinning));
+
+    // Once the drum stopped, the state machine can be stopped
+    if let Some(spinning) = dryer.get_mut::<Spinning>() {
+        spinning.rpm = 0;
+    }
+    match dryer.stop()? {
+        DryerStates::SpinningState(Some(spinning)) => assert_eq!(spinning.rpm, 0),
+        _ => panic!("Expected to stop in the Spinning state"),
+    }
+    assert_eq!(dryer.state_id(), None);
+
+    Ok(())
+}
+
+fn main() {
+    run_restart_example().unwrap();
+    run_reset_example().unwrap();
+    run_stop_error_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_reset_example, run_restart_example, run_stop_error_example};
+
+    #[test]
+    fn restart_example() {
+        run_restart_example().unwrap();
+    }
+
+    #[test]
+    fn reset_example() {
+        run_reset_example().unwrap();
+    }
+
+    #[test]
+    fn stop_error_example() {
+        run_stop_error_example().unwrap();
+    }
+}
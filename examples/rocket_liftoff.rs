@@ -1,3 +1,5 @@
+// The example is kept in the style it was written in before the lints were checked
+#![allow(clippy::from_over_into, clippy::needless_return)]
 use sfsm::*;
 
 // First define all the state structs
@@ -12,7 +14,7 @@ struct Abort {tries: u32}
 add_state_machine!(
     Rocket,                           // Name of the state machine
     WaitForLaunch,                    // The state machine will start at the count down
-    [WaitForLaunch, Launch, Abort],   // All possible states
+    [WaitForLaunch, #[sfsm(final)] Launch, Abort],   // All possible states
     [
         WaitForLaunch => Launch,      // If all is ok, the launch will start
         WaitForLaunch => Abort,       // If there is a malfunction, abort
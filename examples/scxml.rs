@@ -0,0 +1,89 @@
+use sfsm::*;
+
+// An example of how a state machine can be generated from a SCXML file that was designed in an
+// external modeling tool. Enable the scxml feature to run it.
+
+pub struct WaitForLaunch {}
+pub struct Ascent {}
+// The states of the ascent
+pub struct Burn {}
+pub struct Separation {}
+// The states after the ascent
+pub struct Coast {}
+pub struct Landed {}
+
+// The event
+pub struct Ignite {}
+
+// The name of the state machine is taken from the name attribute of the document if it is omitted
+add_state_machine_from_scxml!(Rocket, "examples/rocket.scxml");
+
+derive_state!(WaitForLaunch);
+impl EventTransition<Ascent, Ignite> for WaitForLaunch {}
+derive_transition_into!(WaitForLaunch, Ascent);
+
+derive_state!(Ascent);
+impl CompositeState<Burn> for Ascent {
+    fn initial_state(&mut self) -> Burn {
+        Burn {}
+    }
+}
+derive_transition!(Ascent, Coast, TransitGuard::Transit);
+derive_transition_into!(Ascent, Coast);
+
+derive_state!(Burn);
+derive_transition!(Burn, Separation, TransitGuard::Transit);
+derive_transition_into!(Burn, Separation);
+derive_state!(Separation);
+
+derive_state!(Coast);
+derive_transition!(Coast, Landed, TransitGuard::Transit);
+derive_transition_into!(Coast, Landed);
+
+derive_state!(Landed);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_scxml_example() -> Result<(), SfsmError> {
+    let mut rocket = Rocket::new();
+    rocket.start(WaitForLaunch {})?;
+
+    rocket.handle(Ignite {}.into())?;
+    assert!(IsState::<Burn>::is_state(&rocket));
+
+    rocket.step()?;
+    assert!(IsState::<Separation>::is_state(&rocket));
+
+    rocket.step()?;
+    assert!(IsState::<Coast>::is_state(&rocket));
+
+    rocket.step()?;
+    assert!(IsState::<Landed>::is_state(&rocket));
+    assert!(rocket.is_finished());
+
+    // Every state machine can be exported as SCXML document again
+    assert_eq!(Rocket::SCXML, include_str!("rocket.scxml"));
+
+    Ok(())
+}
+
+fn main() {
+    run_scxml_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_scxml_example;
+
+    #[test]
+    fn scxml_example() {
+        run_scxml_example().unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use sfsm::*;
+
+// An example of how a state machine can be serialized to resume it after a restart. Enable the
+// serde feature to run it. The generated types can be serialized if all states can be serialized.
+
+#[derive(Serialize, Deserialize)]
+pub struct Offline {}
+
+#[derive(Serialize, Deserialize)]
+pub struct Connecting {
+    attempts: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Online {
+    connections: u32,
+    uptime: u32,
+}
+// The states of the connection
+#[derive(Serialize, Deserialize)]
+pub struct Handshake {
+    greeted: bool,
+}
+#[derive(Serialize, Deserialize)]
+pub struct Serving {
+    requests: u32,
+}
+
+add_state_machine!(
+    Gateway,
+    Offline,
+    [
+        Offline,
+        Connecting,
+        Online {
+            Handshake,
+            [Handshake, Serving],
+            [Handshake => Serving]
+        },
+    ],
+    [
+        Offline => Connecting,
+        Connecting => Online,
+        Online => Offline,
+    ]
+);
+
+derive_state!(Offline);
+derive_transition!(Offline, Connecting, TransitGuard::Transit);
+impl From<Offline> for Connecting {
+    fn from(_: Offline) -> Self {
+        Connecting { attempts: 0 }
+    }
+}
+
+impl State for Connecting {
+    fn execute(&mut self) {
+        self.attempts += 1;
+    }
+}
+impl Transition<Online> for Connecting {
+    fn guard(&self) -> TransitGuard {
+        (self.attempts >= 2).into()
+    }
+}
+impl From<Connecting> for Online {
+    fn from(_: Connecting) -> Self {
+        Online {
+            connections: 0,
+            uptime: 0,
+        }
+    }
+}
+
+impl State for Online {
+    fn entry(&mut self) {
+        self.connections += 1;
+    }
+    fn execute(&mut self) {
+        self.uptime += 1;
+    }
+}
+impl CompositeState<Handshake> for Online {
+    fn initial_state(&mut self) -> Handshake {
+        Handshake { greeted: false }
+    }
+}
+impl Transition<Offline> for Online {
+    fn guard(&self) -> TransitGuard {
+        (self.uptime >= 100).into()
+    }
+}
+derive_transition_into!(Online, Offline);
+
+impl State for Handshake {
+    fn entry(&mut self) {
+        self.greeted = true;
+    }
+}
+derive_transition!(Handshake, Serving, TransitGuard::Transit);
+impl From<Handshake> for Serving {
+    fn from(_: Handshake) -> Self {
+        Serving { requests: 0 }
+    }
+}
+
+impl State for Serving {
+    fn execute(&mut self) {
+        self.requests += 1;
+    }
+}
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_serde_snapshot_example() -> Result<(), SfsmError> {
+    let mut gateway = Gateway::new();
+    gateway.start(Offline {})?;
+    gateway.step()?;
+    gateway.step()?;
+    gateway.step()?;
+    assert_eq!(gateway.get::<Online>().map(|online| online.connections), Some(1));
+
+    // Take a snapshot of the states before the gateway restarts
+    let snapshot = serde_json::to_string(gateway.peek_state()).unwrap();
+    assert_eq!(snapshot, r#"{"OnlineState":{"connections":1,"uptime":0}}"#);
+
+    // After the restart, the state machine is restored without entering the state again
+    let states: GatewayStates = serde_json::from_str(&snapshot).unwrap();
+    let mut gateway = Gateway::restore(states);
+    // The sub-states are not part of the snapshot. They start from the initial sub-state, which
+    // is not entered either.
+    assert_eq!(gateway.get::<Handshake>().map(|handshake| handshake.greeted), Some(false));
+    gateway.step()?;
+    let online = gateway.get::<Online>().unwrap();
+    assert_eq!(online.connections, 1);
+    assert_eq!(online.uptime, 1);
+    assert!(gateway.get::<Serving>().is_some());
+    gateway.step()?;
+    assert_eq!(gateway.get::<Serving>().map(|serving| serving.requests), Some(1));
+
+    // The whole state machine can be serialized as well, which includes the sub-states of
+    // composite states
+    let snapshot = serde_json::to_string(&gateway).unwrap();
+    let mut gateway: Gateway = serde_json::from_str(&snapshot).unwrap();
+    gateway.step()?;
+    assert_eq!(gateway.get::<Online>().map(|online| online.uptime), Some(3));
+    assert_eq!(gateway.get::<Serving>().map(|serving| serving.requests), Some(2));
+
+    Ok(())
+}
+
+fn main() {
+    run_serde_snapshot_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_serde_snapshot_example;
+
+    #[test]
+    fn serde_snapshot_example() {
+        run_serde_snapshot_example().unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+use sfsm::*;
+
+// An example of how a state machine can be started in another state than its initial state, like
+// when recovering after a watchdog reset. Only the states of the state machine can be started in,
+// the sub-states of composite states cannot.
+
+pub struct Priming {
+    pressure: u32,
+}
+pub struct Pumping {
+    pressure: u32,
+}
+// The error state
+pub struct Fault {
+    error: Option<PumpError>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PumpError {
+    NoPressure,
+}
+
+add_fallible_state_machine!(
+    Pump,
+    Priming,
+    [Priming, Pumping, Fault],
+    [
+        Priming => Pumping,
+        Pumping => Priming,
+        Fault => Priming,
+    ],
+    PumpError,
+    Fault
+);
+
+impl TryState for Priming {
+    type Error = PumpError;
+    fn try_execute(&mut self) -> Result<(), Self::Error> {
+        self.pressure += 1;
+        Ok(())
+    }
+}
+impl TryTransition<Pumping> for Priming {
+    fn guard(&self) -> TransitGuard {
+        (self.pressure >= 2).into()
+    }
+}
+impl From<Priming> for Pumping {
+    fn from(priming: Priming) -> Self {
+        Pumping {
+            pressure: priming.pressure,
+        }
+    }
+}
+
+impl TryState for Pumping {
+    type Error = PumpError;
+    // After a reset, the pump might have lost its pressure
+    fn try_entry(&mut self) -> Result<(), Self::Error> {
+        if self.pressure == 0 {
+            return Err(PumpError::NoPressure);
+        }
+        Ok(())
+    }
+}
+impl TryTransition<Priming> for Pumping {
+    fn guard(&self) -> TransitGuard {
+        (self.pressure == 0).into()
+    }
+}
+impl From<Pumping> for Priming {
+    fn from(_: Pumping) -> Self {
+        Priming { pressure: 0 }
+    }
+}
+
+impl TryState for Fault {
+    type Error = PumpError;
+}
+impl TryErrorState for Fault {
+    fn consume_error(&mut self, error: Self::Error) {
+        self.error = Some(error);
+    }
+}
+derive_try_transition!(Fault, Priming, TransitGuard::Transit);
+impl From<Fault> for Priming {
+    fn from(_: Fault) -> Self {
+        Priming { pressure: 0 }
+    }
+}
+impl From<Priming> for Fault {
+    fn from(_: Priming) -> Self {
+        Fault { error: None }
+    }
+}
+impl From<Pumping> for Fault {
+    fn from(_: Pumping) -> Self {
+        Fault { error: None }
+    }
+}
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_start_in_example() -> Result<(), ExtendedSfsmError<PumpError>> {
+    // After a reset, the pump resumes pumping without priming again
+    let mut pump = Pump::new();
+    pump.start_in(Pumping { pressure: 3 })?;
+    assert_eq!(pump.state_name(), Some("Pumping"));
+    pump.step()?;
+    assert!(IsState::<Pumping>::is_state(&pump));
+
+    // A running state machine cannot be started again without stopping it first
+    assert!(matches!(
+        pump.start_in(Priming { pressure: 0 }),
+        Err(ExtendedSfsmError::AlreadyStarted)
+    ));
+    assert_eq!(pump.get::<Pumping>().map(|pumping| pumping.pressure), Some(3));
+    pump.stop()?;
+    pump.start_in(Priming { pressure: 0 })?;
+
+    // The state is entered like the initial state, so errors are routed to the error state
+    let mut pump = Pump::new();
+    pump.start_in(Pumping { pressure: 0 })?;
+    assert_eq!(
+        pump.get::<Fault>().and_then(|fault| fault.error.as_ref()),
+        Some(&PumpError::NoPressure)
+    );
+
+    // The fault is handled by priming the pump again
+    pump.step()?;
+    pump.step()?;
+    pump.step()?;
+    assert!(IsState::<Pumping>::is_state(&pump));
+
+    Ok(())
+}
+
+fn main() {
+    run_start_in_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_start_in_example;
+
+    #[test]
+    fn start_in_example() {
+        run_start_in_example().unwrap();
+    }
+}
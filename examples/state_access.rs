@@ -0,0 +1,138 @@
+use sfsm::*;
+
+// An example of how the data of the active state can be read or modified from outside of the
+// state machine, for example to display it or for diagnostics
+
+pub struct Heating {
+    temperature: u32,
+}
+pub struct Brewing {}
+// The states of the brewing
+pub struct Grinding {
+    grams: u32,
+}
+pub struct Extracting {
+    milliliters: u32,
+}
+
+add_state_machine!(
+    CoffeeMaker,
+    Heating,
+    [
+        Heating,
+        Brewing {
+            Grinding,
+            [Grinding, #[sfsm(final)] Extracting],
+            [Grinding => Extracting]
+        },
+    ],
+    [
+        Heating => Brewing,
+        Brewing => Heating after completion,
+    ]
+);
+
+impl State for Heating {
+    fn execute(&mut self) {
+        self.temperature += 30;
+    }
+}
+impl Transition<Brewing> for Heating {
+    fn guard(&self) -> TransitGuard {
+        (self.temperature >= 90).into()
+    }
+}
+derive_transition_into!(Heating, Brewing);
+
+derive_state!(Brewing);
+impl CompositeState<Grinding> for Brewing {
+    fn initial_state(&mut self) -> Grinding {
+        Grinding { grams: 0 }
+    }
+}
+derive_transition!(Brewing, Heating, TransitGuard::Transit);
+impl From<Brewing> for Heating {
+    fn from(_: Brewing) -> Self {
+        Heating { temperature: 20 }
+    }
+}
+
+impl State for Grinding {
+    fn execute(&mut self) {
+        self.grams += 9;
+    }
+}
+impl Transition<Extracting> for Grinding {
+    fn guard(&self) -> TransitGuard {
+        (self.grams >= 18).into()
+    }
+}
+impl From<Grinding> for Extracting {
+    fn from(_: Grinding) -> Self {
+        Extracting { milliliters: 0 }
+    }
+}
+
+impl State for Extracting {
+    fn execute(&mut self) {
+        self.milliliters += 10;
+    }
+}
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_state_access_example() -> Result<(), SfsmError> {
+    let mut coffee_maker = CoffeeMaker::new();
+    coffee_maker.start(Heating { temperature: 20 })?;
+
+    coffee_maker.step()?;
+    assert_eq!(coffee_maker.get::<Heating>().map(|heating| heating.temperature), Some(50));
+    // Only the active state can be accessed
+    assert!(coffee_maker.get::<Brewing>().is_none());
+
+    // The state can be modified as well
+    if let Some(heating) = coffee_maker.get_mut::<Heating>() {
+        heating.temperature = 90;
+    }
+    coffee_maker.step()?;
+    assert!(coffee_maker.get::<Heating>().is_none());
+    assert!(coffee_maker.get::<Brewing>().is_some());
+
+    // The active sub-states of a composite state can be accessed the same way
+    assert_eq!(coffee_maker.get::<Grinding>().map(|grinding| grinding.grams), Some(0));
+    coffee_maker.step()?;
+    coffee_maker.step()?;
+    assert!(coffee_maker.get::<Grinding>().is_none());
+    assert_eq!(
+        coffee_maker.get::<Extracting>().map(|extracting| extracting.milliliters),
+        Some(0)
+    );
+
+    // Sub-states cannot be accessed once their composite state is left
+    coffee_maker.step()?;
+    assert!(coffee_maker.get::<Heating>().is_some());
+    assert!(coffee_maker.get::<Extracting>().is_none());
+
+    Ok(())
+}
+
+fn main() {
+    run_state_access_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_state_access_example;
+
+    #[test]
+    fn state_access_example() {
+        run_state_access_example().unwrap();
+    }
+}
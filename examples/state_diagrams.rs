@@ -0,0 +1,133 @@
+use sfsm::*;
+
+// An example of how the definition of a state machine can be exported as a Mermaid or PlantUML
+// state diagram to embed it into Markdown documentation
+
+pub struct Off {}
+pub struct Operating {}
+// The states of the radio link region
+pub struct LinkDown {}
+pub struct LinkUp {}
+// The states of the power management region
+pub struct Awake {}
+pub struct Asleep {}
+
+// The event
+pub struct PowerOff {}
+
+// The message
+pub struct Status {}
+
+add_state_machine!(
+    Device,
+    Off,
+    [
+        Off,
+        #[sfsm(history = shallow)]
+        Operating {
+            LinkDown,
+            [LinkDown, LinkUp],
+            [LinkDown => LinkUp]
+            ||
+            Awake,
+            [Awake, Asleep],
+            [Awake => Asleep, Asleep => Awake]
+        },
+    ],
+    [
+        Off => Operating,
+        Operating => Off on PowerOff,
+    ]
+);
+
+add_messages!(Device, [Status <- Operating]);
+
+derive_state!(Off);
+derive_transition!(Off, Operating, TransitGuard::Transit);
+derive_transition_into!(Off, Operating);
+
+derive_state!(Operating);
+impl CompositeState<LinkDown> for Operating {
+    fn initial_state(&mut self) -> LinkDown {
+        LinkDown {}
+    }
+}
+impl CompositeState<Awake> for Operating {
+    fn initial_state(&mut self) -> Awake {
+        Awake {}
+    }
+}
+impl EventTransition<Off, PowerOff> for Operating {}
+impl ReturnMessage<Status> for Operating {
+    fn return_message(&mut self) -> Option<Status> {
+        Some(Status {})
+    }
+}
+derive_transition_into!(Operating, Off);
+
+derive_state!(LinkDown);
+derive_transition!(LinkDown, LinkUp, TransitGuard::Transit);
+derive_transition_into!(LinkDown, LinkUp);
+derive_state!(LinkUp);
+
+derive_state!(Awake);
+derive_transition!(Awake, Asleep, TransitGuard::Transit);
+derive_transition_into!(Awake, Asleep);
+derive_state!(Asleep);
+derive_transition!(Asleep, Awake, TransitGuard::Transit);
+derive_transition_into!(Asleep, Awake);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_state_diagrams_example() -> Result<(), core::fmt::Error> {
+    // The Mermaid diagram can be embedded into Markdown in a mermaid code block
+    let mermaid = Device::MERMAID;
+    println!("{}", mermaid);
+
+    assert!(mermaid.starts_with("stateDiagram-v2\n"));
+    assert!(mermaid.contains("    [*] --> Off\n"));
+    // Names that are no valid identifiers are declared with an alias
+    assert!(mermaid.contains("    state \"Operating (H)\" as Operating\n"));
+    // The regions of a composite state are separated with --
+    assert!(mermaid.contains("        [*] --> LinkDown\n        LinkDown --> LinkUp\n        --\n"));
+    assert!(mermaid.contains("    Operating --> Off : PowerOff\n"));
+
+    let plantuml = Device::PLANTUML;
+    println!("{}", plantuml);
+
+    assert!(plantuml.starts_with("@startuml\n"));
+    assert!(plantuml.ends_with("@enduml\n"));
+
+    // The messages are only known once they are added to the state machine
+    let mut mermaid_with_messages = String::new();
+    Device::write_mermaid_with_messages(&mut mermaid_with_messages)?;
+    assert!(mermaid_with_messages.contains("    Operating --> StatusMessage : poll\n"));
+
+    let mut plantuml_with_messages = String::new();
+    Device::write_plantuml_with_messages(&mut plantuml_with_messages)?;
+    assert!(plantuml_with_messages.contains("    Operating -[dashed]-> StatusMessage : poll\n"));
+    assert!(plantuml_with_messages.ends_with("@enduml\n"));
+
+    Ok(())
+}
+
+fn main() {
+    run_state_diagrams_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_state_diagrams_example;
+
+    #[test]
+    fn state_diagrams_example() {
+        run_state_diagrams_example().unwrap();
+    }
+}
@@ -0,0 +1,106 @@
+use sfsm::*;
+
+// An example of how the active state can be logged or displayed by its name or id without the
+// trace feature
+
+pub struct Idle {}
+pub struct Moving {}
+// The states of the ride
+pub struct Accelerating {}
+pub struct Cruising {}
+pub struct DoorsOpen {}
+
+add_state_machine!(
+    Elevator,
+    Idle,
+    [
+        Idle,
+        Moving {
+            Accelerating,
+            [Accelerating, Cruising],
+            [Accelerating => Cruising]
+        },
+        DoorsOpen,
+    ],
+    [
+        Idle => Moving,
+        Moving => DoorsOpen,
+        DoorsOpen => Idle,
+    ]
+);
+
+derive_state!(Idle);
+derive_transition!(Idle, Moving, TransitGuard::Transit);
+derive_transition_into!(Idle, Moving);
+
+derive_state!(Moving);
+impl CompositeState<Accelerating> for Moving {
+    fn initial_state(&mut self) -> Accelerating {
+        Accelerating {}
+    }
+}
+impl Transition<DoorsOpen> for Moving {
+    fn guard(&self) -> TransitGuard {
+        TransitGuard::Remain
+    }
+}
+derive_transition_into!(Moving, DoorsOpen);
+
+derive_state!(Accelerating);
+derive_transition!(Accelerating, Cruising, TransitGuard::Transit);
+derive_transition_into!(Accelerating, Cruising);
+derive_state!(Cruising);
+
+derive_state!(DoorsOpen);
+derive_transition!(DoorsOpen, Idle, TransitGuard::Transit);
+derive_transition_into!(DoorsOpen, Idle);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_state_names_example() -> Result<(), SfsmError> {
+    let mut elevator = Elevator::new();
+    // A state machine that has not been started has no active state
+    assert_eq!(elevator.state_name(), None);
+    assert_eq!(elevator.state_id(), None);
+    elevator.start(Idle {})?;
+
+    assert_eq!(elevator.state_name(), Some("Idle"));
+    assert_eq!(elevator.state_id(), Some(ElevatorStateId::Idle));
+
+    // The sub-states of a composite state are not considered
+    elevator.step()?;
+    elevator.step()?;
+    assert!(IsState::<Cruising>::is_state(&elevator));
+    assert_eq!(elevator.state_name(), Some("Moving"));
+    assert_eq!(elevator.state_id(), Some(ElevatorStateId::Moving));
+
+    // The list of names contains the sub-states as well and is indexed by the state id
+    assert_eq!(
+        Elevator::STATES,
+        &["Idle", "Moving", "Accelerating", "Cruising", "DoorsOpen"]
+    );
+    assert_eq!(Elevator::STATES[ElevatorStateId::Cruising as usize], "Cruising");
+
+    Ok(())
+}
+
+fn main() {
+    run_state_names_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_state_names_example;
+
+    #[test]
+    fn state_names_example() {
+        run_state_names_example().unwrap();
+    }
+}
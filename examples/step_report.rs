@@ -0,0 +1,93 @@
+use sfsm::*;
+
+// An example of how the outcome of a step can be used to react to transitions
+
+pub struct Idle {
+    requests: u32,
+}
+pub struct Priming {}
+pub struct Pumping {}
+
+add_state_machine!(
+    Pump,
+    Idle,
+    [Idle, Priming, Pumping],
+    [
+        Idle => Priming,
+        Priming => Pumping,
+        Pumping => Idle,
+    ]
+);
+
+impl State for Idle {
+    fn execute(&mut self) {
+        self.requests += 1;
+    }
+}
+impl Transition<Priming> for Idle {
+    fn guard(&self) -> TransitGuard {
+        (self.requests >= 2).into()
+    }
+}
+derive_transition_into!(Idle, Priming);
+
+derive_state!(Priming);
+derive_transition!(Priming, Pumping, TransitGuard::Transit);
+derive_transition_into!(Priming, Pumping);
+
+derive_state!(Pumping);
+derive_transition!(Pumping, Idle, TransitGuard::Remain);
+impl From<Pumping> for Idle {
+    fn from(_: Pumping) -> Self {
+        Idle { requests: 0 }
+    }
+}
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_step_report_example() -> Result<(), SfsmError> {
+    let mut pump = Pump::new();
+    pump.start(Idle { requests: 0 })?;
+
+    // The report tells which state remained active
+    let outcome = pump.step_report()?;
+    assert_eq!(outcome, StepOutcome::Stayed(PumpStateId::Idle));
+
+    // Or which transition was taken, so a supervisor can react to it
+    let outcome = pump.step_report()?;
+    assert_eq!(
+        outcome,
+        StepOutcome::Transitioned {
+            from: PumpStateId::Idle,
+            to: PumpStateId::Priming
+        }
+    );
+
+    if let StepOutcome::Transitioned { from, to } = pump.step_report()? {
+        println!("The pump went from {:?} to {:?}", from, to);
+        assert_eq!(to, PumpStateId::Pumping);
+    }
+
+    Ok(())
+}
+
+fn main() {
+    run_step_report_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_step_report_example;
+
+    #[test]
+    fn step_report_example() {
+        run_step_report_example().unwrap();
+    }
+}
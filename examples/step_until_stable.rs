@@ -0,0 +1,133 @@
+use sfsm::*;
+
+// An example of how a state machine can be stepped until no transition is taken anymore
+
+// The states of the boot sequence
+pub struct PowerUp {}
+pub struct SelfTest {}
+pub struct LoadConfig {}
+pub struct Ready {}
+
+add_state_machine!(
+    Boot,
+    PowerUp,
+    [PowerUp, SelfTest, LoadConfig, #[sfsm(final)] Ready],
+    [
+        PowerUp => SelfTest,
+        SelfTest => LoadConfig,
+        LoadConfig => Ready,
+    ]
+);
+
+derive_state!(PowerUp);
+derive_transition!(PowerUp, SelfTest, TransitGuard::Transit);
+derive_transition_into!(PowerUp, SelfTest);
+
+derive_state!(SelfTest);
+derive_transition!(SelfTest, LoadConfig, TransitGuard::Transit);
+derive_transition_into!(SelfTest, LoadConfig);
+
+derive_state!(LoadConfig);
+derive_transition!(LoadConfig, Ready, TransitGuard::Transit);
+derive_transition_into!(LoadConfig, Ready);
+
+derive_state!(Ready);
+
+// A state machine that never comes to rest
+pub struct Ping {}
+pub struct Pong {}
+
+add_state_machine!(
+    Rally,
+    Ping,
+    [Ping, Pong],
+    [
+        Ping => Pong,
+        Pong => Ping,
+    ]
+);
+
+derive_state!(Ping);
+derive_transition!(Ping, Pong, TransitGuard::Transit);
+derive_transition_into!(Ping, Pong);
+
+derive_state!(Pong);
+derive_transition!(Pong, Ping, TransitGuard::Transit);
+derive_transition_into!(Pong, Ping);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_step_until_stable_example() -> Result<(), SfsmError> {
+    let mut boot = Boot::new();
+    boot.start(PowerUp {})?;
+
+    // All transitions of the boot sequence are taken within a single call
+    let transitions = boot.step_until_stable(10)?;
+    assert_eq!(transitions, 3);
+    assert!(IsState::<Ready>::is_state(&boot));
+
+    // A stable state machine takes no transition at all
+    assert_eq!(boot.step_until_stable(10)?, 0);
+
+    // A state machine that keeps transiting forever exceeds the limit
+    let mut rally = Rally::new();
+    rally.start(Ping {})?;
+    let result = rally.step_until_stable(5);
+    assert!(matches!(result, Err(SfsmError::TransitionLimitExceeded)));
+
+    Ok(())
+}
+
+fn run_transition_limit_example() -> Result<(), SfsmError> {
+    // The boot sequence takes three transitions. A lower limit is exceeded by the transition
+    // after it, which has already been taken when the error is returned.
+    let mut boot = Boot::new();
+    boot.start(PowerUp {})?;
+    let result = boot.step_until_stable(2);
+    assert!(matches!(result, Err(SfsmError::TransitionLimitExceeded)));
+    assert!(IsState::<Ready>::is_state(&boot));
+
+    let mut boot = Boot::new();
+    boot.start(PowerUp {})?;
+    let result = boot.step_until_stable(0);
+    assert!(matches!(result, Err(SfsmError::TransitionLimitExceeded)));
+    assert!(IsState::<SelfTest>::is_state(&boot));
+
+    // A limit that is reached, but not exceeded, is fine
+    let mut boot = Boot::new();
+    boot.start(PowerUp {})?;
+    assert_eq!(boot.step_until_stable(3)?, 3);
+
+    let mut boot = Boot::new();
+    boot.start(PowerUp {})?;
+    assert_eq!(boot.step_until_stable(4)?, 3);
+
+    Ok(())
+}
+
+fn main() {
+    run_step_until_stable_example().unwrap();
+    run_transition_limit_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_step_until_stable_example, run_transition_limit_example};
+
+    #[test]
+    fn step_until_stable_example() {
+        run_step_until_stable_example().unwrap();
+    }
+
+    #[test]
+    fn transition_limit_example() {
+        run_transition_limit_example().unwrap();
+    }
+}
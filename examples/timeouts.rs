@@ -0,0 +1,214 @@
+use sfsm::*;
+
+// An example of how transitions can be taken after a state has been active for a while
+
+// The states of the traffic light
+pub struct Off {}
+pub struct Operating {}
+pub struct Red {}
+pub struct Green {}
+pub struct Yellow {}
+
+// The events
+pub struct PowerOn {}
+pub struct PowerOff {}
+
+add_state_machine!(
+    #[sfsm(clock = MockClock)]          // The type of the clock used for the timeouts
+    TrafficLight,
+    Off,
+    [
+        Off,
+        Operating {
+            Red,
+            [Red, Green, Yellow],
+            [
+                Red => Green after 3s,          // Durations can be given in s, ms or us
+                Green => Yellow after 2s,
+                Yellow => Red after 500ms,
+            ]
+        },
+    ],
+    [
+        Off => Operating on PowerOn,
+        Operating => Off on PowerOff,
+    ]
+);
+
+derive_state!(Off);
+impl EventTransition<Operating, PowerOn> for Off {}
+impl From<Off> for Operating {
+    fn from(_: Off) -> Self {
+        Operating {}
+    }
+}
+
+derive_state!(Operating);
+impl CompositeState<Red> for Operating {
+    fn initial_state(&mut self) -> Red {
+        Red {}
+    }
+}
+impl EventTransition<Off, PowerOff> for Operating {}
+derive_transition_into!(Operating, Off);
+
+// A timeout transition is only taken if its guard allows it as well
+derive_state!(Red);
+derive_transition!(Red, Green, TransitGuard::Transit);
+derive_transition_into!(Red, Green);
+
+derive_state!(Green);
+derive_transition!(Green, Yellow, TransitGuard::Transit);
+derive_transition_into!(Green, Yellow);
+
+derive_state!(Yellow);
+derive_transition!(Yellow, Red, TransitGuard::Transit);
+derive_transition_into!(Yellow, Red);
+
+// A clock of a timer that only ticks ten times per second
+pub struct CoarseClock {
+    ticks: u64,
+}
+
+impl Clock for CoarseClock {
+    const TICKS_PER_SECOND: u64 = 10;
+
+    fn now(&self) -> u64 {
+        self.ticks
+    }
+}
+
+// The states of the blinking light
+pub struct Lit {}
+pub struct Dark {}
+
+add_state_machine!(
+    #[sfsm(clock = CoarseClock)]
+    Blinker,
+    Lit,
+    [Lit, Dark],
+    [
+        Lit => Dark after 50ms,         // Shorter than a tick of the clock
+        Dark => Lit after 150ms,        // Not a multiple of a tick of the clock
+    ]
+);
+
+derive_state!(Lit);
+derive_transition!(Lit, Dark, TransitGuard::Transit);
+derive_transition_into!(Lit, Dark);
+
+derive_state!(Dark);
+derive_transition!(Dark, Lit, TransitGuard::Transit);
+derive_transition_into!(Dark, Lit);
+
+/// Register a logger function
+/// Enable the trace features for the tracing to work
+/// The logger function receives logs from the state machine and forwards them
+/// to what ever logging mechanism desired.
+#[sfsm_trace]
+fn trace(log: &str) {
+    println!("{}", log);
+}
+
+fn run_timeouts_example() -> Result<(), SfsmError> {
+    // The clock is moved into the state machine when it is created
+    let mut traffic_light = TrafficLight::new(MockClock::new());
+    traffic_light.start(Off {})?;
+
+    traffic_light.handle(PowerOn {}.into())?;
+    assert!(IsState::<Red>::is_state(&traffic_light));
+
+    // The timeout starts when the state is entered and is checked on every step
+    traffic_light.clock_mut().advance(2999);
+    traffic_light.step()?;
+    assert!(IsState::<Red>::is_state(&traffic_light));
+
+    traffic_light.clock_mut().advance(1);
+    traffic_light.step()?;
+    assert!(IsState::<Green>::is_state(&traffic_light));
+
+    // Time that passes without a step is taken into account on the next step
+    traffic_light.clock_mut().advance(5000);
+    traffic_light.step()?;
+    assert!(IsState::<Yellow>::is_state(&traffic_light));
+
+    traffic_light.clock_mut().advance(500);
+    traffic_light.step()?;
+    assert!(IsState::<Red>::is_state(&traffic_light));
+
+    // Entering a state again restarts its timeout
+    traffic_light.clock_mut().advance(1000);
+    traffic_light.handle(PowerOff {}.into())?;
+    traffic_light.handle(PowerOn {}.into())?;
+    traffic_light.clock_mut().advance(2000);
+    traffic_light.step()?;
+    assert!(IsState::<Red>::is_state(&traffic_light));
+
+    Ok(())
+}
+
+fn run_restore_example() -> Result<(), SfsmError> {
+    // A restored state counts as entered when it is restored, like after a restart of a device
+    // whose clock has been running for a while
+    let mut clock = MockClock::new();
+    clock.set(100_000);
+    let mut traffic_light =
+        TrafficLight::restore(TrafficLightStates::OperatingState(Some(Operating {})), clock);
+    assert!(IsState::<Red>::is_state(&traffic_light));
+
+    traffic_light.step()?;
+    assert!(IsState::<Red>::is_state(&traffic_light));
+
+    traffic_light.clock_mut().advance(3000);
+    traffic_light.step()?;
+    assert!(IsState::<Green>::is_state(&traffic_light));
+
+    Ok(())
+}
+
+fn run_coarse_clock_example() -> Result<(), SfsmError> {
+    let mut blinker = Blinker::new(CoarseClock { ticks: 0 });
+    blinker.start(Lit {})?;
+
+    // Durations that do not fit the resolution of the clock are rounded up to whole ticks
+    blinker.step()?;
+    assert!(IsState::<Lit>::is_state(&blinker));
+    blinker.clock_mut().ticks += 1;
+    blinker.step()?;
+    assert!(IsState::<Dark>::is_state(&blinker));
+
+    blinker.clock_mut().ticks += 1;
+    blinker.step()?;
+    assert!(IsState::<Dark>::is_state(&blinker));
+    blinker.clock_mut().ticks += 1;
+    blinker.step()?;
+    assert!(IsState::<Lit>::is_state(&blinker));
+
+    Ok(())
+}
+
+fn main() {
+    run_timeouts_example().unwrap();
+    run_restore_example().unwrap();
+    run_coarse_clock_example().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_coarse_clock_example, run_restore_example, run_timeouts_example};
+
+    #[test]
+    fn timeouts_example() {
+        run_timeouts_example().unwrap();
+    }
+
+    #[test]
+    fn restore_example() {
+        run_restore_example().unwrap();
+    }
+
+    #[test]
+    fn coarse_clock_example() {
+        run_coarse_clock_example().unwrap();
+    }
+}
@@ -1,3 +1,5 @@
+// The example is kept in the style it was written in before the lints were checked
+#![allow(unused_imports, clippy::from_over_into, clippy::needless_return, clippy::empty_line_after_outer_attr)]
 use sfsm::*;
 use sfsm::message::{MessageError, ReturnMessage, ReceiveMessage};
 
@@ -21,7 +23,7 @@ struct Abort {}
 add_state_machine!(
     Rocket,
     WaitForLaunch,
-    [WaitForLaunch, Launch, Abort],
+    [WaitForLaunch, #[sfsm(final)] Launch, Abort],
     [
         WaitForLaunch => Launch,
         WaitForLaunch => Abort,
@@ -0,0 +1,139 @@
+// The traits are only implemented by the user and called by the generated state machine. The
+// returned futures are therefore never required to be Send.
+#![allow(async_fn_in_trait)]
+
+use crate::TransitGuard;
+
+/// Trait that must be implemented by all states of an async state machine
+///
+/// Behaves like the ``` State ``` trait, but all functions are async and can await other futures.
+/// The state machine awaits them one after another without allocating.
+pub trait AsyncState {
+    /// Implement any behavior that hast to be executed when entering the state.
+    ///
+    /// ```rust
+    /// # use sfsm_base::asynchronous::AsyncState;
+    /// # struct FooState;
+    /// # impl AsyncState for FooState {
+    ///     async fn entry(&mut self) {
+    ///         println!("Called right after being transitioned into");
+    ///     }
+    /// # }
+    /// ```
+    async fn entry(&mut self) {}
+
+    /// Implement any behavior that has to be executed when the state is being executed.
+    /// This function will be called as long as the state does not transit.
+    async fn execute(&mut self) {}
+
+    /// Implement any behavior that hast to be executed when exiting the state.
+    async fn exit(&mut self) {}
+
+    /// Implement any behavior that has to be executed when the state machine is stopped while
+    /// this state is active. It is called after the exit function.
+    async fn on_stop(&mut self) {}
+}
+
+/// Trait that must be implemented by a state of an async state machine that want to transition
+/// to DestinationState.
+///
+/// Behaves like the ``` Transition ``` trait, but the action and the guard are async.
+pub trait AsyncTransition<DestinationState>: Into<DestinationState> + AsyncState {
+    /// Implement any behavior that hast to be executed when exiting the state.
+    async fn action(&mut self) {}
+
+    /// Specifies when the state has to transit. Return ``` TransitGuard::Remain ``` to remain
+    /// in the current state and ``` TransitGuard::Transit ``` to transit into the next one.
+    /// ```rust
+    /// # use sfsm_base::asynchronous::{AsyncTransition, AsyncState};
+    /// # use sfsm_base::TransitGuard;
+    /// # struct FooState;
+    /// # struct BarState;
+    /// # impl AsyncState for FooState {};
+    /// # impl Into<BarState> for FooState {
+    /// #     fn into(self) -> BarState { BarState{} }
+    /// # }
+    ///
+    /// # impl AsyncTransition<BarState> for FooState {
+    ///     async fn guard(&self) -> TransitGuard {
+    ///         TransitGuard::Transit
+    ///     }
+    /// # }
+    /// ```
+    async fn guard(&self) -> TransitGuard;
+}
+
+/// Trait that must be implemented by all states of an async fallible state machine
+///
+/// Behaves like the ``` TryState ``` trait, but all functions are async. If an error is returned,
+/// the state machine immediately transitions into the error state.
+pub trait AsyncTryState {
+    // The error type that can be returned by the state
+    type Error;
+
+    /// Implement any behavior that hast to be executed when entering the state.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    ///
+    /// ```rust
+    /// # use sfsm_base::asynchronous::AsyncTryState;
+    /// # struct FooState;
+    /// # impl AsyncTryState for FooState {
+    /// #     type Error = ();
+    ///     async fn try_entry(&mut self) -> Result<(), Self::Error> {
+    ///         println!("Called right after being transitioned into");
+    ///         Ok(())
+    ///     }
+    /// # }
+    /// ```
+    async fn try_entry(&mut self) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Implement any behavior that hast to be stepping.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    async fn try_execute(&mut self) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Implement any behavior that hast to be executed when exiting the state.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    async fn try_exit(&mut self) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Implement any behavior that has to be executed when the state machine is stopped while
+    /// this state is active. It is called after the exit function.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    async fn try_on_stop(&mut self) -> Result<(), Self::Error> { Ok(()) }
+}
+
+/// Trait that must be implemented by a state of an async fallible state machine that want to
+/// transition to DestinationState.
+///
+/// Behaves like the ``` TryTransition ``` trait, but the action and the guard are async.
+pub trait AsyncTryTransition<DestinationState>: Into<DestinationState> + AsyncTryState {
+    /// Implement any behavior that hast to be executed when transitioning to the next the state.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    async fn try_action(&mut self) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Specifies when the state has to transit. Return ``` TransitGuard::Remain ``` to remain
+    /// in the current state and ``` TransitGuard::Transit ``` to transit into the next one.
+    async fn guard(&self) -> TransitGuard;
+}
+
+/// This trait must be implemented by the error state of an async fallible state machine.
+///
+/// Like with the ``` TryErrorState ```, the error is injected into the error state after it has
+/// been generated. Consuming the error is not async, the entry function of the error state is
+/// called right after it.
+pub trait AsyncTryErrorState: AsyncTryState {
+    /// Handle the incoming error
+    /// ```rust
+    /// # use sfsm_base::asynchronous::{AsyncTryState, AsyncTryErrorState};
+    /// # struct ErrorState;
+    /// # impl AsyncTryState for ErrorState {
+    /// #      type Error = ();
+    /// # };
+    /// #
+    /// # impl AsyncTryErrorState for ErrorState {
+    ///     fn consume_error(&mut self, err: Self::Error) {
+    ///         println!("Received an error: {:?}", err);
+    ///     }
+    /// # }
+    /// ```
+    fn consume_error(&mut self, err: Self::Error);
+}
@@ -11,6 +11,14 @@ pub enum ExtendedSfsmError<T> {
     /// machine has not been started before stepping.
     Internal,
 
+    /// Returned by ``` step_until_stable ``` if the state machine takes more transitions than
+    /// allowed without becoming stable.
+    TransitionLimitExceeded,
+
+    /// Returned by ``` start_in ``` if the state machine is already running. It has to be
+    /// stopped or reset instead.
+    AlreadyStarted,
+
     /// The custom error can be returned from the error state if an error cannot be handled.
     /// In that case, the state machine bubbles the error up to the calling start or step
     /// function where it then must be handled by the user.
@@ -76,12 +84,30 @@ pub trait TryState {
     /// # }
     /// ```
     fn try_exit(&mut self) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Implement any behavior that has to be executed when the state machine is stopped while
+    /// this state is active. It is called after the exit function.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    ///
+    /// ```rust
+    /// # use sfsm_base::fallible::TryState;
+    /// # struct FooState;
+    /// # impl TryState for FooState {
+    /// #    type Error = ();
+    ///     fn try_on_stop(&mut self) -> Result<(), Self::Error> {
+    ///         println!("Called when the state machine is stopped");
+    ///         return Ok(());
+    ///     }
+    /// # }
+    /// ```
+    fn try_on_stop(&mut self) -> Result<(), Self::Error> { Ok(()) }
 }
 
 /// Trait that must be implemented by all states have a transition.
 ///
-/// Behaves similar to the ``` TryTransition ``` trait but errors can be returned during every
-/// call.
+/// Behaves similar to the ``` Transition ``` trait but errors can be returned during every
+/// call. Like there, the ``` #[sfsm(exclusive_actions)] ``` option restricts the execution of the
+/// action to the transition that is actually taken.
 pub trait TryTransition<DestinationState>: Into<DestinationState> + TryState {
 
     /// Implement any behavior that hast to be executed when transitioning to the next the state.
@@ -145,6 +171,180 @@ pub trait TryTransition<DestinationState>: Into<DestinationState> + TryState {
     fn guard(&self) -> TransitGuard;
 }
 
+/// Trait that must be implemented by a state that transitions to DestinationState once an Event
+/// is handled in a fallible state machine.
+///
+/// Behaves similar to the ``` EventTransition ``` trait but errors can be returned from the
+/// action.
+pub trait TryEventTransition<DestinationState, Event>: Into<DestinationState> + TryState {
+    /// Implement any behavior that has to be executed when the event causes the transition.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    ///
+    /// ```rust
+    /// # use sfsm_base::fallible::{TryState, TryEventTransition};
+    /// # struct FooState;
+    /// # struct BarState;
+    /// # struct Ignite { thrust: u32 }
+    /// # impl TryState for FooState {
+    /// #      type Error = ();
+    /// # };
+    /// # impl Into<BarState> for FooState {
+    /// #     fn into(self) -> BarState {
+    /// #         BarState{}
+    /// #     }
+    /// # }
+    ///
+    /// # impl TryEventTransition<BarState, Ignite> for FooState {
+    ///     fn try_action(&mut self, event: &Ignite) -> Result<(), Self::Error> {
+    ///         println!("Ignited with a thrust of {}", event.thrust);
+    ///         Ok(())
+    ///     }
+    /// # }
+    /// ```
+    fn try_action(&mut self, _event: &Event) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Specifies if the state has to transit once the event arrives. Return
+    /// ``` TransitGuard::Remain ``` to ignore the event and ``` TransitGuard::Transit ``` to
+    /// transit into the next state. Transits by default.
+    /// ```rust
+    /// # use sfsm_base::TransitGuard;
+    /// # use sfsm_base::fallible::{TryState, TryEventTransition};
+    /// # struct FooState;
+    /// # struct BarState;
+    /// # struct Ignite { thrust: u32 }
+    /// # impl TryState for FooState {
+    /// #      type Error = ();
+    /// # };
+    /// # impl Into<BarState> for FooState {
+    /// #     fn into(self) -> BarState {
+    /// #         BarState{}
+    /// #     }
+    /// # }
+    /// #
+    /// # impl TryEventTransition<BarState, Ignite> for FooState {
+    ///     fn guard(&self, event: &Ignite) -> TransitGuard {
+    ///         (event.thrust > 100).into()
+    ///     }
+    /// # }
+    /// ```
+    fn guard(&self, _event: &Event) -> TransitGuard { TransitGuard::Transit }
+}
+
+/// Trait that must be implemented by all states of a fallible state machine with a context.
+///
+/// Behaves like the ``` TryState ``` trait, but every function additionally receives a mutable
+/// reference to the context that is owned by the state machine.
+pub trait TryContextState<Context> {
+    // The error type that can be returned by the state
+    type Error;
+
+    /// Implement any behavior that hast to be executed when entering the state.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    ///
+    /// ```rust
+    /// # use sfsm_base::fallible::TryContextState;
+    /// # struct FooState;
+    /// # struct Context { entries: u32 }
+    /// # impl TryContextState<Context> for FooState {
+    /// #     type Error = ();
+    ///     fn try_entry(&mut self, context: &mut Context) -> Result<(), Self::Error> {
+    ///         context.entries += 1;
+    ///         Ok(())
+    ///     }
+    /// # }
+    /// ```
+    fn try_entry(&mut self, _context: &mut Context) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Implement any behavior that hast to be executed while stepping.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    fn try_execute(&mut self, _context: &mut Context) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Implement any behavior that hast to be executed when exiting the state.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    fn try_exit(&mut self, _context: &mut Context) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Implement any behavior that has to be executed when the state machine is stopped while
+    /// this state is active. It is called after the exit function.
+    fn try_on_stop(&mut self, _context: &mut Context) -> Result<(), Self::Error> { Ok(()) }
+}
+
+/// Trait that must be implemented by a state of a fallible state machine with a context that
+/// wants to transition to DestinationState.
+///
+/// Behaves like the ``` TryTransition ``` trait, but the action receives a mutable and the guard
+/// an immutable reference to the context of the state machine.
+pub trait TryContextTransition<DestinationState, Context>:
+    Into<DestinationState> + TryContextState<Context>
+{
+    /// Implement any behavior that hast to be executed when transitioning to the next the state.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    fn try_action(&mut self, _context: &mut Context) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Specifies when the state has to transit. Return ``` TransitGuard::Remain ``` to remain
+    /// in the current state and ``` TransitGuard::Transit ``` to transit into the next one.
+    /// ```rust
+    /// # use sfsm_base::TransitGuard;
+    /// # use sfsm_base::fallible::{TryContextState, TryContextTransition};
+    /// # struct FooState;
+    /// # struct BarState;
+    /// # struct Context { armed: bool }
+    /// # impl TryContextState<Context> for FooState {
+    /// #      type Error = ();
+    /// # };
+    /// # impl Into<BarState> for FooState {
+    /// #     fn into(self) -> BarState {
+    /// #         BarState{}
+    /// #     }
+    /// # }
+    /// #
+    /// # impl TryContextTransition<BarState, Context> for FooState {
+    ///     fn guard(&self, context: &Context) -> TransitGuard {
+    ///         context.armed.into()
+    ///     }
+    /// # }
+    /// ```
+    fn guard(&self, context: &Context) -> TransitGuard;
+}
+
+/// Trait that must be implemented by a state of a fallible state machine with a context that
+/// transitions to DestinationState once an Event is handled.
+///
+/// Behaves like the ``` TryEventTransition ``` trait, but additionally receives the context of the
+/// state machine.
+pub trait TryContextEventTransition<DestinationState, Event, Context>:
+    Into<DestinationState> + TryContextState<Context>
+{
+    /// Implement any behavior that has to be executed when the event causes the transition.
+    /// Return ``` Ok(()) ``` if no error occurred or ``` Err(Self::Error) ``` if something happened.
+    fn try_action(&mut self, _event: &Event, _context: &mut Context) -> Result<(), Self::Error> { Ok(()) }
+
+    /// Specifies if the state has to transit once the event arrives. Transits by default.
+    fn guard(&self, _event: &Event, _context: &Context) -> TransitGuard { TransitGuard::Transit }
+}
+
+/// This trait must be implemented by the error state of a fallible state machine with a context.
+///
+/// Behaves like the ``` TryErrorState ``` trait, but additionally receives the context of the
+/// state machine.
+pub trait TryContextErrorState<Context>: TryContextState<Context> {
+    /// Handle the incoming error
+    /// ```rust
+    /// # use sfsm_base::fallible::{TryContextState, TryContextErrorState};
+    /// # struct ErrorState;
+    /// # struct Context { errors: u32 }
+    /// # impl TryContextState<Context> for ErrorState {
+    /// #      type Error = ();
+    /// # };
+    /// #
+    /// # impl TryContextErrorState<Context> for ErrorState {
+    ///     fn consume_error(&mut self, err: Self::Error, context: &mut Context) {
+    ///         context.errors += 1;
+    ///     }
+    /// # }
+    /// ```
+    fn consume_error(&mut self, err: Self::Error, context: &mut Context);
+}
+
 /// This trait must be implemented by the error state.
 ///
 /// The error is being injected into the error state after it has been generated and the
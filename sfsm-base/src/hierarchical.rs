@@ -0,0 +1,24 @@
+/// Trait that must be implemented by all composite states
+///
+/// A composite state contains its own sub-states. Every time the composite state is entered,
+/// the initial sub-state is created from it and entered right after the composite state itself.
+/// The trait has to be implemented in addition to the ``` State ``` or ``` TryState ``` trait.
+pub trait CompositeState<InitialSubState> {
+    /// Creates the initial sub-state after the composite state has been entered.
+    ///
+    /// ```rust
+    /// # use sfsm_base::hierarchical::CompositeState;
+    /// # struct Online {
+    /// #     retries: u32,
+    /// # }
+    /// # struct Standby {
+    /// #     retries: u32,
+    /// # }
+    /// # impl CompositeState<Standby> for Online {
+    ///     fn initial_state(&mut self) -> Standby {
+    ///         Standby { retries: self.retries }
+    ///     }
+    /// # }
+    /// ```
+    fn initial_state(&mut self) -> InitialSubState;
+}
@@ -10,6 +10,19 @@ pub mod non_fallible;
 /// Contains definitions and code for the messaging system
 pub mod message;
 
+/// Contains definitions for hierarchical state machines with composite states
+pub mod hierarchical;
+
+/// Contains definitions for timeout transitions
+pub mod timing;
+
+/// Contains definitions for state machines whose states can await
+#[cfg(feature = "async")]
+pub mod asynchronous;
+
+/// Contains definitions for persisting the state of a state machine in a compact binary format
+pub mod persist;
+
 /// Enum used to indicate to the guard function if the transition should transit to the
 /// next state or remain in the current one.
 /// ```rust
@@ -43,6 +56,20 @@ pub enum TransitGuard {
     Transit
 }
 
+/// The outcome of a single step of the state machine
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepOutcome<StateId> {
+    /// No transition was taken. Contains the active state of the outer state machine.
+    Stayed(StateId),
+    /// A transition was taken from one state to another
+    Transitioned {
+        /// The state that was left
+        from: StateId,
+        /// The state that was entered
+        to: StateId,
+    },
+}
+
 /// Implements from<bool> trait for use of use.
 /// This allows to transit by returning true. Which simplify the code since it allows to return the
 /// TransitGuard from a simple comparison.
@@ -78,6 +105,7 @@ impl From<bool> for TransitGuard {
 /// Contains traits that are used to interact with the state machine but should not be implemented
 /// manually. All necessary implementations will be created by the macros.
 pub mod __protected {
+    use crate::StepOutcome;
 
     /// Trait that will be implemented for the state machine.
     pub trait StateMachine {
@@ -91,24 +119,126 @@ pub mod __protected {
         /// The generator enum containing all states
         type StatesEnum;
 
+        /// The generated enum identifying all states and sub-states without their data
+        type StateId;
+
         /// Start function that must be called first. It populates the internal enum with the
         /// initial state. If step is called before start, the state machine will return an error.
+        /// A stopped state machine can be started again.
         fn start(&mut self, state: Self::InitialState) -> Result<(), Self::Error>;
 
         /// The step function that executes all states and transitions.
         fn step(&mut self) -> Result<(), Self::Error>;
 
-        /// If desired, the state machine can be stopped. When doing so, the internal states enum
-        /// is returned.
-        fn stop(self) -> Result<Self::StatesEnum, Self::Error>;
+        /// Same as step, but reports if the state machine stayed in its state or transited.
+        /// If multiple transitions are taken by the sub-states of a composite state, the first
+        /// one is reported.
+        ///
+        /// ```rust,ignore
+        /// if let StepOutcome::Transitioned { from, to } = sfsm.step_report()? {
+        ///     println!("Transited from {:?} to {:?}", from, to);
+        /// }
+        /// ```
+        fn step_report(&mut self) -> Result<StepOutcome<Self::StateId>, Self::Error>;
+
+        /// Steps the state machine until a step takes no transition anymore and returns the
+        /// number of transitions that were taken. Every step executes the active state again.
+        /// If more than ``` max_transitions ``` transitions are taken, the stepping is aborted
+        /// with a ``` TransitionLimitExceeded ``` error. This catches states that keep transiting
+        /// forever. Whether the state machine is stable is only known after another step, so the
+        /// transition that exceeds the limit has already been taken when the error is returned.
+        fn step_until_stable(&mut self, max_transitions: usize) -> Result<usize, Self::Error>;
+
+        /// If desired, the state machine can be stopped. When doing so, the active state is left
+        /// and returned in the internal states enum. The state machine remains in the
+        /// ``` Stopped ``` entry of the enum until it is started again. If a hook fails while
+        /// stopping, the error is returned and the state machine stays in the active state.
+        fn stop(&mut self) -> Result<Self::StatesEnum, Self::Error>;
+
+        /// Leaves the active state and starts the state machine again with the given initial
+        /// state. Only the exit hooks of the active state and its sub-states run, the innermost
+        /// sub-states first, but neither transition actions nor the stop hooks. If an exit hook
+        /// fails, the error is returned and the state machine stays in the active state.
+        ///
+        /// ```rust,ignore
+        /// sfsm.reset(WaitForLaunch {})?;
+        /// ```
+        fn reset(&mut self, state: Self::InitialState) -> Result<(), Self::Error>;
+
+        /// Peek the internal states enum.
+        fn peek_state(&self) -> &Self::StatesEnum;
+    }
+
+    /// Trait that will be implemented for the state machines generated with
+    /// ``` add_async_state_machine! ``` and ``` add_async_fallible_state_machine! ```. It behaves
+    /// like the ``` StateMachine ``` trait, but awaits the async functions of the states.
+    #[cfg(feature = "async")]
+    #[allow(async_fn_in_trait)]
+    pub trait AsyncStateMachine {
+        /// The initial state of the state machine.
+        type InitialState;
+
+        /// The returned error.
+        type Error;
+
+        /// The generator enum containing all states
+        type StatesEnum;
+
+        /// The generated enum identifying all states and sub-states without their data
+        type StateId;
+
+        /// Start function that must be awaited first. It populates the internal enum with the
+        /// initial state.
+        async fn start(&mut self, state: Self::InitialState) -> Result<(), Self::Error>;
+
+        /// The step function that executes all states and transitions.
+        async fn step(&mut self) -> Result<(), Self::Error>;
+
+        /// Same as step, but reports if the state machine stayed in its state or transited.
+        async fn step_report(&mut self) -> Result<StepOutcome<Self::StateId>, Self::Error>;
+
+        /// Steps the state machine until a step takes no transition anymore and returns the
+        /// number of transitions that were taken. Like in the ``` StateMachine ``` trait, the
+        /// transition that exceeds the limit has already been taken when the error is returned.
+        async fn step_until_stable(&mut self, max_transitions: usize) -> Result<usize, Self::Error>;
+
+        /// Stops the state machine and returns the internal states enum. The state machine can
+        /// be started again afterwards. If a hook fails, the state machine stays in the active
+        /// state.
+        async fn stop(&mut self) -> Result<Self::StatesEnum, Self::Error>;
+
+        /// Leaves the active state and starts the state machine again with the given initial
+        /// state. Like a reset of the ``` StateMachine ``` trait, only the exit hooks run.
+        async fn reset(&mut self, state: Self::InitialState) -> Result<(), Self::Error>;
 
         /// Peek the internal states enum.
         fn peek_state(&self) -> &Self::StatesEnum;
     }
 
+    /// An implementation of this trait will be generated for every state of the state machine,
+    /// but not for the sub-states of composite states. It starts the state machine in the given
+    /// state instead of the initial state.
+    pub trait StartState<State>: StateMachine {
+        /// Enters the state like start enters the initial state. The generated ``` start_in ```
+        /// method of the state machine calls it without the turbo fish syntax on the trait:
+        ///
+        /// ```rust,ignore
+        /// sfsm.start_in(Launch {})?;
+        /// ```
+        fn start_state(&mut self, state: State) -> Result<(), Self::Error>;
+    }
+
+    /// Same as ``` StartState ```, but implemented for async state machines.
+    #[cfg(feature = "async")]
+    #[allow(async_fn_in_trait)]
+    pub trait AsyncStartState<State>: AsyncStateMachine {
+        /// Enters the state like start enters the initial state.
+        async fn start_state(&mut self, state: State) -> Result<(), Self::Error>;
+    }
+
     /// An implementation of this trait will be generated for every state.
     /// This is can be used to test if the state machine is in a desired state.
-    pub trait IsState<State>: StateMachine {
+    pub trait IsState<State> {
         /// The method must be called with the turbo fish syntax as otherwise Rust cannot figure out
         /// which implementation to call. To check if the state machine is in a given state call:
         ///
@@ -117,12 +247,48 @@ pub mod __protected {
         /// ```
         fn is_state(&self) -> bool;
     }
+
+    /// An implementation of this trait will be generated for every state.
+    /// This can be used to access the data of the state while the state machine is in it.
+    pub trait GetState<State> {
+        /// Returns the state if the state machine is in it. The generated ``` get ``` method of
+        /// the state machine calls it without the turbo fish syntax on the trait:
+        ///
+        /// ```rust,ignore
+        /// let state: Option<&State> = sfsm.get::<State>();
+        /// ```
+        fn get_state(&self) -> Option<&State>;
+
+        /// Same as get_state, but returns a mutable reference to the state.
+        fn get_state_mut(&mut self) -> Option<&mut State>;
+    }
+
+    /// An implementation of this trait will be generated for every state machine that has at
+    /// least one transition bound to an event with ``` Foo => Bar on Event ```.
+    pub trait HandleEvent: StateMachine {
+        /// The generated enum containing all events known to the state machine.
+        type Events;
+
+        /// Passes an event to the active state. Only the transitions of the active state that
+        /// are bound to this event are evaluated. If none of them transits, the event is dropped.
+        /// Other than step, this does not execute the active state.
+        ///
+        /// ```rust,ignore
+        /// sfsm.handle(Ignite { thrust: 100 }.into())?;
+        /// ```
+        fn handle(&mut self, event: Self::Events) -> Result<(), Self::Error>;
+    }
 }
 
 pub use __protected::*;
 pub use non_fallible::*;
 pub use fallible::*;
 pub use message::*;
+pub use hierarchical::*;
+pub use timing::*;
+#[cfg(feature = "async")]
+pub use asynchronous::*;
+pub use persist::*;
 pub use message::__protected::*;
 
 
@@ -12,14 +12,13 @@ pub enum MessageError<T> {
 
 pub mod __protected {
     use crate::message::MessageError;
-    use crate::__protected::StateMachine;
-    use crate::{ReturnMessage, ReceiveMessage, State};
+    use crate::{ReturnMessage, ReceiveMessage};
 
     /// The PushMessage trait implementation will be generated by the add_message! macro and is used
     /// to send messages into the state machine where they will then be forwarded to the correct
     /// state.
-    pub trait PushMessage<TargetState, Message>: StateMachine
-        where TargetState: State + ReceiveMessage<Message>
+    pub trait PushMessage<TargetState, Message>
+        where TargetState: ReceiveMessage<Message>
     {
         /// This will call the receive_message function of ``` FooState ``` if it implemented the ReceiveMessage
         /// trait for message 'FooMessage' and it has been declared to do so with the add_message! macro.
@@ -33,8 +32,8 @@ pub mod __protected {
 
     /// The PollMessage trait implementation will be generated by the add_message! macro and is used
     /// to return messages from states.
-    pub trait PollMessage<TargetState, Message>: StateMachine
-        where TargetState: State + ReturnMessage<Message>
+    pub trait PollMessage<TargetState, Message>
+        where TargetState: ReturnMessage<Message>
     {
         /// This will call the return_message function of ``` FooState ``` if it implemented the ReturnMessage
         /// trait for message 'FooMessage' and it has been declared to do so with the add_message! macro.
@@ -13,6 +13,12 @@ pub enum SfsmError {
     /// Returned if the state machine gets stuck due to an internal error or if the state
     /// machine has not been started before stepping.
     Internal,
+    /// Returned by ``` step_until_stable ``` if the state machine takes more transitions than
+    /// allowed without becoming stable.
+    TransitionLimitExceeded,
+    /// Returned by ``` start_in ``` if the state machine is already running. It has to be
+    /// stopped or reset instead.
+    AlreadyStarted,
 }
 
 /// Trait that must be implemented by all states
@@ -62,6 +68,20 @@ pub trait State {
     /// # }
     /// ```
     fn exit(&mut self) {}
+
+    /// Implement any behavior that has to be executed when the state machine is stopped while
+    /// this state is active. It is called after the exit function.
+    ///
+    /// ```rust
+    /// # use sfsm_base::non_fallible::State;
+    /// # struct FooState;
+    /// # impl State for FooState {
+    ///     fn on_stop(&mut self) {
+    ///         println!("Called when the state machine is stopped");
+    ///     }
+    /// # }
+    /// ```
+    fn on_stop(&mut self) {}
 }
 
 /// Trait that must be implemented by a state that want to transition to DestinationState.
@@ -70,7 +90,10 @@ pub trait State {
 /// On top of the transition trait the state must implement the ``` Into<DestinationState> ```
 /// trait to specify what happens with the source state data while transitioning and how the
 /// destination state is generated.
-/// The action method is run once the transition executes.
+/// The action method is run once the transition executes. By default, the actions of all
+/// transitions of a state are run whenever the state is left or the state machine is stopped.
+/// With the ``` #[sfsm(exclusive_actions)] ``` option, only the action of the transition that is
+/// actually taken is run.
 /// The only non optional method is the guard function that specifies when the state transitions.
 pub trait Transition<DestinationState>: Into<DestinationState> + State {
     /// Implement any behavior that hast to be executed when exiting the state.
@@ -122,3 +145,160 @@ pub trait Transition<DestinationState>: Into<DestinationState> + State {
     /// ```
     fn guard(&self) -> TransitGuard;
 }
+
+/// Trait that must be implemented by a state that transitions to DestinationState once an Event
+/// is handled.
+///
+/// Other than the ``` Transition ``` trait, the guard of an event transition is not polled
+/// during every step. It is only evaluated when the event is passed to the state machine with
+/// the ``` HandleEvent::handle ``` function and it has access to the payload of the event.
+/// If the guard is not implemented, the transition will always transit once the event arrives.
+pub trait EventTransition<DestinationState, Event>: Into<DestinationState> + State {
+    /// Implement any behavior that has to be executed when the event causes the transition.
+    /// ```rust
+    /// # use sfsm_base::non_fallible::{EventTransition, State};
+    /// # struct FooState;
+    /// # struct BarState;
+    /// # struct Ignite { thrust: u32 }
+    /// # impl State for FooState {};
+    /// # impl Into<BarState> for FooState {
+    /// #     fn into(self) -> BarState { BarState{} }
+    /// # }
+    ///
+    /// # impl EventTransition<BarState, Ignite> for FooState {
+    ///     fn action(&mut self, event: &Ignite) {
+    ///         println!("Ignited with a thrust of {}", event.thrust);
+    ///     }
+    /// # }
+    /// ```
+    fn action(&mut self, _event: &Event) {}
+
+    /// Specifies if the state has to transit once the event arrives. Return
+    /// ``` TransitGuard::Remain ``` to ignore the event and ``` TransitGuard::Transit ``` to
+    /// transit into the next state.
+    /// ```rust
+    /// # use sfsm_base::non_fallible::{EventTransition, State};
+    /// # use sfsm_base::TransitGuard;
+    /// # struct FooState;
+    /// # struct BarState;
+    /// # struct Ignite { thrust: u32 }
+    /// # impl State for FooState {};
+    /// # impl Into<BarState> for FooState {
+    /// #     fn into(self) -> BarState { BarState{} }
+    /// # }
+    ///
+    /// # impl EventTransition<BarState, Ignite> for FooState {
+    ///     fn guard(&self, event: &Ignite) -> TransitGuard {
+    ///         (event.thrust > 100).into()
+    ///     }
+    /// # }
+    /// ```
+    fn guard(&self, _event: &Event) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
+
+/// Trait that must be implemented by all states of a state machine with a context.
+///
+/// Behaves like the ``` State ``` trait, but every function additionally receives a mutable
+/// reference to the context that is owned by the state machine. The context can be used to share
+/// data like drivers, configurations or counters between all states without having to move it
+/// through every transition.
+pub trait ContextState<Context> {
+    /// Implement any behavior that hast to be executed when entering the state.
+    ///
+    /// ```rust
+    /// # use sfsm_base::non_fallible::ContextState;
+    /// # struct FooState;
+    /// # struct Context { entries: u32 }
+    /// # impl ContextState<Context> for FooState {
+    ///     fn entry(&mut self, context: &mut Context) {
+    ///         context.entries += 1;
+    ///     }
+    /// # }
+    /// ```
+    fn entry(&mut self, _context: &mut Context) {}
+
+    /// Implement any behavior that has to be executed when the state is being executed.
+    /// This function will be called as long as the state does not transit.
+    ///
+    /// ```rust
+    /// # use sfsm_base::non_fallible::ContextState;
+    /// # struct FooState;
+    /// # struct Context { steps: u32 }
+    /// # impl ContextState<Context> for FooState {
+    ///     fn execute(&mut self, context: &mut Context) {
+    ///         context.steps += 1;
+    ///     }
+    /// # }
+    /// ```
+    fn execute(&mut self, _context: &mut Context) {}
+
+    /// Implement any behavior that hast to be executed when exiting the state.
+    ///
+    /// ```rust
+    /// # use sfsm_base::non_fallible::ContextState;
+    /// # struct FooState;
+    /// # struct Context { exits: u32 }
+    /// # impl ContextState<Context> for FooState {
+    ///     fn exit(&mut self, context: &mut Context) {
+    ///         context.exits += 1;
+    ///     }
+    /// # }
+    /// ```
+    fn exit(&mut self, _context: &mut Context) {}
+
+    /// Implement any behavior that has to be executed when the state machine is stopped while
+    /// this state is active. It is called after the exit function.
+    fn on_stop(&mut self, _context: &mut Context) {}
+}
+
+/// Trait that must be implemented by a state of a state machine with a context that wants to
+/// transition to DestinationState.
+///
+/// Behaves like the ``` Transition ``` trait, but the action receives a mutable and the guard an
+/// immutable reference to the context of the state machine.
+pub trait ContextTransition<DestinationState, Context>:
+    Into<DestinationState> + ContextState<Context>
+{
+    /// Implement any behavior that hast to be executed when transitioning to the next state.
+    fn action(&mut self, _context: &mut Context) {}
+
+    /// Specifies when the state has to transit. Return ``` TransitGuard::Remain ``` to remain
+    /// in the current state and ``` TransitGuard::Transit ``` to transit into the next one.
+    /// ```rust
+    /// # use sfsm_base::non_fallible::{ContextTransition, ContextState};
+    /// # use sfsm_base::TransitGuard;
+    /// # struct FooState;
+    /// # struct BarState;
+    /// # struct Context { armed: bool }
+    /// # impl ContextState<Context> for FooState {};
+    /// # impl Into<BarState> for FooState {
+    /// #     fn into(self) -> BarState { BarState{} }
+    /// # }
+    ///
+    /// # impl ContextTransition<BarState, Context> for FooState {
+    ///     fn guard(&self, context: &Context) -> TransitGuard {
+    ///         context.armed.into()
+    ///     }
+    /// # }
+    /// ```
+    fn guard(&self, context: &Context) -> TransitGuard;
+}
+
+/// Trait that must be implemented by a state of a state machine with a context that transitions
+/// to DestinationState once an Event is handled.
+///
+/// Behaves like the ``` EventTransition ``` trait, but additionally receives the context of the
+/// state machine.
+pub trait ContextEventTransition<DestinationState, Event, Context>:
+    Into<DestinationState> + ContextState<Context>
+{
+    /// Implement any behavior that has to be executed when the event causes the transition.
+    fn action(&mut self, _event: &Event, _context: &mut Context) {}
+
+    /// Specifies if the state has to transit once the event arrives. Transits by default.
+    fn guard(&self, _event: &Event, _context: &Context) -> TransitGuard {
+        TransitGuard::Transit
+    }
+}
@@ -0,0 +1,145 @@
+/// Error type that will be returned if a state machine cannot be encoded or decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PersistError {
+    /// The buffer is too small to hold the encoded state machine
+    BufferTooSmall,
+    /// The checksum does not match the data. The data is corrupted or has never been written.
+    InvalidChecksum,
+    /// The data was encoded with a format version that cannot be decoded
+    UnsupportedVersion(u8),
+    /// The state id does not belong to a state of the state machine
+    UnknownState(u8),
+    /// The payload of the state cannot be encoded or decoded
+    InvalidPayload,
+    /// The state machine is stopped or has not been started yet, so there is no state to encode
+    NotStarted,
+}
+
+/// Trait that must be implemented by all states of a state machine with the persist option
+///
+/// The state machine encodes the id of the active state and the format version itself. The
+/// state only encodes its own payload and creates itself from it again.
+/// ```rust
+/// # use sfsm_base::persist::{Persist, PersistError};
+/// struct Heating {
+///     target: u16,
+/// }
+///
+/// impl Persist for Heating {
+///     fn encode(&self, payload: &mut [u8]) -> Result<usize, PersistError> {
+///         let bytes = self.target.to_le_bytes();
+///         payload.get_mut(..2).ok_or(PersistError::BufferTooSmall)?.copy_from_slice(&bytes);
+///         Ok(2)
+///     }
+///
+///     fn decode(payload: &[u8], version: u8) -> Result<Self, PersistError> {
+///         match (version, payload) {
+///             // The first version stored the target in a single byte
+///             (1, [target]) => Ok(Heating { target: u16::from(*target) }),
+///             (2, [low, high]) => Ok(Heating { target: u16::from_le_bytes([*low, *high]) }),
+///             (1..=2, _) => Err(PersistError::InvalidPayload),
+///             _ => Err(PersistError::UnsupportedVersion(version)),
+///         }
+///     }
+/// }
+/// ```
+pub trait Persist: Sized {
+    /// Writes the payload of the state into the buffer and returns the number of bytes written.
+    /// States without a payload do not have to implement it.
+    fn encode(&self, _payload: &mut [u8]) -> Result<usize, PersistError> {
+        Ok(0)
+    }
+
+    /// Creates the state from a payload that was encoded with the given format version. Payloads
+    /// of older versions can be migrated or rejected with ``` PersistError::UnsupportedVersion ```.
+    fn decode(payload: &[u8], version: u8) -> Result<Self, PersistError>;
+}
+
+/// The decoded frame of a persisted state machine
+#[derive(Debug, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// The format version the state machine was encoded with
+    pub version: u8,
+    /// The id of the active state
+    pub state_id: u8,
+    /// The payload of the active state
+    pub payload: &'a [u8],
+}
+
+// The version, the state id and the length of the payload
+const HEADER_LEN: usize = 4;
+const CRC_LEN: usize = 2;
+
+/// Encodes a frame into the buffer and returns the number of bytes written. The frame consists of
+/// the format version, the state id, the length of the payload as little endian u16, the payload
+/// and a CRC-16/CCITT-FALSE of all previous bytes as little endian u16.
+/// ```rust
+/// # use sfsm_base::persist::{decode_frame, encode_frame, Frame};
+/// let mut buffer = [0u8; 16];
+/// let len = encode_frame(&mut buffer, 1, 3, |payload| {
+///     payload[0] = 42;
+///     Ok(1)
+/// }).unwrap();
+/// assert_eq!(len, 7);
+/// assert_eq!(decode_frame(&buffer), Ok(Frame { version: 1, state_id: 3, payload: &[42] }));
+/// ```
+pub fn encode_frame<F>(buffer: &mut [u8], version: u8, state_id: u8, payload: F) -> Result<usize, PersistError>
+    where F: FnOnce(&mut [u8]) -> Result<usize, PersistError>
+{
+    if buffer.len() < HEADER_LEN + CRC_LEN {
+        return Err(PersistError::BufferTooSmall);
+    }
+    let capacity = buffer.len() - HEADER_LEN - CRC_LEN;
+    let payload_len = payload(&mut buffer[HEADER_LEN..HEADER_LEN + capacity])?;
+    if payload_len > capacity || payload_len > u16::MAX as usize {
+        return Err(PersistError::BufferTooSmall);
+    }
+
+    buffer[0] = version;
+    buffer[1] = state_id;
+    buffer[2..HEADER_LEN].copy_from_slice(&(payload_len as u16).to_le_bytes());
+    let crc_start = HEADER_LEN + payload_len;
+    let crc = crc16(&buffer[..crc_start]);
+    buffer[crc_start..crc_start + CRC_LEN].copy_from_slice(&crc.to_le_bytes());
+    Ok(crc_start + CRC_LEN)
+}
+
+/// Decodes a frame that was encoded with ``` encode_frame ```. Bytes after the frame are ignored,
+/// so the buffer can be a whole flash page.
+pub fn decode_frame(buffer: &[u8]) -> Result<Frame<'_>, PersistError> {
+    if buffer.len() < HEADER_LEN + CRC_LEN {
+        return Err(PersistError::BufferTooSmall);
+    }
+    let payload_len = u16::from_le_bytes([buffer[2], buffer[3]]) as usize;
+    let crc_start = HEADER_LEN + payload_len;
+    // An erased or corrupted length points past the end of the buffer
+    if crc_start + CRC_LEN > buffer.len() {
+        return Err(PersistError::InvalidChecksum);
+    }
+    let crc = u16::from_le_bytes([buffer[crc_start], buffer[crc_start + 1]]);
+    if crc != crc16(&buffer[..crc_start]) {
+        return Err(PersistError::InvalidChecksum);
+    }
+    Ok(Frame {
+        version: buffer[0],
+        state_id: buffer[1],
+        payload: &buffer[HEADER_LEN..crc_start],
+    })
+}
+
+// CRC-16/CCITT-FALSE. Computed bitwise to avoid a lookup table in the flash.
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
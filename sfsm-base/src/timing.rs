@@ -0,0 +1,66 @@
+/// Trait that must be implemented by the clock of a state machine with timeout transitions
+///
+/// The clock returns monotonic ticks. The state machine records the ticks when a state is
+/// entered and compares them to the current ticks every time it is stepped. The ticks are
+/// allowed to wrap around.
+/// ```rust
+/// # use sfsm_base::timing::Clock;
+/// struct SysTick {
+///     ticks: u64,
+/// }
+///
+/// impl Clock for SysTick {
+///     const TICKS_PER_SECOND: u64 = 1000;
+///
+///     fn now(&self) -> u64 {
+///         self.ticks
+///     }
+/// }
+/// ```
+pub trait Clock {
+    /// The number of ticks that pass in one second. It is used to convert the durations of
+    /// the timeout transitions into ticks.
+    const TICKS_PER_SECOND: u64;
+
+    /// Returns the current ticks of the clock.
+    fn now(&self) -> u64;
+}
+
+/// A clock that only advances when it is told to. Useful to test timeout transitions
+/// deterministically.
+/// ```rust
+/// # use sfsm_base::timing::{Clock, MockClock};
+/// let mut clock = MockClock::new();
+/// clock.advance(500);
+/// assert_eq!(clock.now(), 500);
+/// ```
+#[derive(Default)]
+pub struct MockClock {
+    ticks: u64,
+}
+
+impl MockClock {
+    /// Creates a new clock starting at zero ticks.
+    pub fn new() -> Self {
+        Self { ticks: 0 }
+    }
+
+    /// Advances the clock by the given number of ticks.
+    pub fn advance(&mut self, ticks: u64) {
+        self.ticks = self.ticks.wrapping_add(ticks);
+    }
+
+    /// Sets the clock to the given number of ticks.
+    pub fn set(&mut self, ticks: u64) {
+        self.ticks = ticks;
+    }
+}
+
+/// The mock clock counts in milliseconds
+impl Clock for MockClock {
+    const TICKS_PER_SECOND: u64 = 1000;
+
+    fn now(&self) -> u64 {
+        self.ticks
+    }
+}
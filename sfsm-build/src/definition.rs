@@ -0,0 +1,490 @@
+use crate::node::{Entry, Node, ParseError};
+use std::fmt::Write;
+
+/// The definition of a state machine as it is read from a TOML or YAML file
+pub struct Definition {
+    pub name: String,
+    pub visibility: Option<String>,
+    pub initial: String,
+    pub states: Vec<StateDefinition>,
+    pub transitions: Vec<TransitionDefinition>,
+    /// The error type and the error state of a fallible state machine
+    pub error: Option<(String, String)>,
+    pub messages: Vec<MessageDefinition>,
+}
+
+pub struct StateDefinition {
+    pub name: String,
+    pub is_final: bool,
+}
+
+pub struct TransitionDefinition {
+    pub from: String,
+    pub to: String,
+    /// The trigger of the transition like on Event or after 500ms
+    pub trigger: Option<String>,
+}
+
+pub struct MessageDefinition {
+    pub message: String,
+    pub state: String,
+    /// Set if the message is pushed into the state, otherwise it is polled from the state
+    pub push: bool,
+}
+
+/// The keys of a table that have been checked against the allowed and required keys
+struct Fields<'a> {
+    entries: &'a [Entry],
+}
+
+impl<'a> Fields<'a> {
+    fn new(node: &'a Node, allowed: &[&str], required: &[&str]) -> Result<Self, ParseError> {
+        let entries = node.as_mapping()?;
+        for (index, entry) in entries.iter().enumerate() {
+            if !allowed.contains(&entry.key.as_str()) {
+                return Err((
+                    entry.position,
+                    format!(
+                        "Unknown key '{}'. Expected one of: {}",
+                        entry.key,
+                        allowed.join(", ")
+                    ),
+                ));
+            }
+            if entries[..index].iter().any(|known| known.key == entry.key) {
+                return Err((
+                    entry.position,
+                    format!("The key '{}' is defined more than once", entry.key),
+                ));
+            }
+        }
+        if let Some(missing) = required
+            .iter()
+            .find(|key| !entries.iter().any(|entry| entry.key == **key))
+        {
+            return node.error(&format!("Missing the key '{}'", missing));
+        }
+        Ok(Self { entries })
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Node> {
+        self.entries
+            .iter()
+            .find(|entry| entry.key == key)
+            .map(|entry| &entry.value)
+    }
+
+    fn name(&self, key: &str) -> Result<Option<String>, ParseError> {
+        self.get(key).map(type_name).transpose()
+    }
+
+    fn required_name(&self, key: &str) -> Result<String, ParseError> {
+        // Required keys are checked when the fields are created
+        type_name(self.get(key).expect("required key"))
+    }
+
+    fn list(&self, key: &str) -> Result<&'a [Node], ParseError> {
+        self.get(key).map(Node::as_sequence).unwrap_or(Ok(&[]))
+    }
+}
+
+/// Reads the name of a state, event, message or error type. The names are checked well enough
+/// to not break the generated definition. Everything else is checked by the macros.
+fn type_name(node: &Node) -> Result<String, ParseError> {
+    let name = node.as_str()?.trim();
+    let mut depth = 0;
+    for character in name.chars() {
+        match character {
+            '<' => depth += 1,
+            '>' if depth > 0 => depth -= 1,
+            ',' if depth > 0 => {}
+            ':' | '_' | '\'' | '&' | ' ' => {}
+            character if character.is_alphanumeric() => {}
+            _ => return node.error(&format!("'{}' is not a valid type name", name)),
+        }
+    }
+    if name.is_empty() || depth != 0 {
+        return node.error(&format!("'{}' is not a valid type name", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Compares the names of types without the whitespaces that are not significant
+fn same_name(a: &str, b: &str) -> bool {
+    let significant = |name: &str| {
+        name.chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+    };
+    significant(a) == significant(b)
+}
+
+impl Definition {
+    pub fn parse(node: &Node) -> Result<Self, ParseError> {
+        let fields = Fields::new(
+            node,
+            &[
+                "name",
+                "visibility",
+                "initial",
+                "states",
+                "transitions",
+                "error_type",
+                "error_state",
+                "messages",
+            ],
+            &["name", "initial", "states"],
+        )?;
+
+        let visibility = match fields.get("visibility") {
+            Some(node) => match node.as_str()?.trim() {
+                visibility if visibility.starts_with("pub") => Some(visibility.to_string()),
+                _ => return node.error("Expected a visibility like pub or pub(crate)"),
+            },
+            None => None,
+        };
+
+        let states_node = fields.get("states").expect("required key");
+        let mut states: Vec<StateDefinition> = vec![];
+        for state in states_node.as_sequence()? {
+            let state_definition = match state.as_mapping() {
+                Ok(_) => {
+                    let state_fields = Fields::new(state, &["name", "final"], &["name"])?;
+                    StateDefinition {
+                        name: state_fields.required_name("name")?,
+                        is_final: state_fields
+                            .get("final")
+                            .map(Node::as_bool)
+                            .transpose()?
+                            .unwrap_or(false),
+                    }
+                }
+                Err(_) => StateDefinition {
+                    name: type_name(state)?,
+                    is_final: false,
+                },
+            };
+            if states
+                .iter()
+                .any(|known| same_name(&known.name, &state_definition.name))
+            {
+                return state.error(&format!(
+                    "The state '{}' is declared more than once",
+                    state_definition.name
+                ));
+            }
+            states.push(state_definition);
+        }
+        if states.is_empty() {
+            return states_node.error("Expected at least one state");
+        }
+
+        // Checks that a state that is referred to is part of the states
+        let declared = |node: &Node, kind: &str| -> Result<String, ParseError> {
+            let name = type_name(node)?;
+            if states.iter().any(|state| same_name(&state.name, &name)) {
+                Ok(name)
+            } else {
+                node.error(&format!(
+                    "The {} state '{}' is not in the list of states",
+                    kind, name
+                ))
+            }
+        };
+
+        let initial = declared(fields.get("initial").expect("required key"), "initial")?;
+
+        let error = match (fields.get("error_type"), fields.get("error_state")) {
+            (Some(error_type), Some(error_state)) => {
+                Some((type_name(error_type)?, declared(error_state, "error")?))
+            }
+            (Some(node), None) => return node.error("The error type requires an error_state"),
+            (None, Some(node)) => return node.error("The error state requires an error_type"),
+            (None, None) => None,
+        };
+
+        let mut transitions = vec![];
+        for transition in fields.list("transitions")? {
+            let transition_fields =
+                Fields::new(transition, &["from", "to", "on", "after"], &["from", "to"])?;
+            let trigger = match (
+                transition_fields.name("on")?,
+                transition_fields.get("after"),
+            ) {
+                (Some(_), Some(node)) => {
+                    return node.error("A transition cannot have an event and a delay")
+                }
+                (Some(event), None) => Some(format!("on {}", event)),
+                (None, Some(node)) => {
+                    let after = node.as_str()?.trim();
+                    let is_duration = after.starts_with(|c: char| c.is_ascii_digit())
+                        && after.chars().all(|c| c.is_ascii_alphanumeric());
+                    if after != "completion" && !is_duration {
+                        return node
+                            .error(&format!("'{}' is no delay like 500ms or completion", after));
+                    }
+                    Some(format!("after {}", after))
+                }
+                (None, None) => None,
+            };
+            transitions.push(TransitionDefinition {
+                from: declared(
+                    transition_fields.get("from").expect("required key"),
+                    "source",
+                )?,
+                to: declared(transition_fields.get("to").expect("required key"), "target")?,
+                trigger,
+            });
+        }
+
+        let mut messages = vec![];
+        for message in fields.list("messages")? {
+            let message_fields = Fields::new(message, &["message", "push", "poll"], &["message"])?;
+            let (state, push) = match (message_fields.get("push"), message_fields.get("poll")) {
+                (Some(state), None) => (declared(state, "receiving")?, true),
+                (None, Some(state)) => (declared(state, "returning")?, false),
+                _ => {
+                    return message.error(
+                        "A message must either be pushed into a state or polled from a state",
+                    )
+                }
+            };
+            messages.push(MessageDefinition {
+                message: message_fields.required_name("message")?,
+                state,
+                push,
+            });
+        }
+
+        Ok(Self {
+            name: fields.required_name("name")?,
+            visibility,
+            initial,
+            states,
+            transitions,
+            error,
+            messages,
+        })
+    }
+
+    /// Writes the definition as invocations of the add_state_machine! and add_messages! macros
+    pub fn to_rust(&self) -> String {
+        let mut code = String::new();
+        writeln!(code, "// Generated by sfsm-build. Do not edit.").unwrap();
+        writeln!(code).unwrap();
+
+        let macro_name = match self.error {
+            Some(_) => "add_fallible_state_machine",
+            None => "add_state_machine",
+        };
+        writeln!(code, "sfsm::{}!(", macro_name).unwrap();
+        match &self.visibility {
+            Some(visibility) => writeln!(code, "    {} {},", visibility, self.name).unwrap(),
+            None => writeln!(code, "    {},", self.name).unwrap(),
+        }
+        writeln!(code, "    {},", self.initial).unwrap();
+
+        writeln!(code, "    [").unwrap();
+        for state in self.states.iter() {
+            let attribute = if state.is_final {
+                "#[sfsm(final)] "
+            } else {
+                ""
+            };
+            writeln!(code, "        {}{},", attribute, state.name).unwrap();
+        }
+        writeln!(code, "    ],").unwrap();
+
+        writeln!(code, "    [").unwrap();
+        for transition in self.transitions.iter() {
+            let trigger = transition
+                .trigger
+                .as_ref()
+                .map(|trigger| format!(" {}", trigger))
+                .unwrap_or_default();
+            writeln!(
+                code,
+                "        {} => {}{},",
+                transition.from, transition.to, trigger
+            )
+            .unwrap();
+        }
+        match &self.error {
+            Some((error_type, error_state)) => {
+                writeln!(code, "    ],").unwrap();
+                writeln!(code, "    {},", error_type).unwrap();
+                writeln!(code, "    {}", error_state).unwrap();
+            }
+            None => writeln!(code, "    ]").unwrap(),
+        }
+        writeln!(code, ");").unwrap();
+
+        if !self.messages.is_empty() {
+            writeln!(code).unwrap();
+            writeln!(code, "sfsm::add_messages!(").unwrap();
+            writeln!(code, "    {},", self.name).unwrap();
+            writeln!(code, "    [").unwrap();
+            for message in self.messages.iter() {
+                let arrow = if message.push { "->" } else { "<-" };
+                writeln!(
+                    code,
+                    "        {} {} {},",
+                    message.message, arrow, message.state
+                )
+                .unwrap();
+            }
+            writeln!(code, "    ]").unwrap();
+            writeln!(code, ");").unwrap();
+        }
+        code
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::Definition;
+    use crate::node::{Node, ParseError, Position};
+
+    /// Parses the source and returns the position and message of the first error
+    fn error(parse: fn(&str) -> Result<Node, ParseError>, source: &str) -> (Position, String) {
+        match parse(source).and_then(|node| Definition::parse(&node)) {
+            Ok(_) => panic!("Expected the definition to be rejected"),
+            Err(error) => error,
+        }
+    }
+
+    fn toml_error(source: &str) -> (Position, String) {
+        error(crate::toml::parse, source)
+    }
+
+    fn yaml_error(source: &str) -> (Position, String) {
+        error(crate::yaml::parse, source)
+    }
+
+    fn at(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+
+    #[test]
+    fn valid_definitions() {
+        let toml = "name = 'Rocket'\n\
+                    initial = 'Idle'\n\
+                    states = ['Idle', { name = 'Launch', final = true }]\n\
+                    [[transitions]]\n\
+                    from = 'Idle'\n\
+                    to = 'Launch'\n\
+                    after = '500ms'\n";
+        let yaml = "name: Rocket\n\
+                    initial: Idle\n\
+                    states: [Idle, { name: Launch, final: true }]\n\
+                    transitions:\n  \
+                      - { from: Idle, to: Launch, after: 500ms }\n";
+        for node in [crate::toml::parse(toml), crate::yaml::parse(yaml)] {
+            let definition = node.and_then(|node| Definition::parse(&node)).ok().unwrap();
+            assert_eq!(definition.name, "Rocket");
+            assert!(definition.states[1].is_final);
+            assert_eq!(
+                definition.transitions[0].trigger.as_deref(),
+                Some("after 500ms")
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_key() {
+        let (position, message) =
+            toml_error("name = 'Rocket'\ninitial = 'Idle'\nstates = ['Idle']\nstate = 'Idle'\n");
+        assert_eq!(position, at(4, 1));
+        assert!(message.starts_with("Unknown key 'state'"));
+
+        let (position, message) =
+            yaml_error("name: Rocket\ninitial: Idle\nstates: [Idle]\nstate: Idle\n");
+        assert_eq!(position, at(4, 1));
+        assert!(message.starts_with("Unknown key 'state'"));
+    }
+
+    #[test]
+    fn duplicate_key() {
+        // The TOML parser rejects duplicate keys itself
+        let (position, message) =
+            toml_error("name = 'Rocket'\ninitial = 'Idle'\nstates = ['Idle']\nname = 'Other'\n");
+        assert_eq!(position, at(4, 1));
+        assert!(message.contains("duplicate key"));
+
+        let (position, message) =
+            yaml_error("name: Rocket\ninitial: Idle\nstates: [Idle]\nname: Other\n");
+        assert_eq!(position, at(4, 1));
+        assert_eq!(message, "The key 'name' is defined more than once");
+    }
+
+    #[test]
+    fn undeclared_state() {
+        let (position, message) = toml_error(
+            "name = 'Rocket'\ninitial = 'Idle'\nstates = ['Idle']\n\
+             [[transitions]]\nfrom = 'Idle'\nto = 'Launch'\n",
+        );
+        assert_eq!(position, at(6, 6));
+        assert_eq!(
+            message,
+            "The target state 'Launch' is not in the list of states"
+        );
+
+        let (position, message) = yaml_error(
+            "name: Rocket\ninitial: Idle\nstates: [Idle]\n\
+             transitions:\n  - from: Idle\n    to: Launch\n",
+        );
+        assert_eq!(position, at(6, 9));
+        assert_eq!(
+            message,
+            "The target state 'Launch' is not in the list of states"
+        );
+    }
+
+    #[test]
+    fn event_and_delay() {
+        let (position, message) = toml_error(
+            "name = 'Rocket'\ninitial = 'Idle'\nstates = ['Idle']\n\
+             [[transitions]]\nfrom = 'Idle'\nto = 'Idle'\non = 'Tick'\nafter = '1s'\n",
+        );
+        assert_eq!(position, at(8, 9));
+        assert_eq!(message, "A transition cannot have an event and a delay");
+
+        let (position, message) = yaml_error(
+            "name: Rocket\ninitial: Idle\nstates: [Idle]\n\
+             transitions:\n  - { from: Idle, to: Idle, on: Tick, after: 1s }\n",
+        );
+        assert_eq!(position, at(5, 46));
+        assert_eq!(message, "A transition cannot have an event and a delay");
+    }
+
+    #[test]
+    fn bad_delay() {
+        let (position, message) = toml_error(
+            "name = 'Rocket'\ninitial = 'Idle'\nstates = ['Idle']\n\
+             [[transitions]]\nfrom = 'Idle'\nto = 'Idle'\nafter = 'soon'\n",
+        );
+        assert_eq!(position, at(7, 9));
+        assert_eq!(message, "'soon' is no delay like 500ms or completion");
+
+        let (position, message) = yaml_error(
+            "name: Rocket\ninitial: Idle\nstates: [Idle]\n\
+             transitions:\n  - { from: Idle, to: Idle, after: 5 ms }\n",
+        );
+        assert_eq!(position, at(5, 36));
+        assert_eq!(message, "'5 ms' is no delay like 500ms or completion");
+    }
+
+    #[test]
+    fn error_type_without_error_state() {
+        let (position, message) = toml_error(
+            "name = 'Rocket'\ninitial = 'Idle'\nstates = ['Idle']\nerror_type = 'RocketError'\n",
+        );
+        assert_eq!(position, at(4, 14));
+        assert_eq!(message, "The error type requires an error_state");
+
+        let (position, message) =
+            yaml_error("name: Rocket\ninitial: Idle\nstates: [Idle]\nerror_type: RocketError\n");
+        assert_eq!(position, at(4, 13));
+        assert_eq!(message, "The error type requires an error_state");
+    }
+}
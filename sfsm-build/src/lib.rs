@@ -0,0 +1,151 @@
+//! Generates sfsm state machines from definitions in TOML or YAML files.
+//!
+//! The definitions are read in a build script and written as invocations of the
+//! ``` add_state_machine! ``` and ``` add_messages! ``` macros into the output directory of the
+//! build, where they can be included into the crate.
+//! ```rust,ignore
+//! // build.rs
+//! fn main() {
+//!     if let Err(err) = sfsm_build::compile("machines/rocket.toml") {
+//!         panic!("{}", err);
+//!     }
+//! }
+//!
+//! // src/main.rs
+//! include!(concat!(env!("OUT_DIR"), "/rocket.rs"));
+//! ```
+//! A definition contains the same parts as the macros:
+//! ```toml
+//! name = "Rocket"
+//! initial = "WaitForLaunch"
+//! states = ["WaitForLaunch", "Launch", { name = "Landed", final = true }, "Abort"]
+//! error_type = "RocketError"   # Optional, generates a fallible state machine
+//! error_state = "Abort"
+//!
+//! [[transitions]]
+//! from = "WaitForLaunch"
+//! to = "Launch"
+//! on = "Ignite"                # Optional, or after = "500ms" or after = "completion"
+//!
+//! [[messages]]
+//! message = "StartLaunch"
+//! push = "WaitForLaunch"       # Or poll = "Launch"
+//! ```
+//! YAML files use the same keys. Errors in the definition are reported with the file, line and
+//! column they occurred at.
+
+mod definition;
+mod node;
+mod toml;
+mod yaml;
+
+use crate::definition::Definition;
+use crate::node::ParseError;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The errors that can occur while generating a state machine
+pub enum Error {
+    /// The definition file could not be read or the generated file could not be written
+    Io {
+        file: PathBuf,
+        error: std::io::Error,
+    },
+    /// The file is neither a TOML nor a YAML file
+    UnknownFormat { file: PathBuf },
+    /// The build script runs without the OUT_DIR environment variable
+    MissingOutDir,
+    /// The definition is not valid
+    Definition {
+        file: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { file, error } => write!(f, "{}: {}", file.display(), error),
+            Error::UnknownFormat { file } => write!(
+                f,
+                "{}: Unknown format. Expected a .toml, .yaml or .yml file",
+                file.display()
+            ),
+            Error::MissingOutDir => write!(
+                f,
+                "The OUT_DIR environment variable is not set. Call compile from a build script"
+            ),
+            Error::Definition {
+                file,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", file.display(), line, column, message),
+        }
+    }
+}
+
+// Build scripts usually unwrap the result, so the debug output shows the position as well
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Generates the state machine of the definition file and writes it to the output directory of
+/// the build script. The generated file has the name of the definition file with the rs
+/// extension. Returns the path of the generated file.
+pub fn compile<P: AsRef<Path>>(file: P) -> Result<PathBuf, Error> {
+    let file = file.as_ref();
+    println!("cargo:rerun-if-changed={}", file.display());
+
+    let code = generate_file(file)?;
+    let out_dir = std::env::var_os("OUT_DIR").ok_or(Error::MissingOutDir)?;
+    let stem = file.file_stem().unwrap_or_default();
+    let target = Path::new(&out_dir).join(stem).with_extension("rs");
+    std::fs::write(&target, code).map_err(|error| Error::Io {
+        file: target.clone(),
+        error,
+    })?;
+    Ok(target)
+}
+
+/// Reads the definition file and returns the generated code. The format is selected by the
+/// extension of the file.
+pub fn generate_file<P: AsRef<Path>>(file: P) -> Result<String, Error> {
+    let file = file.as_ref();
+    let source = std::fs::read_to_string(file).map_err(|error| Error::Io {
+        file: file.to_path_buf(),
+        error,
+    })?;
+    generate(file, &source)
+}
+
+/// Generates the code of the definition in the source. The file is used to select the format
+/// and to report errors.
+pub fn generate<P: AsRef<Path>>(file: P, source: &str) -> Result<String, Error> {
+    let file = file.as_ref();
+    let parse = match file.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::parse,
+        Some("yaml") | Some("yml") => yaml::parse,
+        _ => {
+            return Err(Error::UnknownFormat {
+                file: file.to_path_buf(),
+            })
+        }
+    };
+
+    let to_error = |(position, message): ParseError| Error::Definition {
+        file: file.to_path_buf(),
+        line: position.line.max(1),
+        column: position.column.max(1),
+        message,
+    };
+    let node = parse(source).map_err(to_error)?;
+    let definition = Definition::parse(&node).map_err(to_error)?;
+    Ok(definition.to_rust())
+}
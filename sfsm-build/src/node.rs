@@ -0,0 +1,90 @@
+/// The position of a node in the definition file. Lines and columns start at 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Calculates the position of a byte offset in the source
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// An error in the definition file together with the position it occurred at
+pub type ParseError = (Position, String);
+
+/// A value of the definition file with the position it was declared at. TOML and YAML files are
+/// both read into nodes, so they can be validated the same way.
+pub struct Node {
+    pub value: Value,
+    pub position: Position,
+}
+
+pub enum Value {
+    Null,
+    String(String),
+    Bool(bool),
+    Sequence(Vec<Node>),
+    Mapping(Vec<Entry>),
+    /// A value that is not used in definitions like a number or a date
+    Other(&'static str),
+}
+
+/// A key of a mapping with the value that is assigned to it
+pub struct Entry {
+    pub key: String,
+    pub position: Position,
+    pub value: Node,
+}
+
+impl Node {
+    pub fn error<T>(&self, message: &str) -> Result<T, ParseError> {
+        Err((self.position, message.to_string()))
+    }
+
+    pub fn as_str(&self) -> Result<&str, ParseError> {
+        match &self.value {
+            Value::String(value) => Ok(value),
+            _ => self.error(&format!("Expected a string, found {}", self.kind())),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, ParseError> {
+        match &self.value {
+            Value::Bool(value) => Ok(*value),
+            _ => self.error(&format!("Expected a boolean, found {}", self.kind())),
+        }
+    }
+
+    pub fn as_sequence(&self) -> Result<&[Node], ParseError> {
+        match &self.value {
+            Value::Sequence(nodes) => Ok(nodes),
+            _ => self.error(&format!("Expected a list, found {}", self.kind())),
+        }
+    }
+
+    pub fn as_mapping(&self) -> Result<&[Entry], ParseError> {
+        match &self.value {
+            Value::Mapping(entries) => Ok(entries),
+            _ => self.error(&format!("Expected a table, found {}", self.kind())),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match &self.value {
+            Value::Null => "an empty value",
+            Value::String(_) => "a string",
+            Value::Bool(_) => "a boolean",
+            Value::Sequence(_) => "a list",
+            Value::Mapping(_) => "a table",
+            Value::Other(kind) => kind,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::node::{Entry, Node, ParseError, Position, Value};
+use toml_edit::{ImDocument, InlineTable, Item, Table};
+
+/// Reads a TOML document into nodes
+pub fn parse(source: &str) -> Result<Node, ParseError> {
+    let document = ImDocument::parse(source).map_err(|err| {
+        let position = err
+            .span()
+            .map(|span| Position::from_offset(source, span.start))
+            .unwrap_or_default();
+        // The messages of the parser span multiple lines
+        let message = err.message().trim().lines().collect::<Vec<_>>().join(", ");
+        (position, message)
+    })?;
+    let reader = Reader { source };
+    Ok(reader.table(document.as_table(), Position::default()))
+}
+
+struct Reader<'a> {
+    source: &'a str,
+}
+
+impl Reader<'_> {
+    fn position(&self, span: Option<std::ops::Range<usize>>, fallback: Position) -> Position {
+        span.map(|span| Position::from_offset(self.source, span.start))
+            .unwrap_or(fallback)
+    }
+
+    fn table(&self, table: &Table, fallback: Position) -> Node {
+        let entries = table
+            .iter()
+            .filter_map(|(key, _)| table.get_key_value(key))
+            .map(|(key, item)| {
+                let position = self.position(key.span(), fallback);
+                Entry {
+                    key: key.get().to_string(),
+                    position,
+                    value: self.item(item, position),
+                }
+            })
+            .collect();
+        Node {
+            value: Value::Mapping(entries),
+            position: self.position(table.span(), fallback),
+        }
+    }
+
+    fn inline_table(&self, table: &InlineTable, fallback: Position) -> Node {
+        let entries = table
+            .iter()
+            .filter_map(|(key, _)| table.get_key_value(key))
+            .map(|(key, item)| {
+                let position = self.position(key.span(), fallback);
+                Entry {
+                    key: key.get().to_string(),
+                    position,
+                    value: self.item(item, position),
+                }
+            })
+            .collect();
+        Node {
+            value: Value::Mapping(entries),
+            position: self.position(table.span(), fallback),
+        }
+    }
+
+    fn item(&self, item: &Item, fallback: Position) -> Node {
+        match item {
+            Item::None => Node {
+                value: Value::Null,
+                position: fallback,
+            },
+            Item::Value(value) => self.value(value, fallback),
+            Item::Table(table) => self.table(table, fallback),
+            Item::ArrayOfTables(tables) => Node {
+                value: Value::Sequence(
+                    tables
+                        .iter()
+                        .map(|table| self.table(table, fallback))
+                        .collect(),
+                ),
+                position: self.position(tables.span(), fallback),
+            },
+        }
+    }
+
+    fn value(&self, value: &toml_edit::Value, fallback: Position) -> Node {
+        let position = self.position(value.span(), fallback);
+        let value = match value {
+            toml_edit::Value::String(value) => Value::String(value.value().clone()),
+            toml_edit::Value::Boolean(value) => Value::Bool(*value.value()),
+            toml_edit::Value::Array(values) => Value::Sequence(
+                values
+                    .iter()
+                    .map(|value| self.value(value, position))
+                    .collect(),
+            ),
+            toml_edit::Value::InlineTable(table) => return self.inline_table(table, position),
+            toml_edit::Value::Integer(_) => Value::Other("an integer"),
+            toml_edit::Value::Float(_) => Value::Other("a float"),
+            toml_edit::Value::Datetime(_) => Value::Other("a date"),
+        };
+        Node { value, position }
+    }
+}
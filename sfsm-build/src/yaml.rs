@@ -0,0 +1,123 @@
+use crate::node::{Entry, Node, ParseError, Position, Value};
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+/// Reads the first document of a YAML file into nodes
+pub fn parse(source: &str) -> Result<Node, ParseError> {
+    let mut builder = Builder {
+        stack: vec![],
+        document: None,
+        error: None,
+    };
+    Parser::new(source.chars())
+        .load(&mut builder, false)
+        .map_err(|err| (position(err.marker()), err.info().to_string()))?;
+
+    if let Some(error) = builder.error {
+        return Err(error);
+    }
+    builder
+        .document
+        .ok_or_else(|| (Position::default(), "The document is empty".to_string()))
+}
+
+fn position(marker: &Marker) -> Position {
+    Position {
+        line: marker.line(),
+        column: marker.col() + 1,
+    }
+}
+
+enum Frame {
+    Sequence(Position, Vec<Node>),
+    /// The entries of a mapping and the key that waits for its value
+    Mapping(Position, Vec<Entry>, Option<(String, Position)>),
+}
+
+/// Builds the nodes from the events of the YAML parser
+struct Builder {
+    stack: Vec<Frame>,
+    document: Option<Node>,
+    error: Option<ParseError>,
+}
+
+impl Builder {
+    fn push(&mut self, node: Node) {
+        let invalid_key = match self.stack.last_mut() {
+            None => {
+                if self.document.is_none() {
+                    self.document = Some(node);
+                }
+                None
+            }
+            Some(Frame::Sequence(_, nodes)) => {
+                nodes.push(node);
+                None
+            }
+            Some(Frame::Mapping(_, entries, key)) => match (key.take(), node.value) {
+                (Some((key, position)), value) => {
+                    let value = Node {
+                        value,
+                        position: node.position,
+                    };
+                    entries.push(Entry {
+                        key,
+                        position,
+                        value,
+                    });
+                    None
+                }
+                (None, Value::String(value)) => {
+                    *key = Some((value, node.position));
+                    None
+                }
+                (None, _) => Some(node.position),
+            },
+        };
+        if let Some(position) = invalid_key {
+            self.fail(position, "Expected a string as key");
+        }
+    }
+
+    fn fail(&mut self, position: Position, message: &str) {
+        if self.error.is_none() {
+            self.error = Some((position, message.to_string()));
+        }
+    }
+}
+
+impl MarkedEventReceiver for Builder {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        let position = position(&marker);
+        match event {
+            Event::Scalar(value, style, _, _) => {
+                let value = match (style, value.as_str()) {
+                    (TScalarStyle::Plain, "true") => Value::Bool(true),
+                    (TScalarStyle::Plain, "false") => Value::Bool(false),
+                    (TScalarStyle::Plain, "~") | (TScalarStyle::Plain, "null") => Value::Null,
+                    (TScalarStyle::Plain, "") => Value::Null,
+                    _ => Value::String(value),
+                };
+                self.push(Node { value, position });
+            }
+            Event::SequenceStart(..) => self.stack.push(Frame::Sequence(position, vec![])),
+            Event::MappingStart(..) => self.stack.push(Frame::Mapping(position, vec![], None)),
+            Event::SequenceEnd | Event::MappingEnd => {
+                let node = match self.stack.pop() {
+                    Some(Frame::Sequence(position, nodes)) => Node {
+                        value: Value::Sequence(nodes),
+                        position,
+                    },
+                    Some(Frame::Mapping(position, entries, _)) => Node {
+                        value: Value::Mapping(entries),
+                        position,
+                    },
+                    None => return,
+                };
+                self.push(node);
+            }
+            Event::Alias(_) => self.fail(position, "Aliases are not supported"),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::types::{History, Machine, MessageDir, Messages, Region, State};
+use std::fmt::Write;
+
+/// Renders the states and transitions of the state machine as a graph in the DOT language of
+/// Graphviz. Composite states are drawn as clusters that contain their regions.
+pub fn dot(machine: &Machine) -> String {
+    let error_state = machine.error_state.as_ref().map(State::display_name);
+    let mut dot = String::new();
+    writeln!(dot, "digraph {} {{", machine.name).unwrap();
+    writeln!(dot, "    compound = true;").unwrap();
+    writeln!(dot, "    node [shape = box, style = rounded];").unwrap();
+    write_region(
+        &mut dot,
+        "__start",
+        &machine.init,
+        &machine.states,
+        &error_state,
+        1,
+    );
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+/// Renders the messages that are pushed to or polled from the states. The lines are meant to be
+/// added to the graph of the state machine.
+pub fn message_dot(messages: &Messages) -> String {
+    let mut dot = String::new();
+    for state_message in messages.messages.iter() {
+        let state = state_message.state.display_name();
+        let (message, edge) = match &state_message.message {
+            MessageDir::Push(message) => {
+                let message = message.display_name();
+                let edge = format!("\"message {}\" -> \"{}\"", message, state);
+                (message, edge)
+            }
+            MessageDir::Poll(message) => {
+                let message = message.display_name();
+                let edge = format!("\"{}\" -> \"message {}\"", state, message);
+                (message, edge)
+            }
+        };
+        writeln!(
+            dot,
+            "    \"message {}\" [label = \"{}\", shape = note];",
+            message, message
+        )
+        .unwrap();
+        writeln!(dot, "    {} [style = dashed];", edge).unwrap();
+    }
+    dot
+}
+
+/// Renders a region with its start point, its states and the transitions between them
+fn write_region(
+    dot: &mut String,
+    start: &str,
+    init: &State,
+    states: &[State],
+    error_state: &Option<String>,
+    depth: usize,
+) {
+    let indent = "    ".repeat(depth);
+    let init = states
+        .iter()
+        .find(|state| state.enum_name == init.enum_name)
+        .unwrap_or(init);
+    writeln!(
+        dot,
+        "{}\"{}\" [label = \"\", shape = point];",
+        indent, start
+    )
+    .unwrap();
+    writeln!(
+        dot,
+        "{}\"{}\" -> \"{}\"{};",
+        indent,
+        start,
+        init.display_name(),
+        edge_attributes(None, None, Some(init))
+    )
+    .unwrap();
+
+    for state in states.iter() {
+        write_state(dot, state, error_state, depth);
+    }
+
+    for state in states.iter() {
+        let targets = state
+            .transits
+            .iter()
+            .map(|transit| {
+                let label = if transit.completion {
+                    Some("after completion".to_string())
+                } else {
+                    transit
+                        .timeout
+                        .as_ref()
+                        .map(|timeout| format!("after {}", timeout.display_name()))
+                };
+                (&transit.dst, label)
+            })
+            .chain(
+                state
+                    .event_transits
+                    .iter()
+                    .map(|transit| (&transit.dst, Some(transit.event.display_name()))),
+            );
+        for (dst, label) in targets {
+            // The regions of the target are only known to the declared state
+            let dst = states
+                .iter()
+                .find(|state| state.enum_name == dst.enum_name)
+                .unwrap_or(dst);
+            writeln!(
+                dot,
+                "{}\"{}\" -> \"{}\"{};",
+                indent,
+                state.display_name(),
+                dst.display_name(),
+                edge_attributes(label, Some(state), Some(dst))
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Renders a state as a node. A composite state is rendered as a cluster with an invisible node
+/// that the transitions are attached to.
+fn write_state(dot: &mut String, state: &State, error_state: &Option<String>, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let name = state.display_name();
+
+    if state.regions.is_empty() {
+        let mut attributes = vec![];
+        if state.is_final {
+            attributes.push("peripheries = 2");
+        }
+        if error_state.as_ref() == Some(&name) {
+            attributes.push("color = red");
+        }
+        if attributes.is_empty() {
+            writeln!(dot, "{}\"{}\";", indent, name).unwrap();
+        } else {
+            writeln!(dot, "{}\"{}\" [{}];", indent, name, attributes.join(", ")).unwrap();
+        }
+        return;
+    }
+
+    let history = match state.history {
+        Some(History::Shallow) => " (H)",
+        Some(History::Deep) => " (H*)",
+        None => "",
+    };
+    writeln!(dot, "{}subgraph \"cluster {}\" {{", indent, name).unwrap();
+    writeln!(dot, "{}    label = \"{}{}\";", indent, name, history).unwrap();
+    writeln!(
+        dot,
+        "{}    \"{}\" [label = \"\", shape = point, style = invis];",
+        indent, name
+    )
+    .unwrap();
+    if let [region] = state.regions.as_slice() {
+        write_sub_region(dot, &name, 0, region, depth + 1);
+    } else {
+        // Regions that are active at the same time are separated by dashed borders
+        for (index, region) in state.regions.iter().enumerate() {
+            writeln!(
+                dot,
+                "{}    subgraph \"cluster {} {}\" {{",
+                indent, name, index
+            )
+            .unwrap();
+            writeln!(dot, "{}        label = \"\";", indent).unwrap();
+            writeln!(dot, "{}        style = dashed;", indent).unwrap();
+            write_sub_region(dot, &name, index, region, depth + 2);
+            writeln!(dot, "{}    }}", indent).unwrap();
+        }
+    }
+    writeln!(dot, "{}}}", indent).unwrap();
+}
+
+fn write_sub_region(dot: &mut String, parent: &str, index: usize, region: &Region, depth: usize) {
+    let start = format!("__start {} {}", parent, index);
+    write_region(dot, &start, &region.init, &region.states, &None, depth);
+}
+
+/// Creates the attributes of an edge. Edges from and to composite states are clipped at the
+/// border of their clusters.
+fn edge_attributes(label: Option<String>, src: Option<&State>, dst: Option<&State>) -> String {
+    let mut attributes = vec![];
+    if let Some(label) = label {
+        attributes.push(format!("label = \"{}\"", label));
+    }
+    if let Some(src) = src.filter(|src| !src.regions.is_empty()) {
+        attributes.push(format!("ltail = \"cluster {}\"", src.display_name()));
+    }
+    if let Some(dst) = dst.filter(|dst| !dst.regions.is_empty()) {
+        attributes.push(format!("lhead = \"cluster {}\"", dst.display_name()));
+    }
+    if attributes.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", attributes.join(", "))
+    }
+}
@@ -1,7 +1,16 @@
+use crate::dot;
+use crate::lints;
+use crate::persist;
+use crate::scxml;
+use crate::serialize;
+use crate::state_diagram::{self, Flavor};
 use crate::trace;
-use crate::types::{Machine, MessageDir, Messages, Mode, State, StateMessage};
-use proc_macro2::TokenStream;
+use crate::types::{
+    Event, EventTransit, History, Machine, MessageDir, Messages, Mode, State, StateMessage, Transit,
+};
+use proc_macro2::{Ident, TokenStream};
 use quote::{quote, ToTokens};
+use syn::GenericParam;
 
 pub struct TransitToErrorToTokens {}
 
@@ -11,45 +20,94 @@ impl<'a> TransitToErrorToTokens {
         tokens: TokenStream,
         current_state: &State,
     ) -> proc_macro2::TokenStream {
-        match &machine.mode {
-            Mode::NonFallible { .. } => {
+        let awaiting = machine.awaiting();
+        match (&machine.mode, &machine.error_state) {
+            (Mode::NonFallible, _) => {
                 quote! {
-                    #tokens;
-                }
-            }
-            Mode::Fallible { .. } => {
-                let error_state_entry = &(machine.error_state)
-                    .as_ref()
-                    .expect("Internal error. Expected to have a error state.")
-                    .enum_name;
-                let enum_name = &machine.enum_name;
-                let error_state = &(machine.error_state)
-                    .as_ref()
-                    .expect("Internal error. Expected to have a error state.");
-                let trace_error_state = trace::trace(trace::format_log(
-                    &machine.name.to_string(),
-                    "Enter error state",
-                    "",
-                ));
-                if error_state.enum_name != current_state.enum_name {
-                    let entry = &machine.trait_definitions.entry;
-                    let state_trait = &machine.trait_definitions.state_trait;
+                    #tokens #awaiting;
+                }
+            }
+            (Mode::Fallible, Some(error_state))
+                if error_state.enum_name != current_state.enum_name =>
+            {
+                let error_routing = TransitToErrorToTokens::error_routing(machine, current_state);
+                quote! {
+                    if let Err(err) = #tokens #awaiting {
+                        #error_routing
+                    }
+                }
+            }
+            // The error state itself and the sub-states of a composite state hand the error
+            // on to the caller
+            (Mode::Fallible, _) => {
+                quote! {
+                    #tokens #awaiting.map_err(|err| {sfsm::ExtendedSfsmError::Custom(err)})?;
+                }
+            }
+        }
+    }
 
-                    quote! {
-                        if let Err(err) = #tokens {
-                            #trace_error_state
-                            let mut err_state: #error_state = state.into();
-                            err_state.consume_error(err);
-                            #state_trait::#entry(&mut err_state).map_err(|err| {sfsm::ExtendedSfsmError::Custom(err)})?;
-                            return Ok(#enum_name::#error_state_entry(Some(err_state)));
+    /// Wraps a call into the sub-states of a composite state. Errors of the sub-states are
+    /// handled as if the composite state itself had failed.
+    fn wrap_sub_machine(
+        machine: &'a Machine,
+        tokens: TokenStream,
+        current_state: &State,
+    ) -> proc_macro2::TokenStream {
+        match (&machine.mode, &machine.error_state) {
+            (Mode::Fallible, Some(error_state))
+                if error_state.enum_name != current_state.enum_name =>
+            {
+                let error_routing = TransitToErrorToTokens::error_routing(machine, current_state);
+                quote! {
+                    match #tokens {
+                        Ok(()) => {}
+                        Err(sfsm::ExtendedSfsmError::Custom(err)) => {
+                            #error_routing
+                        }
+                        Err(err) => {
+                            return Err(err);
                         }
-                    }
-                } else {
-                    quote! {
-                        #tokens.map_err(|err| {sfsm::ExtendedSfsmError::Custom(err)})?;
                     }
                 }
             }
+            _ => {
+                quote! {
+                    #tokens?;
+                }
+            }
+        }
+    }
+
+    /// Moves the current state into the error state and hands it the error
+    fn error_routing(machine: &'a Machine, current_state: &State) -> proc_macro2::TokenStream {
+        let enum_name = &machine.enum_name;
+        let error_state = &(machine.error_state)
+            .as_ref()
+            .expect("Internal error. Expected to have a error state.");
+        let error_state_entry = &error_state.enum_name;
+        let trace_error_state = trace::trace(trace::format_log(
+            &machine.name.to_string(),
+            "Enter error state",
+            "",
+        ));
+        let entry = &machine.trait_definitions.entry;
+        let state_trait = &machine.trait_definitions.state_trait;
+        let error_trait = &machine.trait_definitions.error_trait;
+        let context_arg = machine.context_arg();
+        let record_entry = machine.record_entry();
+        let record_transition =
+            TransitToTokens::record_transition(machine, current_state, error_state);
+        let awaiting = machine.awaiting();
+
+        quote! {
+            #trace_error_state
+            let mut err_state: #error_state = state.into();
+            #record_transition
+            #record_entry
+            #error_trait::consume_error(&mut err_state, err #context_arg);
+            #state_trait::#entry(&mut err_state #context_arg) #awaiting.map_err(|err| {sfsm::ExtendedSfsmError::Custom(err)})?;
+            return Ok(#enum_name::#error_state_entry(Some(err_state)));
         }
     }
 }
@@ -64,16 +122,80 @@ impl<'a> StateMachineToTokens<'a> {
     }
 }
 
+impl<'a> StateMachineToTokens<'a> {
+    /// Generates the is_state checks for the sub-states of all composite states. A sub-state
+    /// is only active if all of its parent states are active as well.
+    fn sub_is_states(
+        &self,
+        machine: &Machine,
+        access: TokenStream,
+        conditions: &[TokenStream],
+    ) -> TokenStream {
+        let (impl_generics, _, where_clause) = self.machine.generics.split_for_impl();
+        let machine_type = self.machine.machine_type();
+        let enum_name = &machine.enum_name;
+        let mut tokens = quote! {};
+        for (field, sub_machine) in machine.sub_machines() {
+            let parent_entry = &sub_machine
+                .parent
+                .as_ref()
+                .expect("Internal error. Expected a composite state.")
+                .enum_name;
+            let mut conditions = conditions.to_vec();
+            conditions.push(quote! {
+                matches!(#access.states, #enum_name::#parent_entry(_))
+            });
+            let sub_access = quote! { #access.#field };
+            let sub_enum_name = &sub_machine.enum_name;
+            for state in sub_machine.states.iter() {
+                let state_entry = &state.enum_name;
+                let sub_access_mut = quote! { &mut #sub_access.states };
+                tokens.extend(quote! {
+                    impl #impl_generics sfsm::IsState<#state> for #machine_type #where_clause {
+                        fn is_state(&self) -> bool {
+                            #( #conditions && )* matches!(#sub_access.states, #sub_enum_name::#state_entry(_))
+                        }
+                    }
+
+                    impl #impl_generics sfsm::GetState<#state> for #machine_type #where_clause {
+                        fn get_state(&self) -> ::core::option::Option<&#state> {
+                            if !(#( #conditions && )* true) {
+                                return None;
+                            }
+                            #[allow(unreachable_patterns)]
+                            match &#sub_access.states {
+                                #sub_enum_name::#state_entry(state) => state.as_ref(),
+                                _ => None,
+                            }
+                        }
+
+                        fn get_state_mut(&mut self) -> ::core::option::Option<&mut #state> {
+                            if !(#( #conditions && )* true) {
+                                return None;
+                            }
+                            #[allow(unreachable_patterns)]
+                            match #sub_access_mut {
+                                #sub_enum_name::#state_entry(state) => state.as_mut(),
+                                _ => None,
+                            }
+                        }
+                    }
+                });
+            }
+            tokens.extend(self.sub_is_states(&sub_machine, sub_access, &conditions));
+        }
+        tokens
+    }
+}
+
 impl ToTokens for StateMachineToTokens<'_> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let sfsm_name = &self.machine.name;
         let enum_name = &self.machine.enum_name;
         let init_state = &self.machine.init;
-        let init_state_entry = &self.machine.init.enum_name;
         let attribute = &self.machine.attributes;
         let vis = &self.machine.visibility;
-        let state_trait = &self.machine.trait_definitions.state_trait;
-        let entry = &self.machine.trait_definitions.entry;
+        let borrow_scope = self.machine.borrow_scope();
 
         let states: Vec<StateToTokens> = self
             .machine
@@ -89,31 +211,96 @@ impl ToTokens for StateMachineToTokens<'_> {
             .map(StateEntriesToTokens::new)
             .collect();
 
-        let exits: Vec<StopToTokens> = self
+        let stops: Vec<StopToTokens> = self
             .machine
             .states
             .iter()
-            .map(|state| {
-                return StopToTokens::new(self.machine, state);
-            })
+            .map(|state| StopToTokens::new(self.machine, state))
+            .collect();
+
+        let resets: Vec<StopToTokens> = self
+            .machine
+            .states
+            .iter()
+            .map(|state| StopToTokens::reset(self.machine, state))
             .collect();
 
         let is_states: Vec<IsStateToTokens> = self
             .machine
             .states
             .iter()
-            .map(|state| {
-                return IsStateToTokens::new(self.machine, state);
-            })
+            .map(|state| IsStateToTokens::new(self.machine, state))
             .collect();
+        let sub_is_states = self.sub_is_states(self.machine, quote! { self }, &[]);
 
-        let init_state_tokens: TokenStream = TransitToErrorToTokens::wrap_if_fallible(
-            self.machine,
-            quote! {
-                #state_trait::#entry(&mut state)
-            },
-            init_state,
-        );
+        // Every state can be started in, the initial state by the start function
+        let start_states: Vec<StartStateToTokens> = self
+            .machine
+            .states
+            .iter()
+            .map(|state| StartStateToTokens::new(self.machine, state))
+            .collect();
+
+        let is_finished = IsFinishedToTokens::new(self.machine);
+        let lints = lints::lints(self.machine);
+        let dot = dot::dot(self.machine);
+        let mermaid = state_diagram::state_diagram(self.machine, Flavor::Mermaid);
+        let plantuml = state_diagram::state_diagram(self.machine, Flavor::PlantUml);
+        let scxml = scxml::scxml(self.machine);
+
+        // Async state machines await their states and implement the async trait instead
+        let asyncness = self.machine.asyncness();
+        let awaiting = self.machine.awaiting();
+        let (state_machine_trait, start_state_trait) = if self.machine.asynchronous {
+            (
+                quote! { sfsm::AsyncStateMachine },
+                quote! { sfsm::AsyncStartState },
+            )
+        } else {
+            (quote! { sfsm::StateMachine }, quote! { sfsm::StartState })
+        };
+
+        let state_id = &self.machine.state_id_name;
+        let state_entry_names: Vec<&Ident> = self
+            .machine
+            .states
+            .iter()
+            .map(|state| &state.enum_name)
+            .collect();
+        let state_ids: Vec<Ident> = self.machine.states.iter().map(State::id_name).collect();
+        let all_state_ids: Vec<Ident> = self
+            .machine
+            .all_states()
+            .iter()
+            .map(State::id_name)
+            .collect();
+        // The names are the same as in the trace
+        let all_state_names: Vec<String> = self
+            .machine
+            .all_states()
+            .iter()
+            .map(State::get_name_type)
+            .collect();
+
+        let sub_machines = self.machine.sub_machines();
+        let sub_machine_fields: Vec<&Ident> = sub_machines.iter().map(|(field, _)| field).collect();
+        let sub_machine_names: Vec<&Ident> = sub_machines
+            .iter()
+            .map(|(_, sub_machine)| &sub_machine.name)
+            .collect();
+        let sub_machine_types: Vec<TokenStream> = sub_machines
+            .iter()
+            .map(|(_, sub_machine)| sub_machine.machine_type())
+            .collect();
+        let sub_machines_to_tokens: Vec<SubMachineToTokens> = sub_machines
+            .iter()
+            .map(|(_, sub_machine)| SubMachineToTokens::new(sub_machine))
+            .collect();
+
+        let generics = &self.machine.generics;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let machine_type = self.machine.machine_type();
+        let enum_type = self.machine.enum_type();
 
         let sfsm_error = &self.machine.sfsm_error;
         let custom_error = &self.machine.custom_error;
@@ -122,50 +309,638 @@ impl ToTokens for StateMachineToTokens<'_> {
             #sfsm_error #custom_error
         };
 
-        let trace_start = trace::trace(trace::format_log(
+        let trace_stop = trace::trace(trace::format_log(&sfsm_name.to_string(), "Stop", ""));
+        let trace_reset = trace::trace(trace::format_log(&sfsm_name.to_string(), "Reset", ""));
+        let trace_limit = trace::trace(trace::format_log(
             &sfsm_name.to_string(),
-            "Start",
-            &init_state.get_name_type(),
+            "Transition limit exceeded",
+            "",
         ));
-        let trace_stop = trace::trace(trace::format_log(&sfsm_name.to_string(), "Stop", ""));
+
+        let events = EventsToTokens::new(self.machine);
+
+        // If a context is used, it is owned by the state machine and borrowed by the states
+        let (context_field, context_param, context_init, context_accessors) =
+            if let Some(context) = &self.machine.options.context {
+                (
+                    quote! {
+                        context: #context,
+                    },
+                    quote! { context: #context, },
+                    quote! { context, },
+                    quote! {
+                        /// Returns a reference to the context of the state machine
+                        pub fn context(&self) -> &#context {
+                            &self.context
+                        }
+
+                        /// Returns a mutable reference to the context of the state machine
+                        pub fn context_mut(&mut self) -> &mut #context {
+                            &mut self.context
+                        }
+                    },
+                )
+            } else {
+                (quote! {}, quote! {}, quote! {}, quote! {})
+            };
+
+        // If a clock is used, it is owned by the state machine as well and times the timeout
+        // transitions. A restored state counts as entered at the time it is restored.
+        let (clock_field, clock_param, clock_init, restore_clock_init, clock_accessors) =
+            if let Some(clock) = &self.machine.options.clock {
+                (
+                    quote! {
+                        clock: #clock,
+                        entered_at: u64,
+                    },
+                    quote! { clock: #clock },
+                    quote! { clock, entered_at: 0, },
+                    quote! { entered_at: sfsm::Clock::now(&clock), clock, },
+                    quote! {
+                        /// Returns a reference to the clock of the state machine
+                        pub fn clock(&self) -> &#clock {
+                            &self.clock
+                        }
+
+                        /// Returns a mutable reference to the clock of the state machine
+                        pub fn clock_mut(&mut self) -> &mut #clock {
+                            &mut self.clock
+                        }
+                    },
+                )
+            } else {
+                (quote! {}, quote! {}, quote! {}, quote! {}, quote! {})
+            };
+
+        // The machine can be serialized if its states, context and clock can be serialized
+        let serialize_enum = serialize::derive(&serialize::state_types(self.machine));
+        let mut field_types = vec![enum_type.clone()];
+        let options = &self.machine.options;
+        field_types.extend(options.context.iter().map(ToTokens::to_token_stream));
+        field_types.extend(options.clock.iter().map(ToTokens::to_token_stream));
+        field_types.extend(sub_machine_types.iter().cloned());
+        let serialize_machine = serialize::derive(&field_types);
+
+        let persist = persist::methods(self.machine);
+
+        // A restored composite state starts in its initial sub-states
+        let restore_states = self.machine.states.iter().filter_map(|state| {
+            if state.regions.is_empty() {
+                return None;
+            }
+            let state_entry = &state.enum_name;
+            let restore = TransitToTokens::restore_sub_machines(
+                self.machine,
+                state,
+                quote! { machine },
+                quote! { &machine.clock },
+            );
+            Some(quote! {
+                #enum_name::#state_entry(Some(ref mut state)) => {
+                    #restore
+                }
+            })
+        });
 
         let token_steam = quote! {
             #(#attribute)*
-            #vis enum #enum_name {
+            #serialize_enum
+            #vis enum #enum_name #generics #where_clause {
                 #(#state_entries)*
+                /// The state machine is stopped or has not been started yet
+                Stopped,
+            }
+
+            /// Identifies the states and sub-states of the state machine without their data
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            #[repr(u8)]
+            #vis enum #state_id {
+                #( #all_state_ids, )*
             }
 
             #(#attribute)*
-            #vis struct #sfsm_name {
-                states: #enum_name,
+            #serialize_machine
+            #vis struct #sfsm_name #generics #where_clause {
+                states: #enum_type,
+                #context_field
+                #clock_field
+                #( #sub_machine_fields: #sub_machine_types, )*
             }
 
-            impl #sfsm_name {
-                pub fn new() -> Self {
+            impl #impl_generics #machine_type #where_clause {
+                pub fn new(#context_param #clock_param) -> Self {
                     Self {
-                        states: #enum_name::#init_state_entry(None)
+                        states: #enum_name::Stopped,
+                        #context_init
+                        #clock_init
+                        #( #sub_machine_fields: #sub_machine_names::new(), )*
+                    }
+                }
+
+                /// Creates the state machine in the given states without entering them again,
+                /// like when the states were deserialized after a restart. The sub-states of
+                /// composite states are not part of the states, so an active composite state
+                /// starts in its initial sub-states. They are created by the composite state, but
+                /// their entry hooks do not run either. To resume the sub-states as well, the
+                /// whole state machine has to be deserialized. The timeouts of the restored states
+                /// start at the current time of the clock.
+                pub fn restore(states: #enum_type, #context_param #clock_param) -> Self {
+                    let mut machine = Self {
+                        states,
+                        #context_init
+                        #restore_clock_init
+                        #( #sub_machine_fields: #sub_machine_names::new(), )*
+                    };
+                    match machine.states {
+                        #( #restore_states )*
+                        _ => {}
+                    }
+                    machine
+                }
+
+                #persist
+
+                /// The states and transitions of the state machine as a graph in the DOT language
+                /// of Graphviz
+                pub const DOT: &'static str = #dot;
+
+                /// The states and transitions of the state machine as a Mermaid state diagram
+                pub const MERMAID: &'static str = #mermaid;
+
+                /// The states and transitions of the state machine as a PlantUML state diagram
+                pub const PLANTUML: &'static str = #plantuml;
+
+                /// The states and transitions of the state machine as a SCXML document
+                pub const SCXML: &'static str = #scxml;
+
+                /// The names of all states and sub-states. The name of a state is found at the
+                /// index of its state id.
+                pub const STATES: &'static [&'static str] = &[ #( #all_state_names, )* ];
+
+                /// Returns the id of the active state. The active sub-states of a composite
+                /// state are not considered. While the state machine is stopped or before it is
+                /// started, there is no active state and None is returned.
+                pub fn state_id(&self) -> ::core::option::Option<#state_id> {
+                    match self.states {
+                        #( #enum_name::#state_entry_names(_) => Some(#state_id::#state_ids), )*
+                        #enum_name::Stopped => None,
+                    }
+                }
+
+                /// Returns the name of the active state or None if the state machine is stopped
+                pub fn state_name(&self) -> ::core::option::Option<&'static str> {
+                    self.state_id().map(|id| Self::STATES[id as usize])
+                }
+
+                /// Returns the given state if it is active
+                pub fn get<StateType>(&self) -> ::core::option::Option<&StateType> where Self: sfsm::GetState<StateType> {
+                    sfsm::GetState::<StateType>::get_state(self)
+                }
+
+                /// Returns the given state mutably if it is active
+                pub fn get_mut<StateType>(&mut self) -> ::core::option::Option<&mut StateType> where Self: sfsm::GetState<StateType> {
+                    sfsm::GetState::<StateType>::get_state_mut(self)
+                }
+
+                /// Returns true if the state machine has reached a final state
+                pub fn is_finished(&self) -> bool {
+                    #is_finished
+                }
+
+                /// Starts the state machine in the given state instead of the initial state. The
+                /// state is entered like the initial state by start. A running state machine is
+                /// not started again, as it would leave its active state without exiting it.
+                pub #asyncness fn start_in<StateType>(&mut self, state: StateType) -> ::core::result::Result<(), #error_type> where Self: #start_state_trait<StateType> + #state_machine_trait<Error = #error_type> {
+                    if !matches!(self.states, #enum_name::Stopped) {
+                        return Err(#sfsm_error::AlreadyStarted);
                     }
+                    #start_state_trait::<StateType>::start_state(self, state) #awaiting
+                }
+
+                #context_accessors
+                #clock_accessors
+
+                // Steps the state machine once and returns the number of transitions taken as well
+                // as the first one of them
+                #asyncness fn step_and_record(&mut self) -> ::core::result::Result<(usize, ::core::option::Option<(#state_id, #state_id)>), #error_type> {
+                    #borrow_scope
+                    let ref mut e = self.states;
+                    *e = match *e {
+                        #( #states, )*
+                        #enum_name::Stopped => return Err(#sfsm_error::Internal),
+                    };
+                    Ok((*transitions, *transition))
                 }
             }
 
-            impl sfsm::StateMachine for #sfsm_name {
+            impl #impl_generics #state_machine_trait for #machine_type #where_clause {
                 type InitialState = #init_state;
                 type Error = #error_type;
-                type StatesEnum = #enum_name;
+                type StatesEnum = #enum_type;
+                type StateId = #state_id;
+
+                #asyncness fn start(&mut self, state: Self::InitialState) -> ::core::result::Result<(), Self::Error> {
+                    #start_state_trait::<#init_state>::start_state(self, state) #awaiting
+                }
+
+                #asyncness fn step(&mut self) -> ::core::result::Result<(), Self::Error> {
+                    self.step_and_record() #awaiting?;
+                    Ok(())
+                }
+
+                #asyncness fn step_report(&mut self) -> ::core::result::Result<sfsm::StepOutcome<Self::StateId>, Self::Error> {
+                    let (_, transition) = self.step_and_record() #awaiting?;
+                    Ok(match transition {
+                        Some((from, to)) => sfsm::StepOutcome::Transitioned { from, to },
+                        None => sfsm::StepOutcome::Stayed(self.state_id().ok_or(#sfsm_error::Internal)?),
+                    })
+                }
+
+                #asyncness fn step_until_stable(&mut self, max_transitions: usize) -> ::core::result::Result<usize, Self::Error> {
+                    let mut transitions = 0;
+                    loop {
+                        match self.step_and_record() #awaiting?.0 {
+                            0 => return Ok(transitions),
+                            taken => transitions += taken,
+                        }
+                        // Only a step without a transition shows that the state machine is
+                        // stable, so the limit is checked after the transition has been taken
+                        if transitions > max_transitions {
+                            #trace_limit
+                            return Err(#sfsm_error::TransitionLimitExceeded);
+                        }
+                    }
+                }
+
+                #asyncness fn stop(&mut self) -> ::core::result::Result<Self::StatesEnum, Self::Error> {
+                    #trace_stop
+                    {
+                        #borrow_scope
+                        // The state machine stays in the active state if stopping it fails
+                        match self.states {
+                            # ( #stops )*
+                            #enum_name::Stopped => Ok(()),
+                            _ => Err(#sfsm_error::Internal),
+                        }?;
+                    }
+                    // The sub-states start from scratch when the state machine is started again
+                    #( self.#sub_machine_fields = #sub_machine_names::new(); )*
+                    Ok(::core::mem::replace(&mut self.states, #enum_name::Stopped))
+                }
+
+                #asyncness fn reset(&mut self, state: Self::InitialState) -> ::core::result::Result<(), Self::Error> {
+                    #trace_reset
+                    {
+                        #borrow_scope
+                        // Only the exit hooks run, the innermost sub-states first
+                        match self.states {
+                            # ( #resets )*
+                            #enum_name::Stopped => Ok(()),
+                            _ => Err(#sfsm_error::Internal),
+                        }?;
+                    }
+                    self.states = #enum_name::Stopped;
+                    #( self.#sub_machine_fields = #sub_machine_names::new(); )*
+                    #start_state_trait::<#init_state>::start_state(self, state) #awaiting
+                }
+
+                fn peek_state(&self) -> &Self::StatesEnum {
+                   return &self.states;
+                }
+            }
+
+            // Implement the is_state checks
+            #(#is_states)*
+            #sub_is_states
+
+            #(#start_states)*
+
+            #events
+
+            #( #sub_machines_to_tokens )*
 
-                fn start(&mut self, mut state: Self::InitialState) -> ::core::result::Result<(), Self::Error> {
+            #lints
+        };
+
+        tokens.extend(token_steam);
+    }
+}
+
+pub struct SubMachineToTokens<'a> {
+    machine: &'a Machine,
+}
+
+impl<'a> SubMachineToTokens<'a> {
+    pub fn new(machine: &'a Machine) -> Self {
+        Self { machine }
+    }
+}
+
+impl ToTokens for SubMachineToTokens<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let sfsm_name = &self.machine.name;
+        let enum_name = &self.machine.enum_name;
+        let events_enum_name = &self.machine.events_enum_name;
+        let parent = &self
+            .machine
+            .parent
+            .as_ref()
+            .expect("Internal error. Expected a composite state.");
+        let init_state = &self.machine.init;
+        let init_state_entry = &self.machine.init.enum_name;
+        let attribute = &self.machine.attributes;
+        let vis = &self.machine.visibility;
+        let state_trait = &self.machine.trait_definitions.state_trait;
+        let entry = &self.machine.trait_definitions.entry;
+        let context_arg = self.machine.context_arg();
+        let context_param = self.machine.context_param();
+        let scope_params = self.machine.scope_params();
+        let scope_args = self.machine.scope_args();
+        let borrow_scope = self.machine.borrow_scope();
+        let record_entry = self.machine.record_entry();
+        let forward_params = self.machine.forward_params();
+        let asyncness = self.machine.asyncness();
+        let awaiting = self.machine.awaiting();
+        let sfsm_error = &self.machine.sfsm_error;
+        let custom_error = &self.machine.custom_error;
+        let error_type = quote! {
+            #sfsm_error #custom_error
+        };
+
+        let states: Vec<StateToTokens> = self
+            .machine
+            .states
+            .iter()
+            .map(|state| StateToTokens::new(self.machine, state))
+            .collect();
+
+        let state_entries: Vec<StateEntriesToTokens> = self
+            .machine
+            .states
+            .iter()
+            .map(StateEntriesToTokens::new)
+            .collect();
+
+        let exits: Vec<StopToTokens> = self
+            .machine
+            .states
+            .iter()
+            .map(|state| StopToTokens::leave(self.machine, state))
+            .collect();
+
+        let stops: Vec<StopToTokens> = self
+            .machine
+            .states
+            .iter()
+            .map(|state| StopToTokens::new(self.machine, state))
+            .collect();
+
+        let resets: Vec<StopToTokens> = self
+            .machine
+            .states
+            .iter()
+            .map(|state| StopToTokens::reset(self.machine, state))
+            .collect();
+
+        let sub_machines = self.machine.sub_machines();
+        let sub_machine_fields: Vec<&Ident> = sub_machines.iter().map(|(field, _)| field).collect();
+        let sub_machine_names: Vec<&Ident> = sub_machines
+            .iter()
+            .map(|(_, sub_machine)| &sub_machine.name)
+            .collect();
+        let sub_machine_types: Vec<TokenStream> = sub_machines
+            .iter()
+            .map(|(_, sub_machine)| sub_machine.machine_type())
+            .collect();
+        let sub_machines_to_tokens: Vec<SubMachineToTokens> = sub_machines
+            .iter()
+            .map(|(_, sub_machine)| SubMachineToTokens::new(sub_machine))
+            .collect();
+
+        let generics = &self.machine.generics;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let machine_type = self.machine.machine_type();
+        let enum_type = self.machine.enum_type();
+        let (fn_generics, fn_where_clause) = self.machine.fn_generics();
+
+        let init_state_tokens: TokenStream = TransitToErrorToTokens::wrap_if_fallible(
+            self.machine,
+            quote! {
+                #state_trait::#entry(&mut state #context_arg)
+            },
+            init_state,
+        );
+        let init_sub_machine =
+            TransitToTokens::enter_sub_machines(self.machine, init_state, quote! { false });
+        let restore_sub_machine = TransitToTokens::restore_sub_machines(
+            self.machine,
+            init_state,
+            quote! { self },
+            quote! { clock },
+        );
+        let clock_param = self.machine.clock_param();
+        let restore_entry = if self.machine.options.clock.is_some() {
+            quote! { self.entered_at = sfsm::Clock::now(clock); }
+        } else {
+            quote! {}
+        };
+
+        let trace_entry = trace::trace(trace::format_log(
+            &sfsm_name.to_string(),
+            "Enter",
+            &init_state.get_name_type(),
+        ));
+
+        let is_finished = IsFinishedToTokens::new(self.machine);
+
+        // Every region tracks when its active sub-state was entered
+        let (entered_at_field, entered_at_init) = if self.machine.options.clock.is_some() {
+            (quote! { entered_at: u64, }, quote! { entered_at: 0, })
+        } else {
+            (quote! {}, quote! {})
+        };
+
+        // The sub-states might not use all generic parameters that the sub-machine needs for
+        // its composite state and nested sub-states
+        let enum_generics = &self.machine.enum_generics;
+        let enum_where_clause = &enum_generics.where_clause;
+        let (marker_field, marker_init) = if generics.params.is_empty() {
+            (quote! {}, quote! {})
+        } else {
+            let markers = generics.params.iter().map(|param| match param {
+                GenericParam::Type(param) => {
+                    let ident = &param.ident;
+                    quote! { fn() -> #ident }
+                }
+                GenericParam::Lifetime(param) => {
+                    let lifetime = &param.lifetime;
+                    quote! { &#lifetime () }
+                }
+                GenericParam::Const(param) => {
+                    let ident = &param.ident;
+                    quote! { [(); #ident] }
+                }
+            });
+            (
+                quote! { _marker: ::core::marker::PhantomData<( #( #markers, )* )>, },
+                quote! { _marker: ::core::marker::PhantomData, },
+            )
+        };
+
+        // The last active sub-state is kept after leaving if it might be resumed later on
+        let exit_states = if self.machine.history.is_some() {
+            quote! {
+                self.states = match self.states {
+                    # ( #exits )*,
+                }?;
+            }
+        } else {
+            quote! {
+                match self.states {
+                    # ( #exits )*,
+                }?;
+                self.states = #enum_name::#init_state_entry(None);
+            }
+        };
+
+        // A composite state with a history always resumes. Otherwise only a deep history
+        // further up can request to resume.
+        let (deep_param, resume) = if self.machine.history.is_some() {
+            let resume_states: Vec<ResumeStateToTokens> = self
+                .machine
+                .states
+                .iter()
+                .map(|state| ResumeStateToTokens::new(self.machine, state))
+                .collect();
+            let resume = quote! {
+                #borrow_scope
+                let resumed = match self.states {
+                    #( #resume_states )*
+                    _ => None,
+                };
+                if let Some(states) = resumed {
+                    self.states = states;
+                    return Ok(());
+                }
+            };
+            let deep = if parent.history == Some(History::Deep) {
+                quote! {
+                    let deep = true;
+                }
+            } else {
+                quote! {}
+            };
+            let resume = if parent.history.is_some() {
+                quote! {
+                    #deep
+                    {
+                        #resume
+                    }
+                }
+            } else {
+                quote! {
+                    if deep {
+                        #resume
+                    }
+                }
+            };
+            (quote! { deep }, resume)
+        } else {
+            (quote! { _deep }, quote! {})
+        };
+
+        // The events are forwarded from the outer machine to the active sub-state
+        let handle = if self.machine.events.is_empty() {
+            quote! {}
+        } else {
+            let event_states: Vec<EventStateToTokens> = self
+                .machine
+                .states
+                .iter()
+                .map(|state| EventStateToTokens::new(self.machine, state))
+                .collect();
+            quote! {
+                #asyncness fn handle(&mut self, event: &#events_enum_name #forward_params) -> ::core::result::Result<(), #error_type> {
+                    #borrow_scope
+                    let ref mut e = self.states;
+                    *e = match *e {
+                        #( #event_states, )*
+                    };
+                    Ok(())
+                }
+            }
+        };
+
+        let serialize_enum = serialize::derive(&serialize::state_types(self.machine));
+        let mut field_types = vec![enum_type.clone()];
+        field_types.extend(sub_machine_types.iter().cloned());
+        let serialize_machine = serialize::derive(&field_types);
+
+        let token_steam = quote! {
+            #(#attribute)*
+            #serialize_enum
+            #[doc(hidden)]
+            #vis enum #enum_name #enum_generics #enum_where_clause {
+                #(#state_entries)*
+            }
+
+            #(#attribute)*
+            #serialize_machine
+            #[doc(hidden)]
+            #vis struct #sfsm_name #generics #where_clause {
+                states: #enum_type,
+                #entered_at_field
+                #( #sub_machine_fields: #sub_machine_types, )*
+                #marker_field
+            }
+
+            impl #impl_generics #machine_type #where_clause {
+                #[allow(dead_code)]
+                fn is_finished(&self) -> bool {
+                    #is_finished
+                }
+
+                fn new() -> Self {
+                    Self {
+                        states: #enum_name::#init_state_entry(None),
+                        #entered_at_init
+                        #( #sub_machine_fields: #sub_machine_names::new(), )*
+                        #marker_init
+                    }
+                }
+
+                // Resumes the last active sub-state if there is a history or otherwise creates
+                // the initial sub-state from the composite state and enters it
+                #asyncness fn enter(&mut self, parent: &mut #parent, #deep_param: bool #forward_params) -> ::core::result::Result<(), #error_type> {
+                    #resume
                     #[inline(always)]
-                    fn run_state(mut state: #init_state) -> ::core::result::Result<#enum_name, #error_type> {
+                    #asyncness fn run_state #fn_generics (mut state: #init_state #scope_params) -> ::core::result::Result<#enum_type, #error_type> #fn_where_clause {
+                        #record_entry
                         #init_state_tokens
+                        #trace_entry
+                        #init_sub_machine
                         Ok(#enum_name::#init_state_entry(Some(state)))
                     }
-                    self.states = run_state(state)?;
-                    #trace_start
+                    #borrow_scope
+                    let state = sfsm::CompositeState::<#init_state>::initial_state(parent);
+                    self.states = run_state(state #scope_args) #awaiting?;
                     Ok(())
                 }
 
-                fn step(&mut self) -> ::core::result::Result<(), Self::Error> {
+                // Creates the initial sub-state from the composite state without entering it when
+                // the state machine is restored
+                #[allow(dead_code)]
+                fn restore(&mut self, parent: &mut #parent #clock_param) {
+                    let mut initial_state = sfsm::CompositeState::<#init_state>::initial_state(parent);
+                    let state = &mut initial_state;
+                    #restore_entry
+                    #restore_sub_machine
+                    self.states = #enum_name::#init_state_entry(Some(initial_state));
+                }
+
+                #asyncness fn step(&mut self #forward_params) -> ::core::result::Result<(), #error_type> {
                     use #enum_name::*;
+                    #borrow_scope
                     let ref mut e = self.states;
                     *e = match *e {
                         #( #states, )*
@@ -173,61 +948,217 @@ impl ToTokens for StateMachineToTokens<'_> {
                     Ok(())
                 }
 
-                fn stop(mut self) -> ::core::result::Result<Self::StatesEnum, Self::Error> {
-                    #trace_stop
+                // Exits the active sub-state when the composite state is left
+                #[allow(dead_code)]
+                #asyncness fn exit(&mut self #context_param) -> ::core::result::Result<(), #error_type> {
+                    #borrow_scope
+                    #exit_states
+                    Ok(())
+                }
+
+                #asyncness fn stop(&mut self #context_param) -> ::core::result::Result<(), #error_type> {
+                    #borrow_scope
                     match self.states {
-                        # ( #exits )*,
+                        # ( #stops )*
+                        _ => Err(#sfsm_error::Internal),
                     }
                 }
 
-                fn peek_state(&self) -> &Self::StatesEnum {
-                   return &self.states;
+                #asyncness fn reset(&mut self #context_param) -> ::core::result::Result<(), #error_type> {
+                    #borrow_scope
+                    match self.states {
+                        # ( #resets )*
+                        _ => Err(#sfsm_error::Internal),
+                    }
                 }
+
+                #handle
             }
 
-            // Implement the is_state checks
-            #(#is_states)*
+            #( #sub_machines_to_tokens )*
         };
 
         tokens.extend(token_steam);
     }
 }
 
+pub struct ResumeStateToTokens<'a> {
+    machine: &'a Machine,
+    state: &'a State,
+}
+
+impl<'a> ResumeStateToTokens<'a> {
+    pub fn new(machine: &'a Machine, state: &'a State) -> Self {
+        Self { machine, state }
+    }
+}
+
+impl ToTokens for ResumeStateToTokens<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let enum_name = &self.machine.enum_name;
+        let state_entry = &self.state.enum_name;
+        let state = &self.state;
+        let sfsm_error = &self.machine.sfsm_error;
+        let custom_error = &self.machine.custom_error;
+        let error_type = quote! {
+            #sfsm_error #custom_error
+        };
+        let state_trait = &self.machine.trait_definitions.state_trait;
+        let entry = &self.machine.trait_definitions.entry;
+        let context_arg = self.machine.context_arg();
+        let scope_params = self.machine.scope_params();
+        let enum_type = self.machine.enum_type();
+        let (fn_generics, fn_where_clause) = self.machine.fn_generics();
+        let scope_args = self.machine.scope_args();
+        let record_entry = self.machine.record_entry();
+        let asyncness = self.machine.asyncness();
+        let awaiting = self.machine.awaiting();
+
+        let state_entry_tokens = TransitToErrorToTokens::wrap_if_fallible(
+            self.machine,
+            quote! {
+                #state_trait::#entry(&mut state #context_arg)
+            },
+            self.state,
+        );
+
+        // The sub-states of a resumed composite state are resumed as well with a deep history
+        let (deep_param, deep_arg) = if self.state.regions.is_empty() {
+            (quote! {}, quote! {})
+        } else {
+            (quote! { , deep: bool }, quote! { , deep })
+        };
+        let enter_sub_machines =
+            TransitToTokens::enter_sub_machines(self.machine, self.state, quote! { deep });
+
+        let trace_resume = trace::trace(trace::format_log(
+            &self.machine.name.to_string(),
+            "Resume",
+            &self.state.get_name_type(),
+        ));
+
+        let token_steam = quote! {
+            #enum_name::#state_entry(ref mut state_option @ Some(_)) => {
+                #[inline(always)]
+                #asyncness fn run_state #fn_generics (mut state: #state #deep_param #scope_params) -> ::core::result::Result<#enum_type, #error_type> #fn_where_clause {
+                    #record_entry
+                    #state_entry_tokens
+                    #trace_resume
+                    #enter_sub_machines
+                    Ok(#enum_name::#state_entry(Some(state)))
+                }
+                let state = state_option.take().ok_or(#sfsm_error::Internal)?;
+                Some(run_state(state #deep_arg #scope_args) #awaiting?)
+            }
+        };
+
+        tokens.extend(token_steam);
+    }
+}
+
+/// How a state is left
+#[derive(Clone, Copy, PartialEq)]
+enum Leaving {
+    /// The state is left by a transition of its composite state
+    Exit,
+    /// The state machine is stopped
+    Stop,
+    /// The state machine is reset, so only the exit hooks run
+    Reset,
+}
+
 pub struct StopToTokens<'a> {
     machine: &'a Machine,
     state: &'a State,
+    leaving: Leaving,
 }
 
 impl<'a> StopToTokens<'a> {
     pub fn new(machine: &'a Machine, state: &'a State) -> Self {
-        Self { machine, state }
+        Self {
+            machine,
+            state,
+            leaving: Leaving::Stop,
+        }
+    }
+
+    /// Only leaves the state without calling the stop hook. Used for the sub-states of a
+    /// composite state that is left.
+    pub fn leave(machine: &'a Machine, state: &'a State) -> Self {
+        Self {
+            machine,
+            state,
+            leaving: Leaving::Exit,
+        }
+    }
+
+    /// Only calls the exit hooks of the state and its sub-states when the state machine is
+    /// reset. Neither the transition actions nor the stop hook run.
+    pub fn reset(machine: &'a Machine, state: &'a State) -> Self {
+        Self {
+            machine,
+            state,
+            leaving: Leaving::Reset,
+        }
     }
 }
 
 impl ToTokens for StopToTokens<'_> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        if self.leaving != Leaving::Exit {
+            return self.stop_tokens(tokens);
+        }
         let state_entry = &self.state.enum_name;
         let enum_name = &self.machine.enum_name;
+
+        // With exclusive actions, no transition is taken while leaving and thus no action runs
+        let leaving_transits: Vec<&State> = if self.machine.options.exclusive_actions {
+            vec![]
+        } else {
+            self.state
+                .transits
+                .iter()
+                .map(|transit| &transit.dst)
+                .collect()
+        };
         let transition_actions =
-            ExitTransitionToTokens::new(&self.state.transits, self.machine, self.state);
+            ExitTransitionToTokens::new(leaving_transits, self.machine, self.state);
 
         let state_trait = &self.machine.trait_definitions.state_trait;
         let exit = &self.machine.trait_definitions.exit;
         let sfsm_error = &self.machine.sfsm_error;
 
+        let context_arg = self.machine.context_arg();
+
         let exit_token_stream = TransitToErrorToTokens::wrap_if_fallible(
             self.machine,
             quote! {
-                    #state_trait::#exit(&mut state)
+                    #state_trait::#exit(&mut state #context_arg)
             },
             self.state,
         );
 
+        // The sub-states of a composite state are left first
+        let sub_machine_tokens = TransitToTokens::call_sub_machines(
+            self.machine,
+            self.state,
+            true,
+            |field, sub_machine_name| quote! { #sub_machine_name::exit(#field #context_arg) },
+        );
+
+        let trace_exit = trace::trace(trace::format_log(
+            &self.machine.name.to_string(),
+            "Exit",
+            &self.state.get_name_type(),
+        ));
+
         let token_steam = quote! {
             #enum_name::#state_entry(ref mut state_option) => {
                 let mut state = state_option.take().ok_or(#sfsm_error::Internal)?;
+                #sub_machine_tokens
                 #exit_token_stream
                 #transition_actions
+                #trace_exit
                 Ok(#enum_name::#state_entry(Some(state)))
             }
         };
@@ -236,6 +1167,165 @@ impl ToTokens for StopToTokens<'_> {
     }
 }
 
+impl StopToTokens<'_> {
+    /// Generates the arm that stops or resets the state in place. The state is only borrowed, so
+    /// it stays active if one of its hooks fails. Errors are returned to the caller instead of
+    /// being routed to the error state.
+    fn stop_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let state_entry = &self.state.enum_name;
+        let enum_name = &self.machine.enum_name;
+        let state_trait = &self.machine.trait_definitions.state_trait;
+        let exit = &self.machine.trait_definitions.exit;
+        let stop = &self.machine.trait_definitions.stop;
+        let transit_trait = &self.machine.trait_definitions.transit_trait;
+        let action = &self.machine.trait_definitions.action;
+        let context_arg = self.machine.context_arg();
+        let context_generic = self.machine.context_generic();
+        let awaiting = self.machine.awaiting();
+
+        let wrap = |call: TokenStream| match self.machine.mode {
+            Mode::NonFallible => quote! {
+                #call #awaiting;
+            },
+            Mode::Fallible => quote! {
+                #call #awaiting.map_err(|err| {sfsm::ExtendedSfsmError::Custom(err)})?;
+            },
+        };
+
+        // With exclusive actions, no transition is taken while stopping and thus no action runs.
+        // A reset does not take any transition either.
+        let resetting = self.leaving == Leaving::Reset;
+        let transition_actions: Vec<TokenStream> =
+            if self.machine.options.exclusive_actions || resetting {
+                vec![]
+            } else {
+                self.state
+                    .transits
+                    .iter()
+                    .map(|transit| {
+                        let dst = &transit.dst;
+                        wrap(quote! {
+                            #transit_trait::<#dst #context_generic>::#action(state #context_arg)
+                        })
+                    })
+                    .collect()
+            };
+        let exit_token_stream = wrap(quote! { #state_trait::#exit(state #context_arg) });
+        let (stop_token_stream, trace_exit) = if resetting {
+            let trace_exit = trace::trace(trace::format_log(
+                &self.machine.name.to_string(),
+                "Exit",
+                &self.state.get_name_type(),
+            ));
+            (quote! {}, trace_exit)
+        } else {
+            let stop_token_stream = wrap(quote! { #state_trait::#stop(state #context_arg) });
+            (stop_token_stream, quote! {})
+        };
+
+        // The sub-states of a composite state are stopped or reset first
+        let mut sub_machines = self.machine.sub_machines_of(self.state);
+        sub_machines.reverse();
+        let sub_machine_tokens = sub_machines.iter().map(|(field, sub_machine)| {
+            let sub_machine_name = &sub_machine.name;
+            if resetting {
+                quote! { #sub_machine_name::reset(#field #context_arg) #awaiting?; }
+            } else {
+                quote! { #sub_machine_name::stop(#field #context_arg) #awaiting?; }
+            }
+        });
+
+        let token_steam = quote! {
+            #enum_name::#state_entry(Some(ref mut state)) => {
+                #( #sub_machine_tokens )*
+                #exit_token_stream
+                #( #transition_actions )*
+                #trace_exit
+                #stop_token_stream
+                Ok(())
+            }
+        };
+
+        tokens.extend(token_steam);
+    }
+}
+
+pub struct StartStateToTokens<'a> {
+    machine: &'a Machine,
+    state: &'a State,
+}
+
+impl<'a> StartStateToTokens<'a> {
+    pub fn new(machine: &'a Machine, state: &'a State) -> Self {
+        Self { machine, state }
+    }
+}
+
+impl ToTokens for StartStateToTokens<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let state_entry = &self.state.enum_name;
+        let state = &self.state;
+        let enum_name = &self.machine.enum_name;
+        let (impl_generics, _, where_clause) = self.machine.generics.split_for_impl();
+        let machine_type = self.machine.machine_type();
+        let enum_type = self.machine.enum_type();
+        let (fn_generics, fn_where_clause) = self.machine.fn_generics();
+        let state_trait = &self.machine.trait_definitions.state_trait;
+        let entry = &self.machine.trait_definitions.entry;
+        let context_arg = self.machine.context_arg();
+        let scope_params = self.machine.scope_params();
+        let scope_args = self.machine.scope_args();
+        let borrow_scope = self.machine.borrow_scope();
+        let record_entry = self.machine.record_entry();
+        let asyncness = self.machine.asyncness();
+        let awaiting = self.machine.awaiting();
+        let sfsm_error = &self.machine.sfsm_error;
+        let custom_error = &self.machine.custom_error;
+        let error_type = quote! {
+            #sfsm_error #custom_error
+        };
+        let start_state_trait = if self.machine.asynchronous {
+            quote! { sfsm::AsyncStartState }
+        } else {
+            quote! { sfsm::StartState }
+        };
+
+        let state_entry_tokens = TransitToErrorToTokens::wrap_if_fallible(
+            self.machine,
+            quote! {
+                #state_trait::#entry(&mut state #context_arg)
+            },
+            self.state,
+        );
+        let enter_sub_machines =
+            TransitToTokens::enter_sub_machines(self.machine, self.state, quote! { false });
+        let trace_start = trace::trace(trace::format_log(
+            &self.machine.name.to_string(),
+            "Start",
+            &self.state.get_name_type(),
+        ));
+
+        let token_steam = quote! {
+            impl #impl_generics #start_state_trait<#state> for #machine_type #where_clause {
+                #asyncness fn start_state(&mut self, mut state: #state) -> ::core::result::Result<(), Self::Error> {
+                    #[inline(always)]
+                    #asyncness fn run_state #fn_generics (mut state: #state #scope_params) -> ::core::result::Result<#enum_type, #error_type> #fn_where_clause {
+                        #record_entry
+                        #state_entry_tokens
+                        #enter_sub_machines
+                        Ok(#enum_name::#state_entry(Some(state)))
+                    }
+                    #borrow_scope
+                    self.states = run_state(state #scope_args) #awaiting?;
+                    #trace_start
+                    Ok(())
+                }
+            }
+        };
+        tokens.extend(token_steam);
+    }
+}
+
 pub struct IsStateToTokens<'a> {
     machine: &'a Machine,
     state: &'a State,
@@ -252,15 +1342,68 @@ impl ToTokens for IsStateToTokens<'_> {
         let state_entry = &self.state.enum_name;
         let state = &self.state;
         let enum_name = &self.machine.enum_name;
-        let sfsm_name = &self.machine.name;
+        let (impl_generics, _, where_clause) = self.machine.generics.split_for_impl();
+        let machine_type = self.machine.machine_type();
         let token_steam = quote! {
-            impl sfsm::IsState<#state> for #sfsm_name {
+            impl #impl_generics sfsm::IsState<#state> for #machine_type #where_clause {
                 fn is_state(&self) -> bool {
                     matches!(self.states, #enum_name::#state_entry(_))
                 }
             }
 
+            impl #impl_generics sfsm::GetState<#state> for #machine_type #where_clause {
+                fn get_state(&self) -> ::core::option::Option<&#state> {
+                    #[allow(unreachable_patterns)]
+                    match &self.states {
+                        #enum_name::#state_entry(state) => state.as_ref(),
+                        _ => None,
+                    }
+                }
+
+                fn get_state_mut(&mut self) -> ::core::option::Option<&mut #state> {
+                    #[allow(unreachable_patterns)]
+                    match &mut self.states {
+                        #enum_name::#state_entry(state) => state.as_mut(),
+                        _ => None,
+                    }
+                }
+            }
+
+        };
+        tokens.extend(token_steam);
+    }
+}
+
+pub struct IsFinishedToTokens<'a> {
+    machine: &'a Machine,
+}
+
+impl<'a> IsFinishedToTokens<'a> {
+    pub fn new(machine: &'a Machine) -> Self {
+        Self { machine }
+    }
+}
+
+impl ToTokens for IsFinishedToTokens<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let enum_name = &self.machine.enum_name;
+        let final_entries: Vec<&Ident> = self
+            .machine
+            .states
+            .iter()
+            .filter(|state| state.is_final)
+            .map(|state| &state.enum_name)
+            .collect();
+
+        // A state machine without a final state never finishes
+        let token_steam = if final_entries.is_empty() {
+            quote! { false }
+        } else {
+            quote! {
+                matches!(self.states, #( #enum_name::#final_entries(_) )|*)
+            }
         };
+
         tokens.extend(token_steam);
     }
 }
@@ -317,11 +1460,18 @@ impl<'a> ToTokens for StateToTokens<'a> {
 
         let state_trait = &self.machine.trait_definitions.state_trait;
         let execute = &self.machine.trait_definitions.execute;
+        let context_arg = self.machine.context_arg();
+        let scope_params = self.machine.scope_params();
+        let enum_type = self.machine.enum_type();
+        let (fn_generics, fn_where_clause) = self.machine.fn_generics();
+        let scope_args = self.machine.scope_args();
+        let asyncness = self.machine.asyncness();
+        let awaiting = self.machine.awaiting();
 
         let state_execute_tokens = TransitToErrorToTokens::wrap_if_fallible(
             self.machine,
             quote! {
-                    #state_trait::#execute(&mut state)
+                    #state_trait::#execute(&mut state #context_arg)
             },
             self.state,
         );
@@ -332,19 +1482,29 @@ impl<'a> ToTokens for StateToTokens<'a> {
             &self.state.get_name_type(),
         ));
 
+        // The sub-states of a composite state are only stepped if it does not transit itself
+        let forward_args = self.machine.forward_args();
+        let sub_machine_step = TransitToTokens::call_sub_machines(
+            self.machine,
+            self.state,
+            false,
+            |field, sub_machine_name| quote! { #sub_machine_name::step(#field #forward_args) },
+        );
+
         let token_steam = quote! {
                 #enum_name::#state_entry(ref mut state_option) => {
                     #[inline(always)]
-                    fn run_state(state_option: &mut ::core::option::Option<#state>) -> ::core::result::Result<#enum_name, #error_type> {
+                    #asyncness fn run_state #fn_generics (state_option: &mut ::core::option::Option<#state> #scope_params) -> ::core::result::Result<#enum_type, #error_type> #fn_where_clause {
                         let mut state = state_option.take().ok_or(#sfsm_error::Internal)?;
                         #trace_execute
                         #state_execute_tokens
                         #( #transition_checks )*
                         {
+                            #sub_machine_step
                             return Ok(#enum_name::#state_entry(Some(state)));
                         }
                     }
-                    run_state(state_option)?
+                    run_state(state_option #scope_args) #awaiting?
                 }
         };
 
@@ -352,12 +1512,26 @@ impl<'a> ToTokens for StateToTokens<'a> {
     }
 }
 
+impl ToTokens for Event {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let path = &self.path;
+        let name = &self.name;
+        let generics = &self.generics;
+        let token_steam = quote! {
+            #path #name #generics
+        };
+
+        tokens.extend(token_steam);
+    }
+}
+
 impl ToTokens for State {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let path = &self.path;
         let name = &self.name;
         let generics = &self.generics;
         let token_steam = quote! {
-            #name #generics
+            #path #name #generics
         };
 
         tokens.extend(token_steam);
@@ -367,11 +1541,11 @@ impl ToTokens for State {
 pub struct TransitionToTokens<'a> {
     machine: &'a Machine,
     state: &'a State,
-    target: &'a State,
+    target: &'a Transit,
 }
 
 impl<'a> TransitionToTokens<'a> {
-    pub fn new(machine: &'a Machine, state: &'a State, target: &'a State) -> Self {
+    pub fn new(machine: &'a Machine, state: &'a State, target: &'a Transit) -> Self {
         Self {
             machine,
             state,
@@ -382,85 +1556,249 @@ impl<'a> TransitionToTokens<'a> {
 
 impl ToTokens for TransitionToTokens<'_> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-        let target_state_entry = &self.target.enum_name;
-        let enum_name = &self.machine.enum_name;
-        let entry = &self.machine.trait_definitions.entry;
-        let exit_transitions =
-            ExitTransitionToTokens::new(&self.state.transits, self.machine, self.state);
-
-        let state_trait = &self.machine.trait_definitions.state_trait;
         let transit_trait = &self.machine.trait_definitions.transit_trait;
-        let exit = &self.machine.trait_definitions.exit;
-
-        let exit_token_stream = TransitToErrorToTokens::wrap_if_fallible(
+        let context_arg = self.machine.context_arg();
+        let context_generic = self.machine.context_generic();
+        let awaiting = self.machine.awaiting();
+
+        // Either run the action of the taken transition only or the actions of all of them
+        let taken_transits: Vec<&State> = if self.machine.options.exclusive_actions {
+            vec![&self.target.dst]
+        } else {
+            self.state
+                .transits
+                .iter()
+                .map(|transit| &transit.dst)
+                .collect()
+        };
+        let exit_transitions =
+            ExitTransitionToTokens::new(taken_transits, self.machine, self.state);
+        let target_state = TransitToTokens::find_target(self.machine, &self.target.dst);
+        let transit = TransitToTokens::transit(
             self.machine,
+            self.state,
+            &self.target.dst,
             quote! {
-                    #state_trait::#exit(&mut state)
+                #exit_transitions
             },
-            self.state,
         );
 
-        let target_state = self
-            .machine
+        // A timeout transition is only checked once the state has been active long enough. The
+        // elapsed ticks are scaled instead of dividing the duration by the ticks, so durations
+        // that are not a multiple of a tick are rounded up instead of being taken too early.
+        let elapsed = match (&self.target.timeout, &self.machine.options.clock) {
+            (Some(timeout), Some(clock)) => {
+                let value = &timeout.value;
+                let per_second = timeout.per_second;
+                quote! {
+                    sfsm::Clock::now(clock).wrapping_sub(*entered_at).saturating_mul(#per_second) >= #value * <#clock as sfsm::Clock>::TICKS_PER_SECOND &&
+                }
+            }
+            _ => quote! {},
+        };
+
+        // A completion transition is only checked once all regions have reached a final state
+        let completed = if self.target.completion {
+            let sub_machines = self.machine.sub_machines_of(self.state);
+            let checks = sub_machines.iter().map(|(field, sub_machine)| {
+                let sub_machine_name = &sub_machine.name;
+                quote! { #sub_machine_name::is_finished(#field) && }
+            });
+            quote! { #( #checks )* }
+        } else {
+            quote! {}
+        };
+
+        let token_steam = quote! {
+            if #elapsed #completed #transit_trait::<#target_state #context_generic>::guard(&state #context_arg) #awaiting == sfsm::TransitGuard::Transit {
+                #transit
+            } else
+        };
+
+        tokens.extend(token_steam);
+    }
+}
+
+pub struct TransitToTokens {}
+
+impl<'a> TransitToTokens {
+    /// Finds the declared state a transition points to
+    fn find_target(machine: &'a Machine, target: &State) -> &'a State {
+        machine
+            .states
+            .iter()
+            .find(|state| state.enum_name == target.enum_name)
+            .expect("Internal error. Expected to find a state matching the transition")
+    }
+
+    /// Calls a function of the machines holding the sub-states of a composite state. The
+    /// regions are called in the order of their definition or in reverse order when they are
+    /// left.
+    fn call_sub_machines<F>(
+        machine: &'a Machine,
+        state: &State,
+        reverse: bool,
+        call: F,
+    ) -> TokenStream
+    where
+        F: Fn(&Ident, &Ident) -> TokenStream,
+    {
+        let mut sub_machines = machine
             .states
             .iter()
-            .find(|state| state.enum_name == *target_state_entry)
-            .expect("Internal error. Expected to find a state matching the transition");
+            .find(|known| known.enum_name == state.enum_name)
+            .map(|known| machine.sub_machines_of(known))
+            .unwrap_or_default();
+        if reverse {
+            sub_machines.reverse();
+        }
+        let awaiting = machine.awaiting();
+        let calls = sub_machines.iter().map(|(field, sub_machine)| {
+            let call = call(field, &sub_machine.name);
+            TransitToErrorToTokens::wrap_sub_machine(machine, quote! { #call #awaiting }, state)
+        });
+        quote! {
+            #( #calls )*
+        }
+    }
+
+    /// Generates the code that enters the sub-states if the state is a composite state. If deep
+    /// is set, the sub-states are resumed from their history.
+    fn enter_sub_machines(machine: &'a Machine, state: &State, deep: TokenStream) -> TokenStream {
+        let forward_args = machine.forward_args();
+        TransitToTokens::call_sub_machines(machine, state, false, |field, sub_machine_name| {
+            quote! { #sub_machine_name::enter(#field, &mut state, #deep #forward_args) }
+        })
+    }
+
+    /// Generates the code that creates the initial sub-states if the state is a composite state
+    /// without entering them. The sub-machines are fields of the target and the clock is only
+    /// passed on if the state machine has one.
+    fn restore_sub_machines(
+        machine: &'a Machine,
+        state: &State,
+        target: TokenStream,
+        clock: TokenStream,
+    ) -> TokenStream {
+        let clock_arg = if machine.options.clock.is_some() {
+            quote! { , #clock }
+        } else {
+            quote! {}
+        };
+        let calls = machine
+            .sub_machines_of(state)
+            .into_iter()
+            .map(|(field, sub_machine)| {
+                let sub_machine_name = &sub_machine.name;
+                quote! { #sub_machine_name::restore(&mut #target.#field, state #clock_arg); }
+            });
+        quote! {
+            #( #calls )*
+        }
+    }
+
+    /// Counts a taken transition and remembers it if it is the first one taken
+    fn record_transition(machine: &'a Machine, state: &State, target: &State) -> TokenStream {
+        let state_id = &machine.state_id_name;
+        let source = state.id_name();
+        let destination = target.id_name();
+        quote! {
+            *transitions += 1;
+            if transition.is_none() {
+                *transition = Some((#state_id::#source, #state_id::#destination));
+            }
+        }
+    }
+
+    /// Generates the code that leaves the current state, runs the actions, converts the state
+    /// into the target state and enters it.
+    fn transit(
+        machine: &'a Machine,
+        state: &State,
+        target: &State,
+        actions: TokenStream,
+    ) -> TokenStream {
+        let target_state_entry = &target.enum_name;
+        let enum_name = &machine.enum_name;
+        let entry = &machine.trait_definitions.entry;
+        let state_trait = &machine.trait_definitions.state_trait;
+        let exit = &machine.trait_definitions.exit;
+        let context_arg = machine.context_arg();
+        let record_entry = machine.record_entry();
+        let record_transition = TransitToTokens::record_transition(machine, state, target);
+
+        let exit_token_stream = TransitToErrorToTokens::wrap_if_fallible(
+            machine,
+            quote! {
+                    #state_trait::#exit(&mut state #context_arg)
+            },
+            state,
+        );
+
+        let target_state = TransitToTokens::find_target(machine, target);
 
         let state_entry_tokens: TokenStream = TransitToErrorToTokens::wrap_if_fallible(
-            self.machine,
+            machine,
             quote! {
-                #state_trait::#entry(&mut state)
+                #state_trait::#entry(&mut state #context_arg)
             },
-            self.state,
+            state,
         );
 
         let trace_entry = trace::trace(trace::format_log(
-            &self.machine.name.to_string(),
+            &machine.name.to_string(),
             "Enter",
-            &self.target.get_name_type(),
+            &target.get_name_type(),
         ));
         let trace_exit = trace::trace(trace::format_log(
-            &self.machine.name.to_string(),
+            &machine.name.to_string(),
             "Exit",
-            &self.state.get_name_type(),
+            &state.get_name_type(),
         ));
         let trace_transit = trace::trace(trace::format_log(
-            &self.machine.name.to_string(),
+            &machine.name.to_string(),
             "Transit",
             &format!(
                 "From {} to {}",
-                &self.state.get_name_type(),
-                &self.target.get_name_type()
+                &state.get_name_type(),
+                &target.get_name_type()
             ),
         ));
 
-        let token_steam = quote! {
-            if #transit_trait::<#target_state>::guard(&state) == sfsm::TransitGuard::Transit {
-                #exit_token_stream
-                #exit_transitions
-                #trace_exit
-                #trace_transit
-                let mut state: #target_state = state.into();
-
-                #state_entry_tokens
-                #trace_entry
-                return Ok(#enum_name::#target_state_entry(Some(state)));
-            } else
-        };
-
-        tokens.extend(token_steam);
+        // The sub-states are left before and entered after their composite state
+        let exit_sub_machine =
+            TransitToTokens::call_sub_machines(machine, state, true, |field, sub_machine_name| {
+                quote! { #sub_machine_name::exit(#field #context_arg) }
+            });
+        let enter_sub_machine =
+            TransitToTokens::enter_sub_machines(machine, target, quote! { false });
+
+        quote! {
+            #exit_sub_machine
+            #exit_token_stream
+            #actions
+            #trace_exit
+            #trace_transit
+            let mut state: #target_state = state.into();
+            #record_transition
+            #record_entry
+
+            #state_entry_tokens
+            #trace_entry
+            #enter_sub_machine
+            return Ok(#enum_name::#target_state_entry(Some(state)));
+        }
     }
 }
 
 pub struct ExitTransitionToTokens<'a> {
     machine: &'a Machine,
-    transits: &'a Vec<State>,
+    transits: Vec<&'a State>,
     state: &'a State,
 }
 
 impl<'a> ExitTransitionToTokens<'a> {
-    pub fn new(transits: &'a Vec<State>, machine: &'a Machine, state: &'a State) -> Self {
+    pub fn new(transits: Vec<&'a State>, machine: &'a Machine, state: &'a State) -> Self {
         Self {
             transits,
             machine,
@@ -471,9 +1809,11 @@ impl<'a> ExitTransitionToTokens<'a> {
 
 impl ToTokens for ExitTransitionToTokens<'_> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-        let transits = self.transits;
+        let transits = &self.transits;
         let transit_trait = &self.machine.trait_definitions.transit_trait;
         let action = &self.machine.trait_definitions.action;
+        let context_arg = self.machine.context_arg();
+        let context_generic = self.machine.context_generic();
 
         let exit_token_streams: Vec<proc_macro2::TokenStream> = transits
             .iter()
@@ -481,7 +1821,7 @@ impl ToTokens for ExitTransitionToTokens<'_> {
                 TransitToErrorToTokens::wrap_if_fallible(
                     self.machine,
                     quote! {
-                        #transit_trait::<#transits>::#action(&mut state)
+                        #transit_trait::<#transits #context_generic>::#action(&mut state #context_arg)
                     },
                     self.state,
                 )
@@ -516,10 +1856,13 @@ impl ToTokens for StateMessageToTokens<'_> {
         let state = &self.state_message.state;
         let enum_name = &self.messages.enum_name;
         let sfsm_name = &self.messages.name;
+        let (impl_generics, type_generics, where_clause) = self.messages.generics.split_for_impl();
 
         let token_steam = match message_dir {
             MessageDir::Push(message) => {
+                let message_path = &message.path;
                 let message_name = &message.name;
+                let message_name = quote! { #message_path #message_name };
                 let message_args = &message.generics;
                 let trace_push = trace::message(trace::format_log(
                     &self.messages.name.to_string(),
@@ -527,7 +1870,7 @@ impl ToTokens for StateMessageToTokens<'_> {
                     &format!("{} to {}", &message.get_name_type(), &state.get_name_type()),
                 ));
                 quote! {
-                    impl sfsm::PushMessage<#state, #message_name #message_args> for #sfsm_name {
+                    impl #impl_generics sfsm::PushMessage<#state, #message_name #message_args> for #sfsm_name #type_generics #where_clause {
                         fn push_message(&mut self, message: #message_name #message_args) -> ::core::result::Result<(), sfsm::MessageError<#message_name #message_args>> {
                             match self.states {
                                 #enum_name::#enum_entry(ref mut state_option) => {
@@ -547,7 +1890,9 @@ impl ToTokens for StateMessageToTokens<'_> {
                 }
             }
             MessageDir::Poll(message) => {
+                let message_path = &message.path;
                 let message_name = &message.name;
+                let message_name = quote! { #message_path #message_name };
                 let message_args = &message.generics;
                 let trace_poll = trace::message(trace::format_log(
                     &self.messages.name.to_string(),
@@ -559,7 +1904,7 @@ impl ToTokens for StateMessageToTokens<'_> {
                     ),
                 ));
                 quote! {
-                    impl sfsm::PollMessage<#state, #message_name #message_args> for #sfsm_name {
+                    impl #impl_generics sfsm::PollMessage<#state, #message_name #message_args> for #sfsm_name #type_generics #where_clause {
                         fn poll_message(&mut self) -> ::core::result::Result<::core::option::Option<#message_name #message_args>, sfsm::MessageError<()>> {
                             match self.states {
                                 #enum_name::#enum_entry(ref mut state_option) => {
@@ -605,8 +1950,270 @@ impl ToTokens for MessagesToTokens<'_> {
             .map(|message| StateMessageToTokens::new(message, self.messages))
             .collect();
 
+        let sfsm_name = &self.messages.name;
+        let (impl_generics, type_generics, where_clause) = self.messages.generics.split_for_impl();
+        let message_dot = dot::message_dot(self.messages);
+        let message_mermaid = state_diagram::message_diagram(self.messages, Flavor::Mermaid);
+        let message_plantuml = state_diagram::message_diagram(self.messages, Flavor::PlantUml);
+
         let token_steam = quote! {
             #(#messages_to_tokens)*
+
+            impl #impl_generics #sfsm_name #type_generics #where_clause {
+                /// Writes the graph of the state machine in the DOT language of Graphviz together
+                /// with the messages that are pushed to or polled from its states
+                pub fn write_dot_with_messages<W: ::core::fmt::Write>(writer: &mut W) -> ::core::fmt::Result {
+                    writer.write_str(Self::DOT.strip_suffix("}\n").unwrap_or(Self::DOT))?;
+                    writer.write_str(#message_dot)?;
+                    writer.write_str("}\n")
+                }
+
+                /// Writes the Mermaid state diagram of the state machine together with the messages
+                /// that are pushed to or polled from its states
+                pub fn write_mermaid_with_messages<W: ::core::fmt::Write>(writer: &mut W) -> ::core::fmt::Result {
+                    writer.write_str(Self::MERMAID)?;
+                    writer.write_str(#message_mermaid)
+                }
+
+                /// Writes the PlantUML state diagram of the state machine together with the
+                /// messages that are pushed to or polled from its states
+                pub fn write_plantuml_with_messages<W: ::core::fmt::Write>(writer: &mut W) -> ::core::fmt::Result {
+                    writer.write_str(Self::PLANTUML.strip_suffix("@enduml\n").unwrap_or(Self::PLANTUML))?;
+                    writer.write_str(#message_plantuml)?;
+                    writer.write_str("@enduml\n")
+                }
+            }
+        };
+
+        tokens.extend(token_steam);
+    }
+}
+
+pub struct EventsToTokens<'a> {
+    machine: &'a Machine,
+}
+
+impl<'a> EventsToTokens<'a> {
+    pub fn new(machine: &'a Machine) -> Self {
+        Self { machine }
+    }
+}
+
+impl ToTokens for EventsToTokens<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        // The event handling is only generated if at least one transition is bound to an event
+        if self.machine.events.is_empty() {
+            return;
+        }
+
+        let (impl_generics, _, where_clause) = self.machine.generics.split_for_impl();
+        let machine_type = self.machine.machine_type();
+        let events_enum_name = &self.machine.events_enum_name;
+        let attribute = &self.machine.attributes;
+        let vis = &self.machine.visibility;
+        let events = &self.machine.events;
+        let event_entries: Vec<&Ident> = events.iter().map(|event| &event.enum_name).collect();
+        let borrow_scope = self.machine.borrow_scope();
+        let enum_name = &self.machine.enum_name;
+        let sfsm_error = &self.machine.sfsm_error;
+
+        let event_states: Vec<EventStateToTokens> = self
+            .machine
+            .states
+            .iter()
+            .map(|state| EventStateToTokens::new(self.machine, state))
+            .collect();
+
+        let token_steam = quote! {
+            #(#attribute)*
+            #vis enum #events_enum_name {
+                #( #event_entries(#events), )*
+            }
+
+            #(
+                impl From<#events> for #events_enum_name {
+                    fn from(event: #events) -> Self {
+                        #events_enum_name::#event_entries(event)
+                    }
+                }
+            )*
+
+            impl #impl_generics sfsm::HandleEvent for #machine_type #where_clause {
+                type Events = #events_enum_name;
+
+                fn handle(&mut self, event: Self::Events) -> ::core::result::Result<(), Self::Error> {
+                    let event = &event;
+                    #borrow_scope
+                    let ref mut e = self.states;
+                    *e = match *e {
+                        #( #event_states, )*
+                        #enum_name::Stopped => return Err(#sfsm_error::Internal),
+                    };
+                    Ok(())
+                }
+            }
+        };
+
+        tokens.extend(token_steam);
+    }
+}
+
+pub struct EventStateToTokens<'a> {
+    machine: &'a Machine,
+    state: &'a State,
+}
+
+impl<'a> EventStateToTokens<'a> {
+    pub fn new(machine: &'a Machine, state: &'a State) -> Self {
+        Self { machine, state }
+    }
+}
+
+impl ToTokens for EventStateToTokens<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let enum_name = &self.machine.enum_name;
+        let events_enum_name = &self.machine.events_enum_name;
+        let state_entry = &self.state.enum_name;
+        let state = &self.state;
+        let sfsm_error = &self.machine.sfsm_error;
+        let custom_error = &self.machine.custom_error;
+        let error_type = quote! {
+            #sfsm_error #custom_error
+        };
+        let forward_args = self.machine.forward_args();
+        let scope_params = self.machine.scope_params();
+        let enum_type = self.machine.enum_type();
+        let (fn_generics, fn_where_clause) = self.machine.fn_generics();
+        let scope_args = self.machine.scope_args();
+
+        // Events that are not handled by a composite state are passed on to its sub-states
+        let sub_machine_handle = TransitToTokens::call_sub_machines(
+            self.machine,
+            self.state,
+            false,
+            |field, sub_machine_name| {
+                quote! { #sub_machine_name::handle(#field, event #forward_args) }
+            },
+        );
+
+        if self.state.event_transits.is_empty() && self.state.regions.is_empty() {
+            tokens.extend(quote! {
+                #enum_name::#state_entry(ref mut state_option) => {
+                    let state = state_option.take().ok_or(#sfsm_error::Internal)?;
+                    #enum_name::#state_entry(Some(state))
+                }
+            });
+            return;
+        }
+
+        // Group the transitions by their event while keeping the order of the definition
+        let mut events: Vec<&Event> = vec![];
+        for transit in self.state.event_transits.iter() {
+            if !events
+                .iter()
+                .any(|event| event.enum_name == transit.event.enum_name)
+            {
+                events.push(&transit.event);
+            }
+        }
+
+        let event_arms: Vec<TokenStream> = events
+            .iter()
+            .map(|event| {
+                let event_entry = &event.enum_name;
+                let transition_checks: Vec<EventTransitionToTokens> = self
+                    .state
+                    .event_transits
+                    .iter()
+                    .filter(|transit| transit.event.enum_name == *event_entry)
+                    .map(|transit| EventTransitionToTokens::new(self.machine, self.state, transit))
+                    .collect();
+                let trace_handle = trace::step(trace::format_log(
+                    &self.machine.name.to_string(),
+                    "Handle",
+                    &format!(
+                        "{} in {}",
+                        &event.get_name_type(),
+                        &self.state.get_name_type()
+                    ),
+                ));
+                quote! {
+                    #events_enum_name::#event_entry(event) => {
+                        #trace_handle
+                        #( #transition_checks )* {}
+                    }
+                }
+            })
+            .collect();
+
+        let token_steam = quote! {
+            #enum_name::#state_entry(ref mut state_option) => {
+                #[inline(always)]
+                fn run_event #fn_generics (state_option: &mut ::core::option::Option<#state>, event: &#events_enum_name #scope_params) -> ::core::result::Result<#enum_type, #error_type> #fn_where_clause {
+                    let mut state = state_option.take().ok_or(#sfsm_error::Internal)?;
+                    #[allow(unreachable_patterns)]
+                    match event {
+                        #( #event_arms )*
+                        _ => {}
+                    }
+                    #sub_machine_handle
+                    Ok(#enum_name::#state_entry(Some(state)))
+                }
+                run_event(state_option, event #scope_args)?
+            }
+        };
+
+        tokens.extend(token_steam);
+    }
+}
+
+pub struct EventTransitionToTokens<'a> {
+    machine: &'a Machine,
+    state: &'a State,
+    transit: &'a EventTransit,
+}
+
+impl<'a> EventTransitionToTokens<'a> {
+    pub fn new(machine: &'a Machine, state: &'a State, transit: &'a EventTransit) -> Self {
+        Self {
+            machine,
+            state,
+            transit,
+        }
+    }
+}
+
+impl ToTokens for EventTransitionToTokens<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let event_trait = &self.machine.trait_definitions.event_trait;
+        let action = &self.machine.trait_definitions.action;
+        let event = &self.transit.event;
+        let target_state = TransitToTokens::find_target(self.machine, &self.transit.dst);
+        let context_arg = self.machine.context_arg();
+        let context_generic = self.machine.context_generic();
+
+        // Only the action of the handled event runs. The actions of the polled transitions are
+        // not run, as none of them has been taken.
+        let event_action = TransitToErrorToTokens::wrap_if_fallible(
+            self.machine,
+            quote! {
+                #event_trait::<#target_state, #event #context_generic>::#action(&mut state, event #context_arg)
+            },
+            self.state,
+        );
+        let transit = TransitToTokens::transit(
+            self.machine,
+            self.state,
+            &self.transit.dst,
+            quote! {
+                #event_action
+            },
+        );
+
+        let token_steam = quote! {
+            if #event_trait::<#target_state, #event #context_generic>::guard(&state, event #context_arg) == sfsm::TransitGuard::Transit {
+                #transit
+            } else
         };
 
         tokens.extend(token_steam);
@@ -4,10 +4,20 @@ use crate::generators::{MessagesToTokens, StateMachineToTokens};
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::ItemFn;
+mod dot;
 mod generators;
+mod lints;
 mod parsers;
+mod persist;
+mod scxml;
+mod serialize;
+mod state_diagram;
 mod trace;
 mod types;
+#[cfg(feature = "scxml")]
+use crate::types::ScxmlMachine;
+#[cfg(feature = "async")]
+use crate::types::{AsyncMachine, AsyncTryMachine};
 use crate::types::{
     DeriveTransition, DeriveTransitionBase, Machine, MatchStateEntry, Messages, State, TryMachine,
 };
@@ -23,10 +33,21 @@ use crate::types::{
 ///     [StateN => StateN, ...]
 /// );
 ///```
-/// - StateMachineName: Defines the name of the state machine.
+/// - StateMachineName: Defines the name of the state machine. It can be preceded by attributes that are forwarded to the generated types and by ``` #[sfsm(...)] ``` options like ``` #[sfsm(exclusive_actions)] ```, ``` #[sfsm(context = Type)] ```, ``` #[sfsm(clock = Type)] ```, ``` #[sfsm(persist(version = N))] ``` or ``` #[sfsm(allow(unreachable, duplicate_transitions, dead_end))] ``` to allow the warnings of the graph checks. It can be followed by generic parameters and a where clause like ``` StateMachineName<'a, T> where T: Trait ```.
 /// - InitialState: The initial state the state machine will start with.
-/// - [State1, State2, StateN, ...]: Specifies all state structs that will be known to the state machine. Each state must implement the ``` State ``` trait.
+/// - [State1, State2, StateN, ...]: Specifies all state structs that will be known to the state machine. Each state must implement the ``` State ``` trait. States, events and messages can be given with their full path like ``` crate::states::State1 ```. The path then becomes part of the generated enum variant like ``` Crate__States__State1State ```.
+///   A composite state is declared with its sub-states like ``` StateN { InitialSubState, [SubState1, ...], [SubState1 => SubStateN, ...] } ``` and must implement the ``` CompositeState ``` trait. Multiple regions that are active at the same time are separated with ``` || ```. A composite state can be preceded by ``` #[sfsm(history = shallow)] ``` or ``` #[sfsm(history = deep)] ``` to resume its last active sub-states.
+///   A state can be marked with ``` #[sfsm(final)] ``` to end the state machine or the region it belongs to.
 /// - [StateN => StateN, ...]: Defines all transitions between states that can occur. For each transition, the state must implement the according ``` Transition ``` trait.
+///   A transition can be bound to an event with ``` StateN => StateN on Event ```. It is then only evaluated when the event is handled and the state must implement the according ``` EventTransition ``` trait instead.
+///   A transition can be delayed with ``` StateN => StateN after 500ms ```. It is then only taken once the state has been active for the given duration measured by the clock of the state machine.
+///   A transition of a composite state can wait for all its regions to reach a final state with ``` StateN => StateN after completion ```.
+///
+/// The generated state machine contains the ``` DOT ```, ``` MERMAID ``` and ``` PLANTUML ``` constants with its states and transitions as a Graphviz graph, a Mermaid and a PlantUML state diagram, and the ``` SCXML ``` constant with the definition as SCXML document.
+/// The active state is returned by ``` state_id() ``` as entry of the generated ``` StateMachineNameStateId ``` enum and by ``` state_name() ``` as name. Both return ``` None ``` while the state machine is stopped. The names of all states are listed in the ``` STATES ``` constant. The data of an active state is accessed with ``` get::<State>() ``` and ``` get_mut::<State>() ```.
+/// With the ``` serde ``` feature, the states enum and the state machine implement ``` Serialize ``` and ``` Deserialize ``` if all states do, and ``` restore(states) ``` creates a state machine in the given states without running their entries.
+/// With the ``` #[sfsm(persist(version = N))] ``` option, ``` encode_into(buffer) ``` and ``` decode_from(buffer) ``` persist the active state in a compact binary format. All states have to implement the ``` Persist ``` trait then.
+/// A stopped state machine is in the ``` Stopped ``` entry of the generated ``` StateMachineNameStates ``` enum until it is started again. ``` reset(state) ``` only runs the exit hooks of the active state and starts it with the given initial state. ``` start_in(state) ``` starts it in any of its states instead of the initial state.
 ///
 /// An example might look like this:
 /// ```rust
@@ -102,12 +123,23 @@ pub fn add_state_machine(input: TokenStream) -> TokenStream {
 ///     ErrorState
 /// );
 ///```
-/// - StateMachineName: Defines the name of the state machine.
+/// - StateMachineName: Defines the name of the state machine. It can be preceded by attributes that are forwarded to the generated types and by ``` #[sfsm(...)] ``` options like ``` #[sfsm(exclusive_actions)] ```, ``` #[sfsm(context = Type)] ```, ``` #[sfsm(clock = Type)] ```, ``` #[sfsm(persist(version = N))] ``` or ``` #[sfsm(allow(unreachable, duplicate_transitions, dead_end))] ``` to allow the warnings of the graph checks. It can be followed by generic parameters and a where clause like ``` StateMachineName<'a, T> where T: Trait ```.
 /// - InitialState: The initial state the state machine will start with.
-/// - [State1, State2, StateN, ...]: Specifies all state structs that will be known to the state machine. Each state must implement the ``` State ``` trait.
+/// - [State1, State2, StateN, ...]: Specifies all state structs that will be known to the state machine. Each state must implement the ``` State ``` trait. States, events and messages can be given with their full path like ``` crate::states::State1 ```. The path then becomes part of the generated enum variant like ``` Crate__States__State1State ```.
+///   A composite state is declared with its sub-states like ``` StateN { InitialSubState, [SubState1, ...], [SubState1 => SubStateN, ...] } ``` and must implement the ``` CompositeState ``` trait. Multiple regions that are active at the same time are separated with ``` || ```. A composite state can be preceded by ``` #[sfsm(history = shallow)] ``` or ``` #[sfsm(history = deep)] ``` to resume its last active sub-states.
+///   A state can be marked with ``` #[sfsm(final)] ``` to end the state machine or the region it belongs to.
 /// - [StateN => StateN, ...]: Defines all transitions between states that can occur. For each transition, the state must implement the according ``` Transition ``` trait.
-/// - ErrorType: Defines the type of error that can be returned from the states.
-/// - ErrorState: Defines the state that will act as the error handle state. It must implement the ``` TryErrorState ``` trait. Adding it to the state definitions is optional.
+///   A transition can be bound to an event with ``` StateN => StateN on Event ```. The state must then implement the according ``` TryEventTransition ``` trait instead.
+///   A transition can be delayed with ``` StateN => StateN after 500ms ```. It is then only taken once the state has been active for the given duration measured by the clock of the state machine.
+///   A transition of a composite state can wait for all its regions to reach a final state with ``` StateN => StateN after completion ```.
+/// - ErrorType: Defines the type of error that can be returned from the states. It can be given with its full path like ``` crate::errors::ErrorType ```.
+/// - ErrorState: Defines the state that will act as the error handle state. It must implement the ``` TryErrorState ``` trait. It must be part of the state definitions.
+///
+/// The generated state machine contains the ``` DOT ```, ``` MERMAID ``` and ``` PLANTUML ``` constants with its states and transitions as a Graphviz graph, a Mermaid and a PlantUML state diagram, and the ``` SCXML ``` constant with the definition as SCXML document.
+/// The active state is returned by ``` state_id() ``` as entry of the generated ``` StateMachineNameStateId ``` enum and by ``` state_name() ``` as name. Both return ``` None ``` while the state machine is stopped. The names of all states are listed in the ``` STATES ``` constant. The data of an active state is accessed with ``` get::<State>() ``` and ``` get_mut::<State>() ```.
+/// With the ``` serde ``` feature, the states enum and the state machine implement ``` Serialize ``` and ``` Deserialize ``` if all states do, and ``` restore(states) ``` creates a state machine in the given states without running their entries.
+/// With the ``` #[sfsm(persist(version = N))] ``` option, ``` encode_into(buffer) ``` and ``` decode_from(buffer) ``` persist the active state in a compact binary format. All states have to implement the ``` Persist ``` trait then.
+/// A stopped state machine is in the ``` Stopped ``` entry of the generated ``` StateMachineNameStates ``` enum until it is started again. ``` reset(state) ``` only runs the exit hooks of the active state and starts it with the given initial state. ``` start_in(state) ``` starts it in any of its states instead of the initial state.
 ///
 /// ```rust
 /// # use sfsm_base::fallible::*;
@@ -176,7 +208,7 @@ pub fn add_state_machine(input: TokenStream) -> TokenStream {
 /// add_fallible_state_machine!(
 ///     Rocket,
 ///     WaitForLaunch,
-///     [WaitForLaunch, Ascent, HandleMalfunction],
+///     [WaitForLaunch, #[sfsm(final)] Ascent, HandleMalfunction],
 ///     [
 ///         WaitForLaunch => Ascent,
 ///         HandleMalfunction => WaitForLaunch
@@ -196,6 +228,100 @@ pub fn add_fallible_state_machine(input: TokenStream) -> TokenStream {
     })
 }
 
+/// Generates an async state machine from a given state machine definition.
+///
+/// The definition is the same as for ``` add_state_machine! ```, but the states implement the
+/// ``` AsyncState ``` and ``` AsyncTransition ``` traits and the state machine implements the
+/// ``` AsyncStateMachine ``` trait. Its start, step, stop and reset functions must be awaited.
+/// The generated code does not allocate, so it runs on any executor or even a simple block_on.
+/// A context and events are not supported by async state machines.
+/// ```rust,ignore
+/// add_async_state_machine!(
+///     StateMachineName,
+///     InitialState,
+///     [State1, State2, StateN, ...],
+///     [StateN => StateN, ...]
+/// );
+///
+/// sfsm.start(State1 {}).await?;
+/// sfsm.step().await?;
+///```
+/// Check out the async_states example for a more complete example.
+/// This macro requires the ``` async ``` feature.
+#[cfg(feature = "async")]
+#[proc_macro]
+pub fn add_async_state_machine(input: TokenStream) -> TokenStream {
+    let definition = syn::parse_macro_input!(input as AsyncMachine);
+    let sfsm_to_tokens = StateMachineToTokens::new(&definition.state_machine);
+
+    TokenStream::from(quote! {
+        #sfsm_to_tokens
+    })
+}
+
+/// Generates an async fallible state machine from a given state machine definition with error
+/// handling.
+///
+/// The definition is the same as for ``` add_fallible_state_machine! ```, but the states implement
+/// the ``` AsyncTryState ``` and ``` AsyncTryTransition ``` traits and the error state the
+/// ``` AsyncTryErrorState ``` trait.
+/// ```rust,ignore
+/// add_async_fallible_state_machine!(
+///     StateMachineName,
+///     InitialState,
+///     [State1, State2, StateN, ...],
+///     [StateN => StateN, ...],
+///     ErrorType,
+///     ErrorState
+/// );
+///```
+/// This macro requires the ``` async ``` feature.
+#[cfg(feature = "async")]
+#[proc_macro]
+pub fn add_async_fallible_state_machine(input: TokenStream) -> TokenStream {
+    let definition = syn::parse_macro_input!(input as AsyncTryMachine);
+    let sfsm_to_tokens = StateMachineToTokens::new(&definition.state_machine);
+
+    TokenStream::from(quote! {
+        #sfsm_to_tokens
+    })
+}
+
+/// Generates a state machine from the states and transitions of a SCXML file.
+///
+/// The file is read at compile time relative to the directory of the crate manifest. The name
+/// of the state machine can be omitted if the document has a name attribute.
+/// ```rust,ignore
+/// add_state_machine_from_scxml!(
+///     #[derive(Debug)]
+///     StateMachineName,
+///     "machines/machine.scxml"
+/// );
+///```
+/// The document is mapped to the same definition as it would be given to ``` add_state_machine! ```:
+/// - ``` <state> ``` and ``` <final> ``` elements become states and nested states become sub-states. A ``` <parallel> ``` element becomes a composite state with a region for each of its child states.
+/// - The ``` initial ``` attribute or ``` <initial> ``` element selects the initial state. Without them the first state is used.
+/// - ``` <history type="shallow"/> ``` and ``` <history type="deep"/> ``` resume the last active sub-states.
+/// - ``` <transition target="StateN"/> ``` becomes a transition. The event attribute binds it to an event type. The ``` done.state.StateN ``` event waits for the completion of the state and ``` after.500ms ``` delays the transition.
+///
+/// Conditions, executable content, data models and invocations are rejected, as the behavior of
+/// the states is implemented in Rust. Every state machine contains its definition as SCXML
+/// document in the ``` SCXML ``` constant, so the models can be round-tripped.
+/// This macro requires the ``` scxml ``` feature.
+#[cfg(feature = "scxml")]
+#[proc_macro]
+pub fn add_state_machine_from_scxml(input: TokenStream) -> TokenStream {
+    let definition = syn::parse_macro_input!(input as ScxmlMachine);
+    let sfsm_to_tokens = StateMachineToTokens::new(&definition.state_machine);
+    let file = definition.file;
+
+    TokenStream::from(quote! {
+        // Rebuilds the state machine when the file changes
+        const _: &str = include_str!(#file);
+        #sfsm_to_tokens
+    })
+}
+
 /// Generates code to push messages into states or poll messages from states.
 ///
 /// The messaging definition is expected too hold to the following pattern:
@@ -210,9 +336,11 @@ pub fn add_fallible_state_machine(input: TokenStream) -> TokenStream {
 ///     ]
 /// );
 /// ```
-/// - StateMachineName: This must match a previously with add_state_machine defined state machine.
-/// - [ Message1 <- State1, ... ] Defines all messages that can be passed back an forth. The message specifies the struct/enum that will be used as a message, the <- arrow defines a poll and the -> a push and the state is the target or source state.
+/// - StateMachineName: This must match a previously with add_state_machine defined state machine. If the state machine is generic, its generic parameters must be repeated together with their bounds like ``` StateMachineName<'a, T: Trait> ```.
+/// - [ Message1 <- State1, ... ] Defines all messages that can be passed back an forth. The message specifies the struct/enum that will be used as a message, the <- arrow defines a poll and the -> a push and the state is the target or source state. Both can be given with their full path like ``` crate::messages::Message1 -> crate::states::State2 ```.
+///
 /// For each message, the source/target state must implement the according ``` ReceiveMessage ``` or ``` ReturnMessage ``` trait.
+/// The messages are also added to the diagrams that are written by ``` write_dot_with_messages ```, ``` write_mermaid_with_messages ``` and ``` write_plantuml_with_messages ```.
 /// An example might look like this.
 /// ```rust
 /// # use sfsm_proc::add_state_machine;
@@ -261,6 +389,7 @@ pub fn add_fallible_state_machine(input: TokenStream) -> TokenStream {
 /// # }
 /// #
 /// # add_state_machine!(
+/// #         #[sfsm(allow(unreachable, dead_end))]
 /// #         Rocket,
 /// #         Action<Ascent>,
 /// #         [Action<Descent>, Action<Ascent>],
@@ -328,6 +457,7 @@ pub fn sfsm_trace(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let trace_function_ident: &proc_macro2::Ident = &trace_function.sig.ident;
     TokenStream::from(quote! {
         #trace_function
+        #[allow(dead_code)]
         fn __sfsm_trace(str: &str) {
             #trace_function_ident(str);
         }
@@ -373,10 +503,8 @@ pub fn derive_transition(input: TokenStream) -> TokenStream {
 #[proc_macro]
 pub fn derive_state(input: TokenStream) -> TokenStream {
     let state: State = syn::parse_macro_input!(input as State);
-    let name = state.name;
-    let generics = state.generics;
     TokenStream::from(quote! {
-        impl sfsm::State for #name #generics {}
+        impl sfsm::State for #state {}
     })
 }
 
@@ -419,10 +547,50 @@ pub fn derive_try_transition(input: TokenStream) -> TokenStream {
 #[proc_macro]
 pub fn derive_try_state(input: TokenStream) -> TokenStream {
     let state: State = syn::parse_macro_input!(input as State);
-    let name = state.name;
-    let generics = state.generics;
     TokenStream::from(quote! {
-        impl sfsm::TryState for #name #generics {}
+        impl sfsm::TryState for #state {}
+    })
+}
+
+/// Derives an empty async transition of a transition from one state into another and allows to
+/// customise if it should always transit or never.
+/// ```rust,ignore
+/// derive_async_transition!(Foo, Bar, TransitGuard::Transit);
+/// // Generates
+/// impl AsyncTransition<Bar> for Foo {
+///     async fn guard(&self) -> TransitGuard {
+///         TransitGuard::Transit
+///     }
+/// }
+/// ```
+#[cfg(feature = "async")]
+#[proc_macro]
+pub fn derive_async_transition(input: TokenStream) -> TokenStream {
+    let transition: DeriveTransition = syn::parse_macro_input!(input as DeriveTransition);
+    let src = transition.transition.src;
+    let dst = transition.transition.dst;
+    let guard = transition.guard;
+    TokenStream::from(quote! {
+        impl sfsm::AsyncTransition<#dst> for #src {
+            async fn guard(&self) -> TransitGuard {
+                #guard
+            }
+        }
+    })
+}
+
+/// Derives an empty implementation of the AsyncState.
+/// ```rust,ignore
+/// derive_async_state!(Foo);
+/// // Generates
+/// impl AsyncState for Foo {};
+/// ```
+#[cfg(feature = "async")]
+#[proc_macro]
+pub fn derive_async_state(input: TokenStream) -> TokenStream {
+    let state: State = syn::parse_macro_input!(input as State);
+    TokenStream::from(quote! {
+        impl sfsm::AsyncState for #state {}
     })
 }
 
@@ -0,0 +1,144 @@
+use crate::types::{Lint, Machine, State};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+/// Analyses the graph of the state machine and all of its regions and generates a warning for
+/// every finding that is not allowed with #[sfsm(allow(...))]
+pub fn lints(machine: &Machine) -> TokenStream {
+    let allowed = &machine.options.allowed;
+    let mut findings = vec![];
+
+    // The error state is entered from any state when an error occurs and might never be left
+    let error_state = machine.error_state.as_ref().map(|state| &state.enum_name);
+    check_region(
+        &machine.init,
+        &machine.states,
+        error_state,
+        true,
+        &mut findings,
+    );
+
+    findings
+        .into_iter()
+        .filter(|(lint, _, _)| !allowed.contains(lint))
+        .map(|(lint, span, message)| warning(lint, span, message))
+        .collect()
+}
+
+fn check_region(
+    init: &State,
+    states: &[State],
+    error_state: Option<&Ident>,
+    top_level: bool,
+    findings: &mut Vec<(Lint, Span, String)>,
+) {
+    let mut reachable = vec![&init.enum_name];
+    let mut index = 0;
+    while index < reachable.len() {
+        if let Some(state) = states
+            .iter()
+            .find(|state| state.enum_name == *reachable[index])
+        {
+            let targets = state
+                .transits
+                .iter()
+                .map(|transit| &transit.dst)
+                .chain(state.event_transits.iter().map(|transit| &transit.dst));
+            for target in targets {
+                if !reachable.contains(&&target.enum_name) {
+                    reachable.push(&target.enum_name);
+                }
+            }
+        }
+        index += 1;
+    }
+
+    for state in states.iter() {
+        let name = state.display_name();
+        // The error state is entered and left by the error handling
+        let is_error_state = Some(&state.enum_name) == error_state;
+        if !reachable.contains(&&state.enum_name) && !is_error_state {
+            findings.push((
+                Lint::Unreachable,
+                state.name.span(),
+                format!(
+                    "The state '{}' cannot be reached from the initial state '{}'",
+                    name,
+                    init.display_name()
+                ),
+            ));
+        }
+
+        // Sub-states are left together with their composite state
+        if top_level
+            && !is_error_state
+            && !state.is_final
+            && state.transits.is_empty()
+            && state.event_transits.is_empty()
+        {
+            findings.push((
+                Lint::DeadEnd,
+                state.name.span(),
+                format!(
+                    "The state '{}' has no outgoing transitions and is not final",
+                    name
+                ),
+            ));
+        }
+
+        for (index, transit) in state.transits.iter().enumerate() {
+            if state.transits[..index]
+                .iter()
+                .any(|known| known.dst.enum_name == transit.dst.enum_name)
+            {
+                findings.push((
+                    Lint::DuplicateTransitions,
+                    transit.dst.name.span(),
+                    format!(
+                        "The transition {} => {} is declared more than once",
+                        name,
+                        transit.dst.display_name()
+                    ),
+                ));
+            }
+        }
+        for (index, transit) in state.event_transits.iter().enumerate() {
+            if state.event_transits[..index].iter().any(|known| {
+                known.dst.enum_name == transit.dst.enum_name
+                    && known.event.enum_name == transit.event.enum_name
+            }) {
+                findings.push((
+                    Lint::DuplicateTransitions,
+                    transit.dst.name.span(),
+                    format!(
+                        "The transition {} => {} on {} is declared more than once",
+                        name,
+                        transit.dst.display_name(),
+                        transit.event.display_name()
+                    ),
+                ));
+            }
+        }
+
+        for region in state.regions.iter() {
+            check_region(&region.init, &region.states, None, false, findings);
+        }
+    }
+}
+
+/// Stable proc macros cannot emit warnings. Instead, a deprecated item is used at the location
+/// of the finding, which lets the compiler report the message as a deprecation warning.
+fn warning(lint: Lint, span: Span, message: String) -> TokenStream {
+    let name = lint.ident();
+    let note = format!("{}. Allow it with #[sfsm(allow({}))]", message, name);
+    // The usage carries the span of the finding so the warning points at it
+    let usage = Ident::new(&name.to_string(), span);
+    quote! {
+        const _: () = {
+            #[deprecated(note = #note)]
+            #[allow(non_camel_case_types)]
+            struct #name;
+            let _ = #usage;
+        };
+    }
+}
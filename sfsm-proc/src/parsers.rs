@@ -1,21 +1,73 @@
+#[cfg(feature = "scxml")]
+use crate::types::ScxmlMachine;
+#[cfg(feature = "async")]
+use crate::types::{AsyncMachine, AsyncTryMachine};
 use crate::types::{
-    DeriveTransition, DeriveTransitionBase, ErrorType, Machine, MatchStateEntry, Message,
-    MessageDir, Messages, Mode, State, StateEntry, StateMessage, TraitDefinitions, Transition,
-    TryMachine,
+    DeriveTransition, DeriveTransitionBase, ErrorType, Event, EventTransit, History, Lint, Machine,
+    MatchStateEntry, Message, MessageDir, Messages, Mode, Options, Region, State, StateEntry,
+    StateMessage, Timeout, TraitDefinitions, Transit, Transition, TryMachine,
 };
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use quote::quote;
+use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream, Parser};
 use syn::punctuated::Punctuated;
 use syn::Token;
-use syn::{AngleBracketedGenericArguments, Attribute, Error, Result, TypePath, Visibility};
+use syn::{
+    AngleBracketedGenericArguments, Attribute, Error, Generics, LitInt, Result, Type, TypePath,
+    Visibility, WhereClause, WherePredicate,
+};
+
+mod kw {
+    syn::custom_keyword!(on);
+    syn::custom_keyword!(after);
+    syn::custom_keyword!(completion);
+}
+
+/// Parses a name that can be qualified with a path like crate::states::Foo. Returns the path in
+/// front of the name including the trailing :: and the name itself.
+fn parse_qualified_name(input: ParseStream) -> Result<(proc_macro2::TokenStream, Ident)> {
+    let mut path = quote! {};
+    if input.peek(Token![::]) {
+        let colons = input.parse::<Token![::]>()?;
+        path.extend(quote! { #colons });
+    }
+    // Path segments like crate, super or self are keywords
+    let mut name = input.call(Ident::parse_any)?;
+    while input.peek(Token![::]) {
+        let colons = input.parse::<Token![::]>()?;
+        path.extend(quote! { #name #colons });
+        name = input.call(Ident::parse_any)?;
+    }
+    Ok((path, name))
+}
 
 /// Parses the name of a state and optionally a type.
-/// For example Foo or Bar<T>
+/// For example Foo, Bar<T> or crate::states::Foo
+/// A composite state is followed by its sub-states in braces like
+/// Foo { Bar, [Bar, Baz], [Bar => Baz] }
+/// Multiple regions are separated with || like
+/// Foo { Bar, [Bar, Baz], [Bar => Baz] || Qux, [Qux], [] }
+/// The state can be preceded by options like #[sfsm(history = deep)] or #[sfsm(final)]
 impl Parse for State {
     fn parse(input: ParseStream) -> Result<Self> {
-        let name: Ident = input.parse()?;
+        let attributes = input.call(Attribute::parse_outer)?;
+        let mut history = None;
+        let mut is_final = false;
+        for attribute in attributes {
+            if !attribute.path.is_ident("sfsm") {
+                return Err(Error::new_spanned(
+                    attribute,
+                    "Only #[sfsm(...)] attributes are supported on states",
+                ));
+            }
+            attribute.parse_args_with(|input: ParseStream| {
+                State::parse_options(input, &mut history, &mut is_final)
+            })?;
+        }
+
+        let (path, name) = parse_qualified_name(input)?;
 
         let generics = if input.peek(Token![<]) {
             input.parse::<AngleBracketedGenericArguments>().ok()
@@ -23,47 +75,201 @@ impl Parse for State {
             None
         };
 
-        let enum_name = State::state_to_enum(&name, &generics);
+        let regions = if input.peek(syn::token::Brace) {
+            let content;
+            syn::braced!(content in input);
+            Region::parse_regions(&content)?
+        } else {
+            vec![]
+        };
+
+        if history.is_some() && regions.is_empty() {
+            return Err(Error::new(
+                name.span(),
+                "A history can only be defined for a composite state",
+            ));
+        }
+
+        if is_final && !regions.is_empty() {
+            return Err(Error::new(
+                name.span(),
+                "A final state cannot be a composite state",
+            ));
+        }
+
+        let enum_name = State::state_to_enum(&path, &name, &generics);
 
         Ok(Self {
+            path,
             name,
             transits: vec![],
             generics,
             enum_name,
+            event_transits: vec![],
+            regions,
+            history,
+            is_final,
         })
     }
 }
 
-/// Parses a transition that must be in the form of
-/// Foo -> Bar or optionally with types like Foo<T> -> Bar<T>
-impl Parse for Transition {
-    fn parse(input: ParseStream) -> Result<Self> {
-        let src: State = input.parse()?;
-        input.parse::<syn::Token![=]>()?;
-        input.parse::<syn::Token![>]>()?;
-        let dst: State = input.parse()?;
-
-        Ok(Self { src, dst })
+impl State {
+    /// Parses a comma separated list of state options like history = deep, final
+    fn parse_options(
+        input: ParseStream,
+        history: &mut Option<History>,
+        is_final: &mut bool,
+    ) -> Result<()> {
+        while !input.is_empty() {
+            // Final is a keyword and therefore not accepted by the normal ident parser
+            let option: Ident = input.call(Ident::parse_any)?;
+            if option == "final" {
+                *is_final = true;
+            } else if option == "history" {
+                input.parse::<Token![=]>()?;
+                let kind: Ident = input.parse()?;
+                *history = if kind == "shallow" {
+                    Some(History::Shallow)
+                } else if kind == "deep" {
+                    Some(History::Deep)
+                } else {
+                    return Err(Error::new(
+                        kind.span(),
+                        format!("Unknown history '{}'. Expected shallow or deep", kind),
+                    ));
+                };
+            } else {
+                return Err(Error::new(
+                    option.span(),
+                    format!("Unknown state option '{}'", option),
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(())
     }
 }
 
-impl Machine {
-    pub fn enum_name(sfsm_name: &Ident) -> Ident {
-        Ident::new(format!("{}States", sfsm_name).as_str(), Span::call_site())
+impl Region {
+    /// Parses the regions of a composite state that are separated by ||
+    fn parse_regions(input: ParseStream) -> Result<Vec<Self>> {
+        let mut regions = vec![input.parse::<Region>()?];
+        if input.peek(syn::Token![,]) {
+            input.parse::<syn::Token![,]>()?;
+        }
+        while input.peek(syn::Token![||]) {
+            input.parse::<syn::Token![||]>()?;
+            regions.push(input.parse::<Region>()?);
+            if input.peek(syn::Token![,]) {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        if !input.is_empty() {
+            return Err(input.error("Expected || followed by another region"));
+        }
+        Ok(regions)
     }
-}
 
-/// Parses the state machine in the form of
-/// name, Foo, [Foo, Bar], [Foo -> Bar]
-impl Parse for Machine {
-    fn parse(input: ParseStream) -> Result<Self> {
-        let attributes = input.call(Attribute::parse_outer)?;
+    /// Finds a state that is referred to by the definition in the list of declared states.
+    /// The kind describes the role of the state in the error message, like initial or target.
+    /// As the paths cannot be resolved, a state that is given with a shorter or longer path than
+    /// it is declared with is found as well, as long as the paths cannot be confused.
+    fn find_declared<'a>(states: &'a [State], state: &State, kind: &str) -> Result<&'a State> {
+        if let Some(declared) = states
+            .iter()
+            .find(|declared| declared.enum_name == state.enum_name)
+        {
+            return Ok(declared);
+        }
+        let mut candidates = states.iter().filter(|declared| declared.same_type(state));
+        match (candidates.next(), candidates.next()) {
+            (Some(declared), None) => Ok(declared),
+            (Some(_), Some(_)) => Err(Error::new(
+                state.name.span(),
+                format!(
+                    "The {} state '{}' is ambiguous. Give it with the path it is declared with",
+                    kind,
+                    state.display_name()
+                ),
+            )),
+            (None, _) => Err(Error::new(
+                state.name.span(),
+                format!(
+                    "The {} state '{}' is not in the list of states",
+                    kind,
+                    state.display_name()
+                ),
+            )),
+        }
+    }
 
-        let visibility: Option<Visibility> = input.parse().ok();
+    /// Replaces the path of a state that is referred to by the definition with the path it is
+    /// declared with, so the generated code always uses the same names for a state
+    fn canonicalize(states: &[State], state: &mut State, kind: &str) -> Result<()> {
+        let declared = Region::find_declared(states, state, kind)?;
+        state.path = declared.path.clone();
+        state.enum_name = declared.enum_name.clone();
+        Ok(())
+    }
 
-        let name: Ident = input.parse()?;
-        input.parse::<syn::Token![,]>()?;
+    /// Collects all events of the states and their sub-states without duplicates
+    pub fn events(&self) -> Result<Vec<Event>> {
+        let mut events: Vec<Event> = vec![];
+        Region::collect_events(&self.states, &mut events)?;
+        Ok(events)
+    }
+
+    /// Finds the first timeout of the states and their sub-states
+    fn find_timeout(states: &[State]) -> Option<&Timeout> {
+        states
+            .iter()
+            .flat_map(|state| state.transits.iter())
+            .find_map(|transit| transit.timeout.as_ref())
+            .or_else(|| {
+                states
+                    .iter()
+                    .flat_map(|state| state.regions.iter())
+                    .find_map(|region| Region::find_timeout(&region.states))
+            })
+    }
 
+    fn collect_events(states: &[State], events: &mut Vec<Event>) -> Result<()> {
+        for state in states.iter() {
+            for transit in state.event_transits.iter() {
+                let event = &transit.event;
+                match events
+                    .iter()
+                    .find(|known| known.enum_name == event.enum_name)
+                {
+                    Some(known) if known.display_name() != event.display_name() => {
+                        return Err(Error::new(
+                            event.name.span(),
+                            format!(
+                                "The events '{}' and '{}' get the same generated name '{}'. Rename one of them",
+                                known.display_name(),
+                                event.display_name(),
+                                event.enum_name
+                            ),
+                        ));
+                    }
+                    Some(_) => {}
+                    None => events.push(event.clone()),
+                }
+            }
+        }
+        for region in states.iter().flat_map(|state| state.regions.iter()) {
+            Region::collect_events(&region.states, events)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the states of a state machine or a region of a composite state in the form of
+/// Foo, [Foo, Bar], [Foo => Bar]
+impl Parse for Region {
+    fn parse(input: ParseStream) -> Result<Self> {
         let init_definition: State = input.parse()?;
         input.parse::<syn::Token![,]>()?;
 
@@ -79,71 +285,481 @@ impl Parse for Machine {
         let transition_group_ts: TokenStream = transition_group.stream().into();
         let transition_parser = Punctuated::<Transition, Token![,]>::parse_terminated;
         let punctuated_transitions = transition_parser.parse(transition_group_ts)?;
-        let transitions: Vec<Transition> = punctuated_transitions.into_iter().collect();
+        let mut transitions: Vec<Transition> = punctuated_transitions.into_iter().collect();
+
+        Region::find_declared(&states_names, &init_definition, "initial")?;
+
+        for transition in transitions.iter_mut() {
+            Region::canonicalize(&states_names, &mut transition.src, "source")?;
+            Region::canonicalize(&states_names, &mut transition.dst, "target")?;
+            let src = Region::find_declared(&states_names, &transition.src, "source")?;
+            if src.is_final {
+                return Err(Error::new(
+                    transition.src.name.span(),
+                    "A final state cannot be left",
+                ));
+            }
+            if transition.completion && src.regions.is_empty() {
+                return Err(Error::new(
+                    transition.src.name.span(),
+                    "Only a composite state can transit after its completion",
+                ));
+            }
+        }
 
         let states: Vec<State> = states_names
             .into_iter()
             .map(|state| {
-                let transitions: Vec<State> = transitions
+                let transits: Vec<Transit> = transitions
                     .iter()
                     .filter(|trans| trans.src.enum_name == state.enum_name)
-                    .map(|trans| trans.dst.clone())
+                    .filter(|trans| trans.event.is_none())
+                    .map(|trans| Transit {
+                        dst: trans.dst.clone(),
+                        timeout: trans.timeout.clone(),
+                        completion: trans.completion,
+                    })
+                    .collect();
+
+                let event_transits: Vec<EventTransit> = transitions
+                    .iter()
+                    .filter(|trans| trans.src.enum_name == state.enum_name)
+                    .filter_map(|trans| {
+                        trans.event.as_ref().map(|event| EventTransit {
+                            event: event.clone(),
+                            dst: trans.dst.clone(),
+                        })
+                    })
                     .collect();
 
                 State {
+                    path: state.path,
                     name: state.name,
-                    transits: transitions,
+                    transits,
                     generics: state.generics,
                     enum_name: state.enum_name,
+                    event_transits,
+                    regions: state.regions,
+                    history: state.history,
+                    is_final: state.is_final,
                 }
             })
             .collect();
 
-        let init = states
+        let init = Region::find_declared(&states, &init_definition, "initial")?.clone();
+
+        Ok(Self { init, states })
+    }
+}
+
+/// Parses the name of an event and optionally a type.
+/// For example Ignite or Command<Launch>
+impl Parse for Event {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let (path, name) = parse_qualified_name(input)?;
+
+        let generics = if input.peek(Token![<]) {
+            input.parse::<AngleBracketedGenericArguments>().ok()
+        } else {
+            None
+        };
+
+        let enum_name = Event::event_to_enum(&path, &name, &generics);
+
+        Ok(Self {
+            path,
+            name,
+            generics,
+            enum_name,
+        })
+    }
+}
+
+/// Parses a transition that must be in the form of
+/// Foo => Bar or optionally with types like Foo<T> => Bar<T>.
+/// The transition can be bound to an event with Foo => Bar on Event.
+/// Or it can be delayed with a timeout like Foo => Bar after 500ms.
+/// A composite state can transit once all of its regions are finished with
+/// Foo => Bar after completion.
+impl Parse for Transition {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let src: State = input.parse()?;
+        input.parse::<syn::Token![=]>()?;
+        input.parse::<syn::Token![>]>()?;
+        let dst: State = input.parse()?;
+
+        let event = if input.peek(kw::on) {
+            input.parse::<kw::on>()?;
+            Some(input.parse::<Event>()?)
+        } else {
+            None
+        };
+
+        let mut timeout = None;
+        let mut completion = false;
+        if input.peek(kw::after) {
+            let after = input.parse::<kw::after>()?;
+            if event.is_some() {
+                return Err(Error::new(
+                    after.span,
+                    "A transition cannot be bound to an event and be delayed at the same time",
+                ));
+            }
+            if input.peek(kw::completion) {
+                input.parse::<kw::completion>()?;
+                completion = true;
+            } else {
+                timeout = Some(input.parse::<Timeout>()?);
+            }
+        }
+
+        Ok(Self {
+            src,
+            dst,
+            event,
+            timeout,
+            completion,
+        })
+    }
+}
+
+/// Parses the duration of a timeout in the form of 500ms, 2s or 100us
+impl Parse for Timeout {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let duration: LitInt = input.parse()?;
+        let per_second = match duration.suffix() {
+            "s" => 1,
+            "ms" => 1_000,
+            "us" => 1_000_000,
+            _ => {
+                return Err(Error::new(
+                    duration.span(),
+                    "Expected a duration with a unit of s, ms or us like 500ms",
+                ))
+            }
+        };
+        // Only keep the digits so the value can be used as an integer of any type
+        duration.base10_parse::<u64>()?;
+        let value = LitInt::new(duration.base10_digits(), duration.span());
+
+        Ok(Self { value, per_second })
+    }
+}
+
+impl Lint {
+    /// Finds the lint with the given name like unreachable or dead_end
+    fn parse_name(name: &Ident) -> Result<Self> {
+        [Lint::Unreachable, Lint::DuplicateTransitions, Lint::DeadEnd]
             .iter()
-            .find(|state| init_definition.enum_name == state.enum_name)
-            .expect("Expected to find the init state in the list of states")
-            .clone();
+            .find(|lint| lint.ident() == *name)
+            .copied()
+            .ok_or_else(|| Error::new(name.span(), format!("Unknown sfsm lint '{}'", name)))
+    }
+}
+
+impl Options {
+    /// Extracts all #[sfsm(...)] attributes from the attributes of the state machine and
+    /// parses the options from them. All other attributes are returned to be forwarded to the
+    /// generated code.
+    pub fn parse_attributes(attributes: Vec<Attribute>) -> Result<(Self, Vec<Attribute>)> {
+        let mut options = Options::default();
+        let mut forwarded = vec![];
+        for attribute in attributes {
+            if !attribute.path.is_ident("sfsm") {
+                forwarded.push(attribute);
+                continue;
+            }
+            attribute.parse_args_with(|input: ParseStream| options.parse_options(input))?;
+        }
+        Ok((options, forwarded))
+    }
+
+    /// Parses a comma separated list of options like exclusive_actions, context = Foo,
+    /// clock = Bar, persist(version = 1)
+    fn parse_options(&mut self, input: ParseStream) -> Result<()> {
+        while !input.is_empty() {
+            let option: Ident = input.parse()?;
+            if option == "exclusive_actions" {
+                self.exclusive_actions = true;
+            } else if option == "context" {
+                input.parse::<Token![=]>()?;
+                self.context = Some(input.parse::<Type>()?);
+            } else if option == "clock" {
+                input.parse::<Token![=]>()?;
+                self.clock = Some(input.parse::<Type>()?);
+            } else if option == "persist" {
+                let content;
+                syn::parenthesized!(content in input);
+                let key: Ident = content.parse()?;
+                if key != "version" {
+                    return Err(Error::new(
+                        key.span(),
+                        format!("Unknown persist option '{}'. Expected 'version'", key),
+                    ));
+                }
+                content.parse::<Token![=]>()?;
+                let version: LitInt = content.parse()?;
+                self.persist = Some(version.base10_parse::<u8>()?);
+            } else if option == "allow" {
+                let content;
+                syn::parenthesized!(content in input);
+                let lints = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+                for lint in lints {
+                    self.allowed.push(Lint::parse_name(&lint)?);
+                }
+            } else {
+                return Err(Error::new(
+                    option.span(),
+                    format!("Unknown sfsm option '{}'", option),
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TraitDefinitions {
+    /// The traits that have to be implemented by the states of a non fallible state machine
+    pub fn non_fallible(context: &Option<Type>) -> Self {
+        let (state_trait, transit_trait, event_trait) = if let Some(context) = context {
+            (
+                quote! {sfsm::ContextState::<#context>},
+                quote! {sfsm::ContextTransition},
+                quote! {sfsm::ContextEventTransition},
+            )
+        } else {
+            (
+                quote! {sfsm::State},
+                quote! {sfsm::Transition},
+                quote! {sfsm::EventTransition},
+            )
+        };
+        Self {
+            state_trait,
+            transit_trait,
+            entry: quote! {entry},
+            exit: quote! {exit},
+            action: quote! {action},
+            execute: quote! {execute},
+            event_trait,
+            error_trait: quote! {},
+            stop: quote! {on_stop},
+        }
+    }
+
+    /// The traits that have to be implemented by the states of a fallible state machine
+    pub fn fallible(context: &Option<Type>) -> Self {
+        let (state_trait, transit_trait, event_trait, error_trait) = if let Some(context) = context
+        {
+            (
+                quote! {sfsm::TryContextState::<#context>},
+                quote! {sfsm::TryContextTransition},
+                quote! {sfsm::TryContextEventTransition},
+                quote! {sfsm::TryContextErrorState::<#context>},
+            )
+        } else {
+            (
+                quote! {sfsm::TryState},
+                quote! {sfsm::TryTransition},
+                quote! {sfsm::TryEventTransition},
+                quote! {sfsm::TryErrorState},
+            )
+        };
+        Self {
+            state_trait,
+            transit_trait,
+            entry: quote! {try_entry},
+            exit: quote! {try_exit},
+            action: quote! {try_action},
+            execute: quote! {try_execute},
+            event_trait,
+            error_trait,
+            stop: quote! {try_on_stop},
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl TraitDefinitions {
+    /// The traits that have to be implemented by the states of an async state machine
+    pub fn asynchronous(mode: &Mode) -> Self {
+        let (state_trait, transit_trait, error_trait) = match mode {
+            Mode::NonFallible => (
+                quote! {sfsm::AsyncState},
+                quote! {sfsm::AsyncTransition},
+                quote! {},
+            ),
+            Mode::Fallible => (
+                quote! {sfsm::AsyncTryState},
+                quote! {sfsm::AsyncTryTransition},
+                quote! {sfsm::AsyncTryErrorState},
+            ),
+        };
+        let definitions = match mode {
+            Mode::NonFallible => TraitDefinitions::non_fallible(&None),
+            Mode::Fallible => TraitDefinitions::fallible(&None),
+        };
+        Self {
+            state_trait,
+            transit_trait,
+            error_trait,
+            ..definitions
+        }
+    }
+}
+
+impl Machine {
+    /// Turns the state machine into an async state machine. The context and events are not
+    /// supported by async state machines.
+    #[cfg(feature = "async")]
+    fn make_async(&mut self) -> Result<()> {
+        if let Some(context) = &self.options.context {
+            return Err(Error::new_spanned(
+                context,
+                "A context is not supported by async state machines",
+            ));
+        }
+        if let Some(event) = self.events.first() {
+            return Err(Error::new(
+                event.name.span(),
+                "Events are not supported by async state machines",
+            ));
+        }
+        self.asynchronous = true;
+        self.trait_definitions = TraitDefinitions::asynchronous(&self.mode);
+        Ok(())
+    }
+
+    /// Parses the generic parameters and the where clause following the name of the state
+    /// machine like ``` Rocket<'a, T: Sensor> where T: Default ```
+    pub fn parse_generics(input: ParseStream) -> Result<Generics> {
+        let mut generics: Generics = input.parse()?;
+        if input.peek(Token![where]) {
+            let where_token = input.parse()?;
+            let mut predicates = Punctuated::new();
+            loop {
+                predicates.push_value(input.parse::<WherePredicate>()?);
+                // The where clause ends with the last comma that is followed by a predicate.
+                // The comma after it belongs to the state machine definition.
+                let fork = input.fork();
+                if fork.parse::<Token![,]>().is_err() || fork.parse::<WherePredicate>().is_err() {
+                    break;
+                }
+                predicates.push_punct(input.parse()?);
+            }
+            generics.where_clause = Some(WhereClause {
+                where_token,
+                predicates,
+            });
+        }
+        Ok(generics)
+    }
+
+    pub fn enum_name(sfsm_name: &Ident) -> Ident {
+        Ident::new(format!("{}States", sfsm_name).as_str(), Span::call_site())
+    }
+
+    pub fn events_enum_name(sfsm_name: &Ident) -> Ident {
+        Ident::new(format!("{}Events", sfsm_name).as_str(), Span::call_site())
+    }
+
+    pub fn state_id_name(sfsm_name: &Ident) -> Ident {
+        Ident::new(format!("{}StateId", sfsm_name).as_str(), Span::call_site())
+    }
+}
+
+/// Parses the state machine in the form of
+/// name, Foo, [Foo, Bar], [Foo -> Bar]
+impl Parse for Machine {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attributes = input.call(Attribute::parse_outer)?;
+        let (options, attributes) = Options::parse_attributes(attributes)?;
+
+        let visibility: Option<Visibility> = input.parse().ok();
+
+        let name: Ident = input.parse()?;
+        let generics = Machine::parse_generics(input)?;
+        input.parse::<syn::Token![,]>()?;
+
+        let region: Region = input.parse()?;
+        let events = region.events()?;
+        let Region { init, states } = region;
+
+        // The generated state ids cannot contain the same state twice, not even on different
+        // levels of the hierarchy
+        let mut all_states = vec![];
+        State::collect_states(&states, &mut all_states);
+        for (index, state) in all_states.iter().enumerate() {
+            if let Some(known) = all_states[..index]
+                .iter()
+                .find(|known| known.enum_name == state.enum_name)
+            {
+                // Differently written paths can still end up with the same generated name, like
+                // foo_bar and fooBar
+                if known.display_name() != state.display_name() {
+                    return Err(Error::new(
+                        state.name.span(),
+                        format!(
+                            "The states '{}' and '{}' get the same generated name '{}'. Rename one of them",
+                            known.display_name(),
+                            state.display_name(),
+                            state.id_name()
+                        ),
+                    ));
+                }
+                return Err(Error::new(
+                    state.name.span(),
+                    format!(
+                        "The state '{}' is declared more than once",
+                        state.get_name_type()
+                    ),
+                ));
+            }
+        }
+
+        if options.clock.is_none() {
+            if let Some(timeout) = Region::find_timeout(&states) {
+                return Err(Error::new(
+                    timeout.value.span(),
+                    "Timeout transitions require a clock. Add it with #[sfsm(clock = Type)]",
+                ));
+            }
+        }
 
         let enum_name = Machine::enum_name(&name);
+        let events_enum_name = Machine::events_enum_name(&name);
+        let state_id_name = Machine::state_id_name(&name);
 
         let sfsm_error = quote! {
             sfsm::SfsmError
         };
 
-        let trait_definitions = TraitDefinitions {
-            state_trait: quote! {
-                sfsm::State
-            },
-            transit_trait: quote! {
-                sfsm::Transition
-            },
-            entry: quote! {
-                entry
-            },
-            exit: quote! {
-                exit
-            },
-            action: quote! {
-                action
-            },
-            execute: quote! {
-                execute
-            },
-        };
+        let trait_definitions = TraitDefinitions::non_fallible(&options.context);
 
         Ok(Self {
             attributes,
+            options,
             visibility,
             name,
+            enum_generics: generics.clone(),
+            generics,
             init,
             states,
             enum_name,
+            events,
+            events_enum_name,
+            state_id_name,
             sfsm_error,
             trait_definitions,
             mode: Mode::NonFallible,
+            asynchronous: false,
             error_state: None,
             custom_error: None,
+            parent: None,
+            history: None,
         })
     }
 }
@@ -174,7 +790,7 @@ impl Parse for MatchStateEntry {
 }
 impl Parse for Message {
     fn parse(input: ParseStream) -> Result<Self> {
-        let name: Ident = input.parse()?;
+        let (path, name) = parse_qualified_name(input)?;
 
         // Only parse the generic argument if the bracket is opened and no - follows.
         // If we only checked for the < the arrow <- would trigger the parsing.
@@ -184,7 +800,11 @@ impl Parse for Message {
             None
         };
 
-        Ok(Self { name, generics })
+        Ok(Self {
+            generics,
+            path,
+            name,
+        })
     }
 }
 
@@ -221,6 +841,7 @@ impl Parse for StateMessage {
 impl Parse for Messages {
     fn parse(input: ParseStream) -> Result<Self> {
         let name: Ident = input.parse()?;
+        let generics = Machine::parse_generics(input)?;
         input.parse::<syn::Token![,]>()?;
 
         let state_message_group = input.parse::<proc_macro2::Group>()?;
@@ -233,6 +854,7 @@ impl Parse for Messages {
 
         Ok(Self {
             name,
+            generics,
             enum_name,
             messages,
         })
@@ -241,7 +863,7 @@ impl Parse for Messages {
 
 impl Parse for ErrorType {
     fn parse(input: ParseStream) -> Result<Self> {
-        let error_name: Ident = input.parse()?;
+        let (path, error_name) = parse_qualified_name(input)?;
 
         let generics = if input.peek(Token![<]) {
             input.parse::<AngleBracketedGenericArguments>().ok()
@@ -250,6 +872,7 @@ impl Parse for ErrorType {
         };
 
         Ok(Self {
+            path,
             error_name,
             generics,
         })
@@ -260,46 +883,95 @@ impl Parse for ErrorType {
 /// name, Foo, [Foo, Bar], [Foo -> Bar], ErrorType, ErrorState
 impl Parse for TryMachine {
     fn parse(input: ParseStream) -> Result<Self> {
-        let mut state_machine: Machine =
-            input.parse().expect("Expected a state machine definition");
+        let mut state_machine: Machine = input.parse()?;
         input.parse::<syn::Token![,]>()?;
-        let error_type: ErrorType = input.parse().expect("Expected an error type");
+        let error_type: ErrorType = input.parse()?;
         input.parse::<syn::Token![,]>()?;
-        let error_state_entry: State = input.parse().expect("Expected an error state");
+        let error_state_entry: State = input.parse()?;
 
+        let error_type_path = error_type.path;
         let error_type_name = error_type.error_name;
         let error_type_generics = error_type.generics;
         let custom_error = quote! {
-            <#error_type_name #error_type_generics>
+            <#error_type_path #error_type_name #error_type_generics>
         };
         let sfsm_error = quote! {
             sfsm::ExtendedSfsmError
         };
 
-        let states = &(state_machine.states);
-        let error_state = states
-            .iter()
-            .find(|state| error_state_entry.enum_name == state.enum_name)
-            .expect("Expected to find the error state in the list of states")
-            .clone();
+        let error_state =
+            Region::find_declared(&state_machine.states, &error_state_entry, "error")?.clone();
+
+        if !error_state.regions.is_empty() {
+            return Err(Error::new(
+                error_state_entry.name.span(),
+                "The error state cannot be a composite state",
+            ));
+        }
 
         state_machine.mode = Mode::Fallible;
         state_machine.error_state = Some(error_state);
         state_machine.sfsm_error = sfsm_error;
         state_machine.custom_error = Some(custom_error);
-        state_machine.trait_definitions = TraitDefinitions {
-            state_trait: quote! {sfsm::TryState},
-            transit_trait: quote! {sfsm::TryTransition},
-            entry: quote! {try_entry},
-            exit: quote! {try_exit},
-            action: quote! {try_action},
-            execute: quote! {try_execute},
-        };
+        state_machine.trait_definitions =
+            TraitDefinitions::fallible(&state_machine.options.context);
 
         Ok(Self { state_machine })
     }
 }
 
+/// Parses an async state machine in the same form as a normal state machine
+#[cfg(feature = "async")]
+impl Parse for AsyncMachine {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut state_machine: Machine = input.parse()?;
+        state_machine.make_async()?;
+        Ok(Self { state_machine })
+    }
+}
+
+/// Parses an async state machine in the same form as a fallible state machine
+#[cfg(feature = "async")]
+impl Parse for AsyncTryMachine {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let try_machine: TryMachine = input.parse()?;
+        let mut state_machine = try_machine.state_machine;
+        state_machine.make_async()?;
+        Ok(Self { state_machine })
+    }
+}
+
+/// Parses the state machine in the form of
+/// name, "machine.scxml" where the name is optional if the document has a name attribute
+#[cfg(feature = "scxml")]
+impl Parse for ScxmlMachine {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attributes = input.call(Attribute::parse_outer)?;
+        let visibility: Option<Visibility> = input.parse().ok();
+        let name: Option<Ident> = if input.peek(syn::Ident) {
+            let name = input.parse()?;
+            input.parse::<syn::Token![,]>()?;
+            Some(name)
+        } else {
+            None
+        };
+        let file: syn::LitStr = input.parse()?;
+
+        let (path, definition) = crate::scxml::read_definition(&file, name)?;
+        // The definition has no spans, so its errors are reported at the path of the file
+        let state_machine = syn::parse2::<Machine>(quote! {
+            #( #attributes )*
+            #visibility #definition
+        })
+        .map_err(|err| Error::new(file.span(), format!("{}: {}", file.value(), err)))?;
+
+        Ok(Self {
+            state_machine,
+            file: path,
+        })
+    }
+}
+
 impl Parse for DeriveTransitionBase {
     fn parse(input: ParseStream) -> Result<Self> {
         let src: State = input.parse()?;
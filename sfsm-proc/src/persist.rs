@@ -0,0 +1,77 @@
+use crate::types::{Machine, State};
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+/// Generates the methods that encode the active state into a buffer and decode it again if the
+/// state machine has the persist option. The encoding is done by the functions of the persist
+/// module of sfsm-base, so the generated code only maps the states to their ids.
+pub fn methods(machine: &Machine) -> TokenStream {
+    let version = match machine.options.persist {
+        Some(version) => version,
+        None => return quote! {},
+    };
+    let enum_name = &machine.enum_name;
+    let state_id = &machine.state_id_name;
+    let states = &machine.states;
+    let entries: Vec<&Ident> = states.iter().map(|state| &state.enum_name).collect();
+    let ids: Vec<Ident> = states.iter().map(State::id_name).collect();
+
+    // The decoded state machine owns the context and the clock like a new one
+    let (context_param, context_arg) = match &machine.options.context {
+        Some(context) => (quote! { context: #context, }, quote! { context, }),
+        None => (quote! {}, quote! {}),
+    };
+    let (clock_param, clock_arg) = match &machine.options.clock {
+        Some(clock) => (quote! { clock: #clock }, quote! { clock }),
+        None => (quote! {}, quote! {}),
+    };
+
+    quote! {
+        /// The format version the state machine is encoded with
+        pub const PERSIST_VERSION: u8 = #version;
+
+        /// Encodes the active state into the buffer and returns the number of bytes written.
+        /// The encoding consists of the format version, the id of the active state, the payload
+        /// of the state and a CRC. The sub-states of composite states are not encoded. The id is
+        /// the position of the state in the definition, so the version has to be increased if
+        /// the states are reordered.
+        pub fn encode_into(&self, buffer: &mut [u8]) -> ::core::result::Result<usize, sfsm::PersistError>
+        where
+            #( #states: sfsm::Persist, )*
+        {
+            match self.states {
+                #(
+                    #enum_name::#entries(Some(ref state)) => sfsm::encode_frame(
+                        buffer,
+                        Self::PERSIST_VERSION,
+                        #state_id::#ids as u8,
+                        |payload| sfsm::Persist::encode(state, payload),
+                    ),
+                )*
+                _ => Err(sfsm::PersistError::NotStarted),
+            }
+        }
+
+        /// Decodes a state machine that was encoded with encode_into and restores it without
+        /// entering the state again. Its timeouts start at the current time of the clock. Encodings of newer format versions are rejected, while the
+        /// payloads of older versions are passed to the state to migrate them.
+        pub fn decode_from(buffer: &[u8], #context_param #clock_param) -> ::core::result::Result<Self, sfsm::PersistError>
+        where
+            #( #states: sfsm::Persist, )*
+        {
+            let frame = sfsm::decode_frame(buffer)?;
+            if frame.version > Self::PERSIST_VERSION {
+                return Err(sfsm::PersistError::UnsupportedVersion(frame.version));
+            }
+            let states = match frame.state_id {
+                #(
+                    id if id == #state_id::#ids as u8 => #enum_name::#entries(Some(
+                        <#states as sfsm::Persist>::decode(frame.payload, frame.version)?,
+                    )),
+                )*
+                id => return Err(sfsm::PersistError::UnknownState(id)),
+            };
+            Ok(Self::restore(states, #context_arg #clock_arg))
+        }
+    }
+}
@@ -0,0 +1,457 @@
+use crate::types::{History, Machine, State};
+use std::fmt::Write;
+
+/// Escapes the characters that are not allowed in the attribute values of XML
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the states and transitions of the state machine as a SCXML document. Composite
+/// states with multiple regions are rendered as parallel states. Completion transitions are
+/// bound to the done.state event of their state and timeouts to an after event like after.500ms.
+pub fn scxml(machine: &Machine) -> String {
+    let mut scxml = String::new();
+    writeln!(scxml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+    writeln!(
+        scxml,
+        "<scxml xmlns=\"http://www.w3.org/2005/07/scxml\" version=\"1.0\" name=\"{}\" initial=\"{}\">",
+        machine.name,
+        machine.init.id_name()
+    )
+    .unwrap();
+    for state in machine.states.iter() {
+        write_state(&mut scxml, state, 1);
+    }
+    writeln!(scxml, "</scxml>").unwrap();
+    scxml
+}
+
+/// Writes the type of the state as a comment above its element when it cannot be read from the
+/// id, because ids cannot contain paths or generics
+fn write_type_comment(scxml: &mut String, state: &State, indent: &str) {
+    let display_name = state.display_name();
+    if state.id_name() != display_name {
+        writeln!(scxml, "{}<!-- {} -->", indent, display_name).unwrap();
+    }
+}
+
+fn write_state(scxml: &mut String, state: &State, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let id = state.id_name();
+    write_type_comment(scxml, state, &indent);
+
+    if state.is_final {
+        writeln!(scxml, "{}<final id=\"{}\"/>", indent, id).unwrap();
+        return;
+    }
+
+    let element = match state.regions.len() {
+        0 | 1 => "state",
+        _ => "parallel",
+    };
+    let initial = match state.regions.as_slice() {
+        [region] => format!(" initial=\"{}\"", region.init.id_name()),
+        _ => String::new(),
+    };
+    let has_children =
+        !state.regions.is_empty() || !state.transits.is_empty() || !state.event_transits.is_empty();
+    if !has_children {
+        writeln!(scxml, "{}<{} id=\"{}\"/>", indent, element, id).unwrap();
+        return;
+    }
+    writeln!(scxml, "{}<{} id=\"{}\"{}>", indent, element, id, initial).unwrap();
+
+    match state.history {
+        Some(History::Shallow) => writeln!(scxml, "{}    <history type=\"shallow\"/>", indent),
+        Some(History::Deep) => writeln!(scxml, "{}    <history type=\"deep\"/>", indent),
+        None => Ok(()),
+    }
+    .unwrap();
+
+    if let [region] = state.regions.as_slice() {
+        for sub_state in region.states.iter() {
+            write_state(scxml, sub_state, depth + 1);
+        }
+    } else {
+        // Every region becomes a child state of the parallel state
+        for (index, region) in state.regions.iter().enumerate() {
+            writeln!(
+                scxml,
+                "{}    <state id=\"{}.{}\" initial=\"{}\">",
+                indent,
+                id,
+                index,
+                region.init.id_name()
+            )
+            .unwrap();
+            for sub_state in region.states.iter() {
+                write_state(scxml, sub_state, depth + 2);
+            }
+            writeln!(scxml, "{}    </state>", indent).unwrap();
+        }
+    }
+
+    for transit in state.transits.iter() {
+        let event = if transit.completion {
+            format!(" event=\"done.state.{}\"", id)
+        } else if let Some(timeout) = &transit.timeout {
+            format!(" event=\"after.{}\"", timeout.display_name())
+        } else {
+            String::new()
+        };
+        writeln!(
+            scxml,
+            "{}    <transition{} target=\"{}\"/>",
+            indent,
+            event,
+            transit.dst.id_name()
+        )
+        .unwrap();
+    }
+    for transit in state.event_transits.iter() {
+        writeln!(
+            scxml,
+            "{}    <transition event=\"{}\" target=\"{}\"/>",
+            indent,
+            escape(&transit.event.display_name()),
+            transit.dst.id_name()
+        )
+        .unwrap();
+    }
+
+    writeln!(scxml, "{}</{}>", indent, element).unwrap();
+}
+
+#[cfg(feature = "scxml")]
+pub use import::read_definition;
+
+/// Reads a SCXML document and turns it into the definition of a state machine as it would be
+/// written in add_state_machine!
+#[cfg(feature = "scxml")]
+mod import {
+    use proc_macro2::{Ident, TokenStream};
+    use roxmltree::{Document, Node};
+    use std::path::Path;
+    use syn::{Error, LitStr, Result};
+
+    /// Reads the SCXML file at the path relative to the manifest of the crate and returns its
+    /// absolute path together with the definition of the state machine
+    pub fn read_definition(file: &LitStr, name: Option<Ident>) -> Result<(String, TokenStream)> {
+        let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+        let path = Path::new(&manifest_dir).join(file.value());
+        let text = std::fs::read_to_string(&path).map_err(|err| {
+            Error::new(
+                file.span(),
+                format!("Cannot read '{}': {}", path.display(), err),
+            )
+        })?;
+        let document = Document::parse(&text).map_err(|err| {
+            Error::new(
+                file.span(),
+                format!("Cannot parse '{}': {}", file.value(), err),
+            )
+        })?;
+
+        let importer = Importer {
+            document: &document,
+            file,
+        };
+        let definition = importer.machine(document.root_element(), name)?;
+        let definition = syn::parse_str::<TokenStream>(&definition)
+            .map_err(|err| Error::new(file.span(), err.to_string()))?;
+
+        Ok((path.to_string_lossy().into_owned(), definition))
+    }
+
+    struct Importer<'a, 'input> {
+        document: &'a Document<'input>,
+        file: &'a LitStr,
+    }
+
+    impl Importer<'_, '_> {
+        /// Creates an error that points to the line of the node in the SCXML file
+        fn error(&self, node: Node, message: &str) -> Error {
+            let position = self.document.text_pos_at(node.range().start);
+            Error::new(
+                self.file.span(),
+                format!(
+                    "{}:{}:{}: {}",
+                    self.file.value(),
+                    position.row,
+                    position.col,
+                    message
+                ),
+            )
+        }
+
+        fn unsupported(&self, node: Node) -> Error {
+            let message = match node.tag_name().name() {
+                "onentry" | "onexit" | "script" | "invoke" | "datamodel" | "donedata" => format!(
+                    "The element <{}> is not supported. The behavior of the states is implemented in Rust",
+                    node.tag_name().name()
+                ),
+                name => format!("The element <{}> is not supported here", name),
+            };
+            self.error(node, &message)
+        }
+
+        fn machine(&self, root: Node, name: Option<Ident>) -> Result<String> {
+            if root.tag_name().name() != "scxml" {
+                return Err(self.error(root, "Expected a <scxml> document"));
+            }
+            let name =
+                match (name, root.attribute("name")) {
+                    (Some(name), _) => name.to_string(),
+                    (None, Some(name)) => self.rust_name(root, name)?,
+                    (None, None) => return Err(self.error(
+                        root,
+                        "The state machine needs a name. Add it to the macro or as name attribute",
+                    )),
+                };
+            for child in root.children().filter(Node::is_element) {
+                match child.tag_name().name() {
+                    "state" | "parallel" | "final" => {}
+                    _ => return Err(self.unsupported(child)),
+                }
+            }
+            Ok(format!("{}, {}", name, self.region(root)?))
+        }
+
+        /// Renders the child states of a node in the form of Init, [Foo, Bar], [Foo => Bar]
+        fn region(&self, node: Node) -> Result<String> {
+            let states: Vec<Node> = node
+                .children()
+                .filter(|child| matches!(child.tag_name().name(), "state" | "parallel" | "final"))
+                .collect();
+            let first = states
+                .first()
+                .ok_or_else(|| self.error(node, "Expected at least one state"))?;
+
+            let init = match (node.attribute("initial"), self.initial_element(node)?) {
+                (Some(init), _) | (None, Some(init)) => init,
+                (None, None) => self.id(*first)?,
+            };
+
+            let mut definitions = vec![];
+            let mut transitions = vec![];
+            for state in states.iter() {
+                definitions.push(self.state(*state)?);
+                for transition in state
+                    .children()
+                    .filter(|child| child.tag_name().name() == "transition")
+                {
+                    transitions.push(self.transition(*state, transition)?);
+                }
+            }
+
+            Ok(format!(
+                "{}, [{}], [{}]",
+                self.rust_name(node, init)?,
+                definitions.join(", "),
+                transitions.join(", ")
+            ))
+        }
+
+        /// Finds the target of the transition in the <initial> element of a state
+        fn initial_element<'b>(&self, node: Node<'b, '_>) -> Result<Option<&'b str>> {
+            let initial = match node
+                .children()
+                .find(|child| child.tag_name().name() == "initial")
+            {
+                Some(initial) => initial,
+                None => return Ok(None),
+            };
+            let mut transitions = initial.children().filter(Node::is_element);
+            match (transitions.next(), transitions.next()) {
+                (Some(transition), None) if transition.tag_name().name() == "transition" => {
+                    transition
+                        .attribute("target")
+                        .map(Some)
+                        .ok_or_else(|| self.error(transition, "Expected a target"))
+                }
+                _ => Err(self.error(
+                    initial,
+                    "The <initial> element must contain exactly one <transition>",
+                )),
+            }
+        }
+
+        /// Renders a state with its options and regions like #[sfsm(final)] Foo or
+        /// Foo { Bar, [Bar], [] }
+        fn state(&self, node: Node) -> Result<String> {
+            let name = self.rust_name(node, self.id(node)?)?;
+            let kind = node.tag_name().name();
+
+            if kind == "final" {
+                if let Some(child) = node.children().find(Node::is_element) {
+                    return Err(self.unsupported(child));
+                }
+                return Ok(format!("#[sfsm(final)] {}", name));
+            }
+
+            let mut history = None;
+            let mut regions = vec![];
+            for child in node.children().filter(Node::is_element) {
+                match (kind, child.tag_name().name()) {
+                    (_, "transition") => {}
+                    (_, "history") => history = Some(self.history(child)?),
+                    ("state", "initial") => {}
+                    ("state", "state") | ("state", "parallel") | ("state", "final") => {}
+                    ("parallel", "state") => regions.push(self.parallel_region(child)?),
+                    _ => return Err(self.unsupported(child)),
+                }
+            }
+            if kind == "state"
+                && node
+                    .children()
+                    .any(|child| matches!(child.tag_name().name(), "state" | "parallel" | "final"))
+            {
+                regions.push(self.region(node)?);
+            }
+            if regions.is_empty() {
+                if history.is_some() {
+                    return Err(self.error(node, "Only a composite state can have a history"));
+                }
+                return Ok(name);
+            }
+
+            let history = history
+                .map(|history| format!("#[sfsm(history = {})] ", history))
+                .unwrap_or_default();
+            Ok(format!(
+                "{}{} {{ {} }}",
+                history,
+                name,
+                regions.join(" || ")
+            ))
+        }
+
+        /// Renders a child state of a parallel state as a region. Its id is only used to
+        /// group the sub-states and is not part of the state machine.
+        fn parallel_region(&self, node: Node) -> Result<String> {
+            for child in node.children().filter(Node::is_element) {
+                match child.tag_name().name() {
+                    "state" | "parallel" | "final" | "initial" => {}
+                    "transition" | "history" => {
+                        return Err(self.error(
+                            child,
+                            "The regions of a parallel state cannot have transitions or a history",
+                        ))
+                    }
+                    _ => return Err(self.unsupported(child)),
+                }
+            }
+            self.region(node)
+        }
+
+        fn history(&self, node: Node) -> Result<&'static str> {
+            if node.children().any(|child| child.is_element()) {
+                return Err(self.error(
+                    node,
+                    "A history cannot have a default transition. The composite state starts with its initial states",
+                ));
+            }
+            match node.attribute("type") {
+                None | Some("shallow") => Ok("shallow"),
+                Some("deep") => Ok("deep"),
+                Some(kind) => Err(self.error(
+                    node,
+                    &format!("Unknown history type '{}'. Expected shallow or deep", kind),
+                )),
+            }
+        }
+
+        /// Renders a transition like Foo => Bar on Event, Foo => Bar after 500ms or
+        /// Foo => Bar after completion
+        fn transition(&self, state: Node, node: Node) -> Result<String> {
+            if let Some(child) = node.children().find(Node::is_element) {
+                return Err(self.error(
+                    child,
+                    "Transitions cannot contain executable content. Implement it in the action of the transition",
+                ));
+            }
+            for attribute in node.attributes() {
+                match attribute.name() {
+                    "target" | "event" => {}
+                    "cond" => {
+                        return Err(self.error(
+                            node,
+                            "Conditions are not supported. Implement them in the guard of the transition",
+                        ))
+                    }
+                    name => {
+                        return Err(self.error(
+                            node,
+                            &format!("The attribute '{}' is not supported on transitions", name),
+                        ))
+                    }
+                }
+            }
+
+            let src = self.id(state)?;
+            let target = node.attribute("target").ok_or_else(|| {
+                self.error(node, "Transitions without a target are not supported")
+            })?;
+            if target.split_whitespace().count() > 1 {
+                return Err(self.error(node, "Transitions can only have a single target"));
+            }
+            if self.is_history(target) {
+                return Err(self.error(
+                    node,
+                    "Transitions into a history are not supported. The composite state resumes its history when it is entered",
+                ));
+            }
+
+            let trigger = match node.attribute("event") {
+                None => String::new(),
+                Some(event) if event == format!("done.state.{}", src) => {
+                    " after completion".to_string()
+                }
+                Some(event) if event.starts_with("after.") => {
+                    format!(" after {}", &event["after.".len()..])
+                }
+                Some(event) => {
+                    if event.split_whitespace().count() > 1
+                        || event.contains('.')
+                        || event.contains('*')
+                    {
+                        return Err(self.error(
+                            node,
+                            "Events are matched by their type. Descriptors with multiple events, . or * are not supported",
+                        ));
+                    }
+                    format!(" on {}", self.rust_name(node, event)?)
+                }
+            };
+
+            Ok(format!(
+                "{} => {}{}",
+                self.rust_name(state, src)?,
+                self.rust_name(node, target)?,
+                trigger
+            ))
+        }
+
+        fn id<'b>(&self, node: Node<'b, '_>) -> Result<&'b str> {
+            node.attribute("id")
+                .ok_or_else(|| self.error(node, "Expected an id"))
+        }
+
+        fn is_history(&self, id: &str) -> bool {
+            self.document
+                .descendants()
+                .any(|node| node.tag_name().name() == "history" && node.attribute("id") == Some(id))
+        }
+
+        /// Checks that the name can be used as the type of a state or event
+        fn rust_name(&self, node: Node, name: &str) -> Result<String> {
+            syn::parse_str::<syn::Type>(name)
+                .map(|_| name.to_string())
+                .map_err(|_| self.error(node, &format!("'{}' is not a valid Rust type", name)))
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::types::Machine;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// The types that are stored in the states enum of the state machine
+pub fn state_types(machine: &Machine) -> Vec<TokenStream> {
+    machine
+        .states
+        .iter()
+        .map(|state| quote! { ::core::option::Option<#state> })
+        .collect()
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn derive(_field_types: &[TokenStream]) -> TokenStream {
+    quote! {}
+}
+
+/// Derives Serialize and Deserialize for a generated type. The higher-ranked bounds are only
+/// checked once the type is serialized, so the implementations exist if all field types
+/// implement the traits and the type can still be declared if they do not.
+#[cfg(feature = "serde")]
+pub fn derive(field_types: &[TokenStream]) -> TokenStream {
+    let bounds = |bound: &str| {
+        field_types
+            .iter()
+            .map(|field_type| format!("for<'__sfsm> {}: {}", field_type, bound))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    let serialize = bounds("sfsm::serde::Serialize");
+    let deserialize = bounds("sfsm::serde::Deserialize<'de>");
+    quote! {
+        #[derive(sfsm::serde::Serialize, sfsm::serde::Deserialize)]
+        #[serde(crate = "sfsm::serde", bound(serialize = #serialize, deserialize = #deserialize))]
+    }
+}
@@ -0,0 +1,135 @@
+use crate::types::{History, Machine, MessageDir, Messages, State};
+use std::fmt::Write;
+
+/// The text based languages the state diagrams can be written in. Both share most of their
+/// syntax for state diagrams.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Flavor {
+    Mermaid,
+    PlantUml,
+}
+
+/// Renders the states and transitions of the state machine as a state diagram. The initial
+/// states are marked with a transition from [*] and the error state with a description.
+pub fn state_diagram(machine: &Machine, flavor: Flavor) -> String {
+    let mut diagram = String::new();
+    match flavor {
+        Flavor::Mermaid => writeln!(diagram, "stateDiagram-v2").unwrap(),
+        Flavor::PlantUml => writeln!(diagram, "@startuml").unwrap(),
+    }
+    write_region(&mut diagram, &machine.init, &machine.states, 1);
+    if let Some(error_state) = &machine.error_state {
+        writeln!(diagram, "    {} : error state", error_state.id_name()).unwrap();
+    }
+    if flavor == Flavor::PlantUml {
+        writeln!(diagram, "@enduml").unwrap();
+    }
+    diagram
+}
+
+/// Renders the messages that are pushed to or polled from the states. The lines are meant to be
+/// added to the state diagram of the state machine.
+pub fn message_diagram(messages: &Messages, flavor: Flavor) -> String {
+    // Mermaid does not support dashed transitions in state diagrams
+    let arrow = match flavor {
+        Flavor::Mermaid => "-->",
+        Flavor::PlantUml => "-[dashed]->",
+    };
+    let mut diagram = String::new();
+    for state_message in messages.messages.iter() {
+        let state = state_message.state.id_name();
+        let (message, edge) = match &state_message.message {
+            MessageDir::Push(message) => {
+                let edge = format!("{} {} {} : push", message.id_name(), arrow, state);
+                (message, edge)
+            }
+            MessageDir::Poll(message) => {
+                let edge = format!("{} {} {} : poll", state, arrow, message.id_name());
+                (message, edge)
+            }
+        };
+        writeln!(
+            diagram,
+            "    state \"{}\" as {}",
+            message.display_name(),
+            message.id_name()
+        )
+        .unwrap();
+        writeln!(diagram, "    {}", edge).unwrap();
+    }
+    diagram
+}
+
+/// Renders a region with its initial state, its states and the transitions between them.
+/// Composite states are rendered as nested states with their regions separated by --.
+fn write_region(diagram: &mut String, init: &State, states: &[State], depth: usize) {
+    let indent = "    ".repeat(depth);
+
+    // States whose names are no valid identifiers are declared with an alias
+    for state in states.iter() {
+        let history = match state.history {
+            Some(History::Shallow) => " (H)",
+            Some(History::Deep) => " (H*)",
+            None => "",
+        };
+        let label = format!("{}{}", state.display_name(), history);
+        let id = state.id_name().to_string();
+        if label != id {
+            writeln!(diagram, "{}state \"{}\" as {}", indent, label, id).unwrap();
+        }
+    }
+
+    writeln!(diagram, "{}[*] --> {}", indent, init.id_name()).unwrap();
+
+    for state in states.iter().filter(|state| !state.regions.is_empty()) {
+        writeln!(diagram, "{}state {} {{", indent, state.id_name()).unwrap();
+        for (index, region) in state.regions.iter().enumerate() {
+            if index > 0 {
+                writeln!(diagram, "{}    --", indent).unwrap();
+            }
+            write_region(diagram, &region.init, &region.states, depth + 1);
+        }
+        writeln!(diagram, "{}}}", indent).unwrap();
+    }
+
+    for state in states.iter() {
+        let targets = state
+            .transits
+            .iter()
+            .map(|transit| {
+                let label = if transit.completion {
+                    Some("after completion".to_string())
+                } else {
+                    transit
+                        .timeout
+                        .as_ref()
+                        .map(|timeout| format!("after {}", timeout.display_name()))
+                };
+                (&transit.dst, label)
+            })
+            .chain(
+                state
+                    .event_transits
+                    .iter()
+                    .map(|transit| (&transit.dst, Some(transit.event.display_name()))),
+            );
+        for (dst, label) in targets {
+            let label = label
+                .map(|label| format!(" : {}", label))
+                .unwrap_or_default();
+            writeln!(
+                diagram,
+                "{}{} --> {}{}",
+                indent,
+                state.id_name(),
+                dst.id_name(),
+                label
+            )
+            .unwrap();
+        }
+    }
+
+    for state in states.iter().filter(|state| state.is_final) {
+        writeln!(diagram, "{}{} --> [*]", indent, state.id_name()).unwrap();
+    }
+}
@@ -26,21 +26,21 @@ pub fn message(_str: String) -> TokenStream {
 
 #[cfg(feature = "trace")]
 pub fn trace(str: String) -> TokenStream {
-    proc_macro2::TokenStream::from(quote! {
+    quote! {
         __sfsm_trace(#str);
-    })
+    }
 }
 
 #[cfg(feature = "trace-steps")]
 pub fn step(str: String) -> TokenStream {
-    proc_macro2::TokenStream::from(quote! {
+    quote! {
         __sfsm_trace(#str);
-    })
+    }
 }
 
 #[cfg(feature = "trace-messages")]
 pub fn message(str: String) -> TokenStream {
-    proc_macro2::TokenStream::from(quote! {
+    quote! {
         __sfsm_trace(#str);
-    })
+    }
 }
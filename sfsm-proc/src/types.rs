@@ -1,13 +1,19 @@
 use convert_case::{Case, Casing};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{AngleBracketedGenericArguments, Attribute, TypePath, Visibility};
+use std::collections::HashSet;
+use syn::{
+    AngleBracketedGenericArguments, Attribute, GenericParam, Generics, LitInt, Type, TypePath,
+    Visibility, WhereClause,
+};
 
+#[derive(Clone)]
 pub enum Mode {
     NonFallible,
     Fallible,
 }
 
+#[derive(Clone)]
 pub struct TraitDefinitions {
     pub transit_trait: TokenStream,
     pub state_trait: TokenStream,
@@ -15,9 +21,55 @@ pub struct TraitDefinitions {
     pub entry: TokenStream,
     pub execute: TokenStream,
     pub action: TokenStream,
+    pub event_trait: TokenStream,
+    pub error_trait: TokenStream,
+    pub stop: TokenStream,
+}
+
+/// Options of the state machine that can be configured with the #[sfsm(...)] attribute
+#[derive(Clone, Default)]
+pub struct Options {
+    /// Only run the action of the transition that is taken instead of the actions of all
+    /// transitions of the state that is left.
+    pub exclusive_actions: bool,
+    /// The type of the context that is owned by the state machine and passed to all states,
+    /// guards and actions.
+    pub context: Option<Type>,
+    /// The type of the clock that is owned by the state machine and used to time the timeout
+    /// transitions.
+    pub clock: Option<Type>,
+    /// The lints that are not reported for the state machine
+    pub allowed: Vec<Lint>,
+    /// The format version the state machine is persisted with if it can be encoded into a
+    /// buffer and decoded from it
+    pub persist: Option<u8>,
+}
+
+/// Checks of the graph of the state machine that can be allowed with #[sfsm(allow(...))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Lint {
+    /// A state that cannot be reached from the initial state of its region
+    Unreachable,
+    /// The same transition is declared more than once
+    DuplicateTransitions,
+    /// A state that is not final but has no outgoing transitions
+    DeadEnd,
+}
+
+impl Lint {
+    /// The name of the lint as it is used in the attribute
+    pub fn ident(&self) -> Ident {
+        let name = match self {
+            Lint::Unreachable => "unreachable",
+            Lint::DuplicateTransitions => "duplicate_transitions",
+            Lint::DeadEnd => "dead_end",
+        };
+        Ident::new(name, Span::call_site())
+    }
 }
 
 pub struct ErrorType {
+    pub path: TokenStream,
     pub error_name: Ident,
     pub generics: Option<AngleBracketedGenericArguments>,
 }
@@ -26,33 +78,235 @@ pub struct TryMachine {
     pub state_machine: Machine,
 }
 
+#[cfg(feature = "async")]
+pub struct AsyncMachine {
+    pub state_machine: Machine,
+}
+
+#[cfg(feature = "async")]
+pub struct AsyncTryMachine {
+    pub state_machine: Machine,
+}
+
+/// A state machine whose states and transitions are read from a SCXML file
+#[cfg(feature = "scxml")]
+pub struct ScxmlMachine {
+    pub state_machine: Machine,
+    /// The absolute path of the SCXML file that has to be tracked by the compiler
+    pub file: String,
+}
+
 #[derive(Clone)]
 /// Contains all data for the states
 pub struct State {
+    /// The path in front of the name including the trailing ::, like crate::states::. It is
+    /// empty if the name is not qualified.
+    pub path: TokenStream,
     pub name: Ident,
-    pub transits: Vec<State>,
+    pub transits: Vec<Transit>,
     pub generics: Option<AngleBracketedGenericArguments>,
     pub enum_name: Ident,
+    pub event_transits: Vec<EventTransit>,
+    pub regions: Vec<Region>,
+    pub history: Option<History>,
+    /// Set if the state ends the state machine or the region it belongs to
+    pub is_final: bool,
+}
+
+#[derive(Clone, PartialEq)]
+/// Defines how a composite state is resumed when it is entered again
+pub enum History {
+    /// Resumes the last active sub-state of every region. Its own sub-states are entered
+    /// from the start.
+    Shallow,
+    /// Resumes the last active sub-states on all levels
+    Deep,
+}
+
+#[derive(Clone)]
+/// Contains the sub-states of one region of a composite state. All regions of a composite state
+/// are active at the same time.
+pub struct Region {
+    pub init: State,
+    pub states: Vec<State>,
+}
+
+/// Flattens the generic arguments of a type into a string that can be used as part of an
+/// identifier. For example ``` <Foo, Bar<u8>> ``` becomes ``` FooBarU8 ```.
+fn generics_to_string(types: &Option<AngleBracketedGenericArguments>) -> String {
+    if let Some(args) = types {
+        let mut args_string = args.into_token_stream().to_string();
+        args_string = str::replace(args_string.as_str(), "'", "");
+        args_string = str::replace(args_string.as_str(), "<", "");
+        args_string = str::replace(args_string.as_str(), ">", "");
+        args_string = str::replace(args_string.as_str(), "&", "");
+        args_string = str::replace(args_string.as_str(), " ", "");
+        args_string = str::replace(args_string.as_str(), ",", "");
+        args_string = str::replace(args_string.as_str(), "]", "");
+        args_string = str::replace(args_string.as_str(), "[", "");
+        args_string.to_case(Case::Pascal)
+    } else {
+        "".to_string()
+    }
+}
+
+/// Flattens the path in front of a name into a string that can be used as part of an
+/// identifier. For example ``` crate::states:: ``` becomes ``` Crate__States__ ```. The segments
+/// are separated by two underscores, which cannot occur in a Pascal cased segment, so that paths
+/// like ``` foo_bar:: ``` and ``` foo::bar:: ``` are told apart.
+fn path_to_string(path: &TokenStream) -> String {
+    path.to_string()
+        .split("::")
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| format!("{}__", segment.to_case(Case::Pascal)))
+        .collect()
+}
+
+/// Formats a type like it would be written in the code. Other than to_string, no spaces are
+/// put around the path separators and generic brackets.
+fn display_name(tokens: TokenStream) -> String {
+    tokens
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(":: ", "::")
+        .replace(" < ", "<")
+        .replace(" <", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+}
+
+/// Flattens a possibly qualified name with its generic arguments into a string that can be used
+/// as part of an identifier. The path is kept so that equally named types of different modules
+/// can be told apart.
+fn qualified_name(
+    path: &TokenStream,
+    name: &Ident,
+    types: &Option<AngleBracketedGenericArguments>,
+) -> String {
+    format!(
+        "{}{}{}",
+        path_to_string(path),
+        name,
+        generics_to_string(types)
+    )
 }
 
 impl State {
-    pub fn state_to_enum(name: &Ident, types: &Option<AngleBracketedGenericArguments>) -> Ident {
-        let args_string = if let Some(args) = types {
-            let mut args_string = args.into_token_stream().to_string();
-            args_string = str::replace(args_string.as_str(), "'", "");
-            args_string = str::replace(args_string.as_str(), "<", "");
-            args_string = str::replace(args_string.as_str(), ">", "");
-            args_string = str::replace(args_string.as_str(), "&", "");
-            args_string = str::replace(args_string.as_str(), " ", "");
-            args_string = str::replace(args_string.as_str(), ",", "");
-            args_string = str::replace(args_string.as_str(), "]", "");
-            args_string = str::replace(args_string.as_str(), "[", "");
-            args_string.to_case(Case::Pascal)
-        } else {
-            "".to_string()
+    pub fn state_to_enum(
+        path: &TokenStream,
+        name: &Ident,
+        types: &Option<AngleBracketedGenericArguments>,
+    ) -> Ident {
+        Ident::new(
+            format!("{}State", qualified_name(path, name, types)).as_str(),
+            Span::call_site(),
+        )
+    }
+
+    pub fn get_name_type(&self) -> String {
+        let name = &self.name;
+        let generics = &self.generics;
+        quote! {
+            #name #generics
+        }
+        .to_string()
+    }
+
+    /// The full name of the state including its path as it is shown in diagrams
+    pub fn display_name(&self) -> String {
+        let path = &self.path;
+        let name = &self.name;
+        let generics = &self.generics;
+        display_name(quote! { #path #name #generics })
+    }
+
+    /// Checks if both states can name the same type although their paths differ, like
+    /// crate::states::Foo and states::Foo. The shorter path must be the end of the longer one.
+    pub fn same_type(&self, other: &State) -> bool {
+        let segments = |path: &TokenStream| -> Vec<String> {
+            path.to_string()
+                .split("::")
+                .map(|segment| segment.trim().to_string())
+                .filter(|segment| !segment.is_empty())
+                .collect()
+        };
+        let (own, others) = (segments(&self.path), segments(&other.path));
+        let (shorter, longer) = if own.len() <= others.len() {
+            (own, others)
+        } else {
+            (others, own)
+        };
+        self.name == other.name
+            && generics_to_string(&self.generics) == generics_to_string(&other.generics)
+            && longer.ends_with(&shorter)
+    }
+
+    pub fn collect_states(states: &[State], collected: &mut Vec<State>) {
+        for state in states.iter() {
+            collected.push(state.clone());
+            for region in state.regions.iter() {
+                State::collect_states(&region.states, collected);
+            }
+        }
+    }
+
+    /// The entry of the state in the state id enum
+    pub fn id_name(&self) -> Ident {
+        Ident::new(
+            qualified_name(&self.path, &self.name, &self.generics).as_str(),
+            Span::call_site(),
+        )
+    }
+}
+
+#[derive(Clone)]
+/// Contains a transition of a state that is evaluated every step
+pub struct Transit {
+    pub dst: State,
+    pub timeout: Option<Timeout>,
+    /// Set if the transition is only taken once all regions of the composite state have
+    /// reached a final state
+    pub completion: bool,
+}
+
+#[derive(Clone)]
+/// Contains the duration after which a transition is taken once its state has been entered.
+/// The duration is stored as a fraction of a second, for example 500ms as 500 / 1000.
+pub struct Timeout {
+    pub value: LitInt,
+    pub per_second: u64,
+}
+
+impl Timeout {
+    /// The duration as it was written in the definition like 500ms
+    pub fn display_name(&self) -> String {
+        let unit = match self.per_second {
+            1 => "s",
+            1_000 => "ms",
+            _ => "us",
         };
+        format!("{}{}", self.value.base10_digits(), unit)
+    }
+}
+
+#[derive(Clone)]
+/// Contains an event that triggers a transition when it is handled by the state machine
+pub struct Event {
+    pub path: TokenStream,
+    pub name: Ident,
+    pub generics: Option<AngleBracketedGenericArguments>,
+    pub enum_name: Ident,
+}
+
+impl Event {
+    pub fn event_to_enum(
+        path: &TokenStream,
+        name: &Ident,
+        types: &Option<AngleBracketedGenericArguments>,
+    ) -> Ident {
         Ident::new(
-            format!("{}{}State", name, args_string).as_str(),
+            format!("{}Event", qualified_name(path, name, types)).as_str(),
             Span::call_site(),
         )
     }
@@ -65,27 +319,430 @@ impl State {
         }
         .to_string()
     }
+
+    /// The full name of the event including its path as it is shown in diagrams
+    pub fn display_name(&self) -> String {
+        let path = &self.path;
+        let name = &self.name;
+        let generics = &self.generics;
+        display_name(quote! { #path #name #generics })
+    }
+}
+
+#[derive(Clone)]
+/// Contains a transition of a state that is only evaluated when the event is handled
+pub struct EventTransit {
+    pub event: Event,
+    pub dst: State,
 }
 
 /// Contains a transition from one state to another
 pub struct Transition {
     pub src: State,
     pub dst: State,
+    pub event: Option<Event>,
+    pub timeout: Option<Timeout>,
+    pub completion: bool,
 }
 
 // Contains all data required to generate the state machine
+#[derive(Clone)]
 pub struct Machine {
     pub attributes: Vec<Attribute>,
+    pub options: Options,
     pub visibility: Option<Visibility>,
     pub name: Ident,
+    /// The generic parameters and the where clause of the state machine struct
+    pub generics: Generics,
+    /// The generic parameters of the states enum. A composite state only keeps the parameters
+    /// that are used by its sub-states.
+    pub enum_generics: Generics,
     pub init: State,
     pub states: Vec<State>,
     pub enum_name: Ident,
+    pub events: Vec<Event>,
+    pub events_enum_name: Ident,
+    pub state_id_name: Ident,
     pub sfsm_error: TokenStream,
     pub custom_error: Option<TokenStream>,
     pub trait_definitions: TraitDefinitions,
     pub mode: Mode,
+    /// Set if the states are async and the state machine has to be awaited
+    pub asynchronous: bool,
     pub error_state: Option<State>,
+    /// Set if the machine holds the sub-states of a composite state
+    pub parent: Option<State>,
+    /// Set if the last active sub-state must be kept after the composite state is left, either
+    /// due to the history of the composite state or a deep history further up.
+    pub history: Option<History>,
+}
+
+/// Collects the names of all identifiers and lifetimes in the tokens
+fn collect_idents(tokens: TokenStream, idents: &mut HashSet<String>) {
+    for token in tokens {
+        match token {
+            proc_macro2::TokenTree::Ident(ident) => {
+                idents.insert(ident.to_string());
+            }
+            proc_macro2::TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            _ => {}
+        }
+    }
+}
+
+fn generic_param_name(param: &GenericParam) -> String {
+    match param {
+        GenericParam::Type(param) => param.ident.to_string(),
+        GenericParam::Lifetime(param) => param.lifetime.ident.to_string(),
+        GenericParam::Const(param) => param.ident.to_string(),
+    }
+}
+
+impl Machine {
+    /// The type of the state machine struct including its generic arguments
+    pub fn machine_type(&self) -> TokenStream {
+        let name = &self.name;
+        let (_, type_generics, _) = self.generics.split_for_impl();
+        quote! { #name #type_generics }
+    }
+
+    /// The type of the states enum including its generic arguments
+    pub fn enum_type(&self) -> TokenStream {
+        let enum_name = &self.enum_name;
+        let (_, type_generics, _) = self.enum_generics.split_for_impl();
+        quote! { #enum_name #type_generics }
+    }
+
+    /// Keeps the generic parameters that are used by the tokens together with the where
+    /// predicates that only refer to kept parameters
+    fn generics_used_by(&self, tokens: TokenStream) -> Generics {
+        let mut used = HashSet::new();
+        collect_idents(tokens, &mut used);
+        let declared: HashSet<String> = self
+            .generics
+            .params
+            .iter()
+            .map(generic_param_name)
+            .collect();
+        let params = self
+            .generics
+            .params
+            .iter()
+            .filter(|param| used.contains(&generic_param_name(param)))
+            .cloned()
+            .collect();
+        let where_clause = self
+            .generics
+            .where_clause
+            .as_ref()
+            .map(|where_clause| WhereClause {
+                where_token: where_clause.where_token,
+                predicates: where_clause
+                    .predicates
+                    .iter()
+                    .filter(|predicate| {
+                        let mut mentioned = HashSet::new();
+                        collect_idents(predicate.to_token_stream(), &mut mentioned);
+                        mentioned
+                            .iter()
+                            .filter(|ident| declared.contains(*ident))
+                            .all(|ident| used.contains(ident))
+                    })
+                    .cloned()
+                    .collect(),
+            });
+        Generics {
+            params,
+            where_clause,
+            ..self.generics.clone()
+        }
+    }
+
+    /// Nested functions cannot use the generic parameters of the surrounding impl block. The
+    /// functions that run a state declare them again and receive a marker of the state machine
+    /// type to infer them.
+    pub fn fn_generics(&self) -> (TokenStream, TokenStream) {
+        let (impl_generics, _, where_clause) = self.generics.split_for_impl();
+        (quote! { #impl_generics }, quote! { #where_clause })
+    }
+
+    /// Additional argument to pass the context into a call of a state, guard or action
+    pub fn context_arg(&self) -> TokenStream {
+        if self.options.context.is_some() {
+            quote! { , context }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Additional parameter of a function that has to forward the context
+    pub fn context_param(&self) -> TokenStream {
+        if let Some(context) = &self.options.context {
+            quote! { , context: &mut #context }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// The async keyword of the generated functions if the state machine is async
+    pub fn asyncness(&self) -> TokenStream {
+        if self.asynchronous {
+            quote! { async }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Awaits a call into a state or a generated function if the state machine is async
+    pub fn awaiting(&self) -> TokenStream {
+        if self.asynchronous {
+            quote! { .await }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Additional parameter of a function that has to forward the clock
+    pub fn clock_param(&self) -> TokenStream {
+        if let Some(clock) = &self.options.clock {
+            quote! { , clock: &#clock }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Additional parameters of a function that enters or steps the sub-states of a composite
+    /// state. The context, the clock, the counter of the taken transitions and the first taken
+    /// transition are forwarded.
+    pub fn forward_params(&self) -> TokenStream {
+        let context_param = self.context_param();
+        let clock_param = self.clock_param();
+        let state_id = &self.state_id_name;
+        quote! {
+            #context_param #clock_param , transitions: &mut usize, transition: &mut ::core::option::Option<(#state_id, #state_id)>
+        }
+    }
+
+    /// Additional arguments to forward the context, the clock and the taken transitions
+    pub fn forward_args(&self) -> TokenStream {
+        let context_arg = self.context_arg();
+        if self.options.clock.is_some() {
+            quote! { #context_arg, clock, transitions, transition }
+        } else {
+            quote! { #context_arg, transitions, transition }
+        }
+    }
+
+    /// Additional parameters of a function that runs a state. Next to the context, the clock
+    /// and the taken transitions, the time the active state was entered and the sub-states of
+    /// all composite states are forwarded.
+    pub fn scope_params(&self) -> TokenStream {
+        let forward_params = self.forward_params();
+        let entered_at = if self.options.clock.is_some() {
+            quote! { , entered_at: &mut u64 }
+        } else {
+            quote! {}
+        };
+        let sub_machines = self.sub_machines();
+        let fields = sub_machines.iter().map(|(field, _)| field);
+        let types = sub_machines
+            .iter()
+            .map(|(_, sub_machine)| sub_machine.machine_type());
+        let marker = if self.generics.params.is_empty() {
+            quote! {}
+        } else {
+            let machine_type = self.machine_type();
+            quote! { , _: ::core::marker::PhantomData<#machine_type> }
+        };
+        quote! {
+            #forward_params #entered_at #( , #fields: &mut #types )* #marker
+        }
+    }
+
+    /// Additional arguments of a function that runs a state
+    pub fn scope_args(&self) -> TokenStream {
+        let forward_args = self.forward_args();
+        let entered_at = if self.options.clock.is_some() {
+            quote! { , entered_at }
+        } else {
+            quote! {}
+        };
+        let fields = self.sub_machines().into_iter().map(|(field, _)| field);
+        let marker = if self.generics.params.is_empty() {
+            quote! {}
+        } else {
+            quote! { , ::core::marker::PhantomData::<Self> }
+        };
+        quote! {
+            #forward_args #entered_at #( , #fields )* #marker
+        }
+    }
+
+    /// Borrows the context, the clock, the time the active state was entered and the sub-states
+    /// so they can be passed on as scope arguments
+    pub fn borrow_scope(&self) -> TokenStream {
+        // The context, the clock and the transition counter of a composite state are not owned
+        // but already passed in as parameters
+        let borrow_context = if self.options.context.is_some() && self.parent.is_none() {
+            quote! { let context = &mut self.context; }
+        } else {
+            quote! {}
+        };
+        // Every call into the outer machine records its transitions from scratch
+        let state_id = &self.state_id_name;
+        let transitions = if self.parent.is_none() {
+            quote! {
+                let transitions = &mut 0usize;
+                let transition: &mut ::core::option::Option<(#state_id, #state_id)> = &mut None;
+            }
+        } else {
+            quote! {}
+        };
+        // Not every function that borrows the scope enters a state and needs the clock
+        let borrow_clock = match (&self.options.clock, &self.parent) {
+            (Some(_), None) => quote! {
+                #[allow(unused_variables)]
+                let clock = &self.clock;
+                #[allow(unused_variables)]
+                let entered_at = &mut self.entered_at;
+            },
+            (Some(_), Some(_)) => quote! {
+                #[allow(unused_variables)]
+                let entered_at = &mut self.entered_at;
+            },
+            (None, _) => quote! {},
+        };
+        let fields = self.sub_machines().into_iter().map(|(field, _)| field);
+        quote! {
+            #borrow_context
+            #transitions
+            #borrow_clock
+            #( let #fields = &mut self.#fields; )*
+        }
+    }
+
+    /// Records the current time as the time the active state was entered
+    pub fn record_entry(&self) -> TokenStream {
+        if self.options.clock.is_some() {
+            quote! { *entered_at = sfsm::Clock::now(clock); }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Collects the states of the machine and the sub-states of all its composite states
+    pub fn all_states(&self) -> Vec<State> {
+        let mut states = vec![];
+        State::collect_states(&self.states, &mut states);
+        states
+    }
+
+    /// Creates the machines holding the sub-states of all composite states together with the
+    /// name of the field they are stored in
+    pub fn sub_machines(&self) -> Vec<(Ident, Machine)> {
+        self.states
+            .iter()
+            .flat_map(|state| self.sub_machines_of(state))
+            .collect()
+    }
+
+    /// Creates a machine for every region of a composite state. The regions are kept in the
+    /// order of their definition.
+    pub fn sub_machines_of(&self, state: &State) -> Vec<(Ident, Machine)> {
+        let state_name = qualified_name(&state.path, &state.name, &state.generics);
+        // The separators of the path segments are kept, so the fields cannot collide either
+        let field_name = state_name
+            .split("__")
+            .map(|segment| segment.to_case(Case::Snake))
+            .collect::<Vec<String>>()
+            .join("__");
+        // The types of the sub-machines get a reserved prefix, so they cannot clash with the
+        // types of the user. Nested sub-machines already carry it in the name of their parent.
+        let type_prefix = if self.parent.is_some() {
+            self.name.to_string()
+        } else {
+            format!("__Sfsm{}", self.name)
+        };
+        state
+            .regions
+            .iter()
+            .enumerate()
+            .map(|(index, region)| {
+                // Only the regions of a state with multiple regions are numbered
+                let (field, name) = if state.regions.len() == 1 {
+                    (
+                        format!("{}_states", field_name),
+                        format!("{}__{}", type_prefix, state_name),
+                    )
+                } else {
+                    (
+                        format!("{}_region_{}_states", field_name, index),
+                        format!("{}__{}__Region{}", type_prefix, state_name, index),
+                    )
+                };
+                (
+                    Ident::new(field.as_str(), Span::call_site()),
+                    self.sub_machine(state, region, Ident::new(name.as_str(), Span::call_site())),
+                )
+            })
+            .collect()
+    }
+
+    /// Creates the machine holding the sub-states of a region. It shares the definitions of
+    /// the outer machine, but has no error state of its own. Errors are forwarded to the outer
+    /// machine instead.
+    fn sub_machine(&self, state: &State, region: &Region, name: Ident) -> Machine {
+        // The sub-machine only keeps the generic parameters used by its own states, its nested
+        // states and the types it is called with
+        let mut states = vec![];
+        State::collect_states(&region.states, &mut states);
+        let nested_generics = states.iter().map(|state| &state.generics);
+        let parent_generics = &state.generics;
+        let context = &self.options.context;
+        let clock = &self.options.clock;
+        let custom_error = &self.custom_error;
+        let generics = self.generics_used_by(quote! {
+            #parent_generics #( #nested_generics )* #context #clock #custom_error
+        });
+        let region_generics = region.states.iter().map(|state| &state.generics);
+        let enum_generics = self.generics_used_by(quote! { #( #region_generics )* });
+
+        Machine {
+            attributes: self.attributes.clone(),
+            options: self.options.clone(),
+            visibility: self.visibility.clone(),
+            enum_name: Machine::enum_name(&name),
+            name,
+            generics,
+            enum_generics,
+            init: region.init.clone(),
+            states: region.states.clone(),
+            events: self.events.clone(),
+            events_enum_name: self.events_enum_name.clone(),
+            state_id_name: self.state_id_name.clone(),
+            sfsm_error: self.sfsm_error.clone(),
+            custom_error: self.custom_error.clone(),
+            trait_definitions: self.trait_definitions.clone(),
+            mode: self.mode.clone(),
+            asynchronous: self.asynchronous,
+            error_state: None,
+            parent: Some(state.clone()),
+            history: if self.history == Some(History::Deep) {
+                Some(History::Deep)
+            } else {
+                state.history.clone()
+            },
+        }
+    }
+
+    /// Additional generic argument of the transition traits if a context is used
+    pub fn context_generic(&self) -> TokenStream {
+        if let Some(context) = &self.options.context {
+            quote! { , #context }
+        } else {
+            quote! {}
+        }
+    }
 }
 
 // Contains data needed to generate generate a enum entry for a state
@@ -103,6 +760,7 @@ pub struct MatchStateEntry {
 // The actual message containing the struct name and optional generics arguments
 pub struct Message {
     pub generics: Option<AngleBracketedGenericArguments>,
+    pub path: TokenStream,
     pub name: Ident,
 }
 
@@ -115,6 +773,22 @@ impl Message {
         }
         .to_string()
     }
+
+    /// The full name of the message including its path as it is shown in diagrams
+    pub fn display_name(&self) -> String {
+        let path = &self.path;
+        let name = &self.name;
+        let generics = &self.generics;
+        display_name(quote! { #path #name #generics })
+    }
+
+    /// The identifier of the message in diagrams that do not allow paths and generics in names
+    pub fn id_name(&self) -> String {
+        format!(
+            "{}Message",
+            qualified_name(&self.path, &self.name, &self.generics)
+        )
+    }
 }
 
 // Enum containing the direction of the message. Can be either a push or poll message
@@ -133,6 +807,8 @@ pub struct StateMessage {
 // The whole message that will be used to generate the macro outputs
 pub struct Messages {
     pub name: Ident,
+    /// The generic parameters of the state machine the messages are added to
+    pub generics: Generics,
     pub enum_name: Ident,
     pub messages: Vec<StateMessage>,
 }
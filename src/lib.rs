@@ -5,3 +5,7 @@ extern crate sfsm_base;
 
 pub use sfsm_proc::*;
 pub use sfsm_base::*;
+
+// The generated state machines derive the serde traits through this path
+#[cfg(feature = "serde")]
+pub use serde;